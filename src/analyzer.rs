@@ -1,8 +1,12 @@
 use std::collections::BTreeMap;
+use std::str::FromStr;
 use crate::candle::Candle;
+use crate::filters::{FilteredResult, SignalFilter, SignalFilterPipeline};
+use crate::levels::Level;
 use crate::patterns::Pattern;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PatternType {
     CloseRetest,
     LongRetest,
@@ -10,9 +14,136 @@ pub enum PatternType {
     AtrSpike,
     Hammer,
     SmallBarApproach,
+    FalseBreakout,
+    Breakout,
+    BreakoutRetest,
+    Engulfing,
+    Harami,
+    Doji,
+    DragonflyDoji,
+    GravestoneDoji,
+    LongLeggedDoji,
+    ShootingStar,
+    InvertedHammer,
+    HangingMan,
+    PiercingLine,
+    DarkCloudCover,
+    MorningStar,
+    EveningStar,
+    ThreeWhiteSoldiers,
+    ThreeBlackCrows,
+    TweezerTop,
+    TweezerBottom,
+    InsideBar,
+    OutsideBar,
+    Marubozu,
+}
+
+impl PatternType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatternType::CloseRetest => "CloseRetest",
+            PatternType::LongRetest => "LongRetest",
+            PatternType::PressureBuildup => "PressureBuildup",
+            PatternType::AtrSpike => "AtrSpike",
+            PatternType::Hammer => "Hammer",
+            PatternType::SmallBarApproach => "SmallBarApproach",
+            PatternType::FalseBreakout => "FalseBreakout",
+            PatternType::Breakout => "Breakout",
+            PatternType::BreakoutRetest => "BreakoutRetest",
+            PatternType::Engulfing => "Engulfing",
+            PatternType::Harami => "Harami",
+            PatternType::Doji => "Doji",
+            PatternType::DragonflyDoji => "DragonflyDoji",
+            PatternType::GravestoneDoji => "GravestoneDoji",
+            PatternType::LongLeggedDoji => "LongLeggedDoji",
+            PatternType::ShootingStar => "ShootingStar",
+            PatternType::InvertedHammer => "InvertedHammer",
+            PatternType::HangingMan => "HangingMan",
+            PatternType::PiercingLine => "PiercingLine",
+            PatternType::DarkCloudCover => "DarkCloudCover",
+            PatternType::MorningStar => "MorningStar",
+            PatternType::EveningStar => "EveningStar",
+            PatternType::ThreeWhiteSoldiers => "ThreeWhiteSoldiers",
+            PatternType::ThreeBlackCrows => "ThreeBlackCrows",
+            PatternType::TweezerTop => "TweezerTop",
+            PatternType::TweezerBottom => "TweezerBottom",
+            PatternType::InsideBar => "InsideBar",
+            PatternType::OutsideBar => "OutsideBar",
+            PatternType::Marubozu => "Marubozu",
+        }
+    }
+}
+
+impl FromStr for PatternType {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "CloseRetest" => Ok(PatternType::CloseRetest),
+            "LongRetest" => Ok(PatternType::LongRetest),
+            "PressureBuildup" => Ok(PatternType::PressureBuildup),
+            "AtrSpike" => Ok(PatternType::AtrSpike),
+            "Hammer" => Ok(PatternType::Hammer),
+            "SmallBarApproach" => Ok(PatternType::SmallBarApproach),
+            "FalseBreakout" => Ok(PatternType::FalseBreakout),
+            "Breakout" => Ok(PatternType::Breakout),
+            "BreakoutRetest" => Ok(PatternType::BreakoutRetest),
+            "Engulfing" => Ok(PatternType::Engulfing),
+            "Harami" => Ok(PatternType::Harami),
+            "Doji" => Ok(PatternType::Doji),
+            "DragonflyDoji" => Ok(PatternType::DragonflyDoji),
+            "GravestoneDoji" => Ok(PatternType::GravestoneDoji),
+            "LongLeggedDoji" => Ok(PatternType::LongLeggedDoji),
+            "ShootingStar" => Ok(PatternType::ShootingStar),
+            "InvertedHammer" => Ok(PatternType::InvertedHammer),
+            "HangingMan" => Ok(PatternType::HangingMan),
+            "PiercingLine" => Ok(PatternType::PiercingLine),
+            "DarkCloudCover" => Ok(PatternType::DarkCloudCover),
+            "MorningStar" => Ok(PatternType::MorningStar),
+            "EveningStar" => Ok(PatternType::EveningStar),
+            "ThreeWhiteSoldiers" => Ok(PatternType::ThreeWhiteSoldiers),
+            "ThreeBlackCrows" => Ok(PatternType::ThreeBlackCrows),
+            "TweezerTop" => Ok(PatternType::TweezerTop),
+            "TweezerBottom" => Ok(PatternType::TweezerBottom),
+            "InsideBar" => Ok(PatternType::InsideBar),
+            "OutsideBar" => Ok(PatternType::OutsideBar),
+            "Marubozu" => Ok(PatternType::Marubozu),
+            _ => Err(format!("Unknown pattern type: {}", src)),
+        }
+    }
 }
 
+/// Single pattern match.
+///
+/// With the `serde` feature the result is serialized to JSON as:
+///
+/// ```json
+/// {
+///   "name": "Hammer",
+///   "direction": "Bullish",
+///   "description": "Hammer candle at level 100",
+///   "confidence": 0.8,
+///   "pattern_type": "Hammer",
+///   "evidence": {
+///     "trigger_time_key": 202401021430,
+///     "time_keys": [202401021430],
+///     "level": 100.0,
+///     "tolerance_band": { "lower": 99.5, "upper": 100.5 },
+///     "measurements": { "body": 0.2, "lower_wick": 1.1, "upper_wick": 0.05 }
+///   }
+/// }
+/// ```
+///
+/// - `direction` is one of `"Bullish"`, `"Bearish"`, `"Neutral"`
+/// - `pattern_type` is the [`PatternType`] variant name
+/// - `confidence` is a number or `null`
+/// - `evidence.level` and `evidence.tolerance_band` are `null` when the pattern does not use them
+/// - `evidence.measurements` keys are sorted
+///
+/// Field and variant names are part of the public schema. They are only renamed together with a major version bump.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PatternResult {
     pub name: String,
     pub direction: SignalDirection,
@@ -28,24 +159,134 @@ pub struct PatternResult {
     /// `None` means confidence is not applicable or not calculated for that pattern.
     pub confidence: Option<f64>,
     pub pattern_type: PatternType,
+    pub evidence: PatternEvidence,
+}
+
+/// Price range around a level which counts as touching it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceBand {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl PriceBand {
+    pub fn new(lower: f64, upper: f64) -> Self {
+        Self { lower, upper }
+    }
+
+    pub fn contains(&self, price: f64) -> bool {
+        price >= self.lower && price <= self.upper
+    }
+}
+
+/// Candles and prices a pattern match was built from.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternEvidence {
+    /// Time key of the candle which completed the pattern
+    pub trigger_time_key: u64,
+    /// Time keys of all candles the pattern looked at, oldest first
+    pub time_keys: Vec<u64>,
+    /// Level price. `None` for patterns which do not use levels
+    pub level: Option<f64>,
+    /// Band around the level the pattern accepted as a touch
+    pub tolerance_band: Option<PriceBand>,
+    /// Pattern-specific values, e.g. `bump_count` for retests or `range_atr_ratio` for ATR spikes
+    pub measurements: BTreeMap<String, f64>,
+}
+
+impl PatternEvidence {
+    pub fn new(trigger_time_key: u64, time_keys: Vec<u64>) -> Self {
+        Self {
+            trigger_time_key,
+            time_keys,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_level(mut self, level: f64) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    pub fn with_tolerance_band(mut self, lower: f64, upper: f64) -> Self {
+        self.tolerance_band = Some(PriceBand::new(lower, upper));
+        self
+    }
+
+    pub fn with_measurement(mut self, name: &str, value: f64) -> Self {
+        self.measurements.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn get_measurement(&self, name: &str) -> Option<f64> {
+        self.measurements.get(name).copied()
+    }
+}
+
+/// Pattern result found by a historical scan.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternMatch {
+    /// Time key of the last candle the pattern was evaluated on
+    pub time_key: u64,
+    pub result: PatternResult,
+}
+
+/// Pattern result produced by multi-level analysis.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelPatternResult {
+    /// Level the result belongs to. `None` for patterns which do not use levels
+    pub level: Option<Level>,
+    pub result: PatternResult,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SignalDirection {
     Bullish,
     Bearish,
     Neutral,
 }
 
+impl SignalDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignalDirection::Bullish => "Bullish",
+            SignalDirection::Bearish => "Bearish",
+            SignalDirection::Neutral => "Neutral",
+        }
+    }
+}
+
+impl FromStr for SignalDirection {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "Bullish" => Ok(SignalDirection::Bullish),
+            "Bearish" => Ok(SignalDirection::Bearish),
+            "Neutral" => Ok(SignalDirection::Neutral),
+            _ => Err(format!("Unknown signal direction: {}", src)),
+        }
+    }
+}
+
+/// Runs registered patterns and passes their results through the filter pipeline.
+///
+/// Without filters every raw match is returned. The analyzer keeps no state: signals which
+/// passed the filters are added to the `accepted` list of the caller, e.g. one list per symbol,
+/// so filters like [`crate::filters::SignalSpacingFilter`] see them on the next calls.
 pub struct CandleAnalyzer<TCandle: Candle> {
     patterns: Vec<Box<dyn Pattern<TCandle>>>,
+    filters: SignalFilterPipeline<TCandle>,
 }
 
 impl<TCandle: Candle> Default for CandleAnalyzer<TCandle> {
     fn default() -> Self {
-        Self {
-            patterns: Vec::new(),
-        }
+        Self::new(Vec::new())
     }
 }
 
@@ -53,6 +294,7 @@ impl<TCandle: Candle> CandleAnalyzer<TCandle> {
     pub fn new(patterns: Vec<Box<dyn Pattern<TCandle>>>) -> Self {
         Self {
             patterns,
+            filters: SignalFilterPipeline::default(),
         }
     }
 
@@ -60,10 +302,643 @@ impl<TCandle: Candle> CandleAnalyzer<TCandle> {
         self.patterns.push(Box::new(pattern));
     }
 
-    pub fn analyze(&self, candles: &BTreeMap<u64, TCandle>, level: f64,) -> Vec<PatternResult> {
+    pub fn register_filter<F: SignalFilter<TCandle> + 'static>(&mut self, filter: F) {
+        self.filters.register_filter(filter);
+    }
+
+    /// Number of most recent candles the patterns and filters look at. `None` means the whole history is needed
+    pub fn get_lookback(&self) -> Option<usize> {
+        let filters = self.filters.get_lookback()?;
+
+        self.patterns
+            .iter()
+            .try_fold(filters, |result, pattern| Some(result.max(pattern.lookback()?)))
+    }
+
+    /// Results which passed all filters
+    pub fn analyze(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        level: f64,
+        accepted: &mut Vec<FilteredResult>,
+    ) -> Vec<PatternResult> {
+        self.analyze_audited(candles, level, accepted)
+            .into_iter()
+            .filter(|r| r.is_passed())
+            .map(|r| r.result)
+            .collect()
+    }
+
+    /// Every raw match with the outcome of each filter, including the suppressed ones
+    pub fn analyze_audited(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        level: f64,
+        accepted: &mut Vec<FilteredResult>,
+    ) -> Vec<FilteredResult> {
         self.patterns
             .iter()
             .filter_map(|p| p.matches(candles, level))
+            .map(|r| self.filters.apply_and_accept(candles, r, accepted))
             .collect()
     }
+
+    /// Evaluates the patterns at every candle of the history, oldest first, and filters the results.
+    ///
+    /// Each evaluation sees only the candles up to the evaluation point. Filters get the signals
+    /// accepted earlier in the scan, so [`crate::filters::SignalSpacingFilter`] works across candles.
+    /// Suppressed results are returned as well, check [`FilteredResult::is_passed`].
+    pub fn scan(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Vec<FilteredResult>
+    where
+        TCandle: Clone,
+    {
+        let mut history = BTreeMap::new();
+        let mut accepted: Vec<FilteredResult> = Vec::new();
+        let mut result = Vec::new();
+
+        for (time_key, candle) in candles {
+            history.insert(*time_key, candle.clone());
+
+            for pattern in self.patterns.iter() {
+                let Some(pattern_result) = pattern.matches(&history, level) else {
+                    continue;
+                };
+
+                let filtered =
+                    self.filters
+                        .apply_and_accept(&history, pattern_result, &mut accepted);
+                result.push(filtered);
+            }
+        }
+
+        result
+    }
+
+    /// Evaluates every pattern against every level in one pass.
+    ///
+    /// Patterns which do not use levels are evaluated once and their results are not tagged with a level.
+    /// Results suppressed by the filters are skipped.
+    pub fn analyze_levels(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        levels: &[Level],
+        accepted: &mut Vec<FilteredResult>,
+    ) -> Vec<LevelPatternResult> {
+        let mut result = Vec::new();
+
+        for pattern in self.patterns.iter() {
+            if !pattern.is_level_based() {
+                let level = levels.first().map(|l| l.price).unwrap_or_default();
+                if let Some(pattern_result) =
+                    self.find_passed(pattern.as_ref(), candles, level, accepted)
+                {
+                    result.push(LevelPatternResult {
+                        level: None,
+                        result: pattern_result,
+                    });
+                }
+                continue;
+            }
+
+            for level in levels {
+                if let Some(pattern_result) =
+                    self.find_passed(pattern.as_ref(), candles, level.price, accepted)
+                {
+                    result.push(LevelPatternResult {
+                        level: Some(level.clone()),
+                        result: pattern_result,
+                    });
+                }
+            }
+        }
+
+        result
+    }
+
+    fn find_passed(
+        &self,
+        pattern: &dyn Pattern<TCandle>,
+        candles: &BTreeMap<u64, TCandle>,
+        level: f64,
+        accepted: &mut Vec<FilteredResult>,
+    ) -> Option<PatternResult> {
+        let pattern_result = pattern.matches(candles, level)?;
+
+        if self.filters.is_empty() {
+            return Some(pattern_result);
+        }
+
+        let filtered = self.filters.apply_and_accept(candles, pattern_result, accepted);
+        filtered.is_passed().then_some(filtered.result)
+    }
+}
+
+/// Stateful counterpart of [`CandleAnalyzer`] for live feeds.
+///
+/// Candles are pushed one at a time and only the candles the registered patterns and filters
+/// look back at are kept. Every call returns the same results `CandleAnalyzer::analyze`
+/// with the same filters would return for the history seen so far, minus the ones already
+/// emitted for the current last candle.
+pub struct StreamingCandleAnalyzer<TCandle: Candle> {
+    patterns: Vec<Box<dyn Pattern<TCandle>>>,
+    filters: SignalFilterPipeline<TCandle>,
+    /// Signals which passed the filters, oldest first
+    accepted: Vec<FilteredResult>,
+    candles: BTreeMap<u64, TCandle>,
+    max_lookback: Option<usize>,
+    /// Pattern type and direction of the result each pattern emitted for the last candle
+    emitted: Vec<Option<(PatternType, SignalDirection)>>,
+    /// Level of the last evaluation. Results emitted for another level are emitted again
+    last_level: Option<f64>,
+}
+
+impl<TCandle: Candle> StreamingCandleAnalyzer<TCandle> {
+    pub fn new(patterns: Vec<Box<dyn Pattern<TCandle>>>) -> Self {
+        let mut result = Self {
+            patterns: Vec::new(),
+            filters: SignalFilterPipeline::default(),
+            accepted: Vec::new(),
+            candles: BTreeMap::new(),
+            max_lookback: Some(0),
+            emitted: Vec::new(),
+            last_level: None,
+        };
+
+        for pattern in patterns {
+            result.add_pattern(pattern);
+        }
+
+        result
+    }
+
+    pub fn register_pattern<P: Pattern<TCandle> + 'static>(&mut self, pattern: P) {
+        self.add_pattern(Box::new(pattern));
+    }
+
+    pub fn register_filter<F: SignalFilter<TCandle> + 'static>(&mut self, filter: F) {
+        self.add_lookback(filter.lookback());
+        self.filters.register_filter(filter);
+    }
+
+    fn add_pattern(&mut self, pattern: Box<dyn Pattern<TCandle>>) {
+        self.add_lookback(pattern.lookback());
+        self.patterns.push(pattern);
+        self.emitted.push(None);
+    }
+
+    fn add_lookback(&mut self, lookback: Option<usize>) {
+        self.max_lookback = match (self.max_lookback, lookback) {
+            (Some(current), Some(lookback)) => Some(current.max(lookback)),
+            _ => None,
+        };
+    }
+
+    pub fn get_candles(&self) -> &BTreeMap<u64, TCandle> {
+        &self.candles
+    }
+
+    /// Appends a new candle and returns the patterns matching on it.
+    ///
+    /// A candle with the same time key as the last one is handled as [`Self::update_last`].
+    /// Candles older than the last one are ignored.
+    pub fn push(&mut self, candle: TCandle, level: f64) -> Vec<PatternResult> {
+        let time_key = candle.get_time_key();
+
+        if let Some((last_key, _)) = self.candles.last_key_value() {
+            if time_key == *last_key {
+                return self.update_last(candle, level);
+            }
+
+            if time_key < *last_key {
+                return Vec::new();
+            }
+        }
+
+        self.candles.insert(time_key, candle);
+        self.trim();
+
+        for emitted in self.emitted.iter_mut() {
+            *emitted = None;
+        }
+
+        self.evaluate(level)
+    }
+
+    /// Replaces the last (still forming) candle and returns only the results
+    /// which were not emitted for this candle yet.
+    pub fn update_last(&mut self, candle: TCandle, level: f64) -> Vec<PatternResult> {
+        let time_key = candle.get_time_key();
+
+        match self.candles.last_key_value() {
+            Some((last_key, _)) if *last_key == time_key => {
+                self.candles.insert(time_key, candle);
+                self.evaluate(level)
+            }
+            _ => self.push(candle, level),
+        }
+    }
+
+    fn evaluate(&mut self, level: f64) -> Vec<PatternResult> {
+        if self.last_level != Some(level) {
+            for emitted in self.emitted.iter_mut() {
+                *emitted = None;
+            }
+            self.last_level = Some(level);
+        }
+
+        let mut result = Vec::new();
+
+        for (pattern, emitted) in self.patterns.iter().zip(self.emitted.iter_mut()) {
+            let Some(pattern_result) = pattern.matches(&self.candles, level) else {
+                continue;
+            };
+
+            let pattern_result = if self.filters.is_empty() {
+                pattern_result
+            } else {
+                let filtered =
+                    self.filters
+                        .apply_and_accept(&self.candles, pattern_result, &mut self.accepted);
+                if !filtered.is_passed() {
+                    continue;
+                }
+                filtered.result
+            };
+
+            let key = (
+                pattern_result.pattern_type.clone(),
+                pattern_result.direction.clone(),
+            );
+            if emitted.as_ref() == Some(&key) {
+                continue;
+            }
+
+            *emitted = Some(key);
+            result.push(pattern_result);
+        }
+
+        result
+    }
+
+    fn trim(&mut self) {
+        let Some(max_lookback) = self.max_lookback else {
+            return;
+        };
+
+        while self.candles.len() > max_lookback.max(1) {
+            self.candles.pop_first();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::filters::{MinConfidenceFilter, SignalSpacingFilter};
+    use crate::patterns::{AtrSpike, Hammer, PressureBuildupPattern, RetestPattern, SmallBarApproach};
+    use crate::test_candles;
+
+    fn make_patterns() -> Vec<Box<dyn Pattern<CandleInstance>>> {
+        vec![
+            Box::new(AtrSpike {
+                period: 3,
+                multiplier: 1.5,
+                atr: None,
+            }),
+            Box::new(Hammer {
+                atr_period: 3,
+                trend_period: 2,
+                ..Hammer::default()
+            }),
+            Box::new(PressureBuildupPattern::default()),
+            Box::new(SmallBarApproach {
+                period: 3,
+                tuning_factor: 1.0,
+                direction: None,
+            }),
+        ]
+    }
+
+    fn make_candles() -> Vec<CandleInstance> {
+        let prices = [
+            (5.0, 7.0, 4.0, 6.0),
+            (4.0, 6.0, 3.0, 5.0),
+            (5.0, 7.0, 4.0, 6.0),
+            (5.0, 7.0, 4.0, 6.0),
+            (6.0, 6.05, 2.0, 5.9),
+            (5.9, 7.0, 5.5, 6.8),
+            (6.8, 12.0, 6.0, 11.0),
+            (6.6, 7.0, 6.4, 6.7),
+            (6.7, 7.0, 6.5, 6.8),
+            (6.8, 7.0, 6.6, 6.9),
+        ];
+
+        test_candles::make_candles(&prices).into_values().collect()
+    }
+
+    fn to_keys(results: &[PatternResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|r| format!("{:?} {:?}", r.pattern_type, r.direction))
+            .collect()
+    }
+
+    #[test]
+    fn streaming_gives_same_results_as_batch() {
+        let batch = CandleAnalyzer::new(make_patterns());
+        let mut streaming = StreamingCandleAnalyzer::new(make_patterns());
+        let mut history = BTreeMap::new();
+
+        for candle in make_candles() {
+            history.insert(candle.time_key, candle.clone());
+            let expected = batch.analyze(&history, 7.0, &mut Vec::new());
+            let actual = streaming.push(candle, 7.0);
+
+            assert_eq!(to_keys(&expected), to_keys(&actual));
+        }
+
+        assert_eq!(streaming.get_candles().len(), 5);
+    }
+
+    #[test]
+    fn streaming_filters_give_same_results_as_batch() {
+        let mut batch = CandleAnalyzer::new(make_patterns());
+        batch.register_filter(SignalSpacingFilter::new(3));
+        let mut streaming = StreamingCandleAnalyzer::new(make_patterns());
+        streaming.register_filter(SignalSpacingFilter::new(3));
+        let mut history = BTreeMap::new();
+        let mut accepted = Vec::new();
+        let mut emitted = Vec::new();
+
+        for candle in make_candles() {
+            history.insert(candle.time_key, candle.clone());
+            let expected = batch.analyze(&history, 7.0, &mut accepted);
+            let actual = streaming.push(candle, 7.0);
+
+            assert_eq!(to_keys(&expected), to_keys(&actual));
+            emitted.extend(to_keys(&actual));
+        }
+
+        // Same signals as the scan lets through, the hammer right after the small bar approach is suppressed
+        assert_eq!(emitted, vec!["SmallBarApproach Bullish", "AtrSpike Neutral"]);
+    }
+
+    #[test]
+    fn update_last_emits_only_new_results() {
+        let mut streaming = StreamingCandleAnalyzer::new(make_patterns());
+        let candles = make_candles();
+
+        for candle in candles.iter().take(4) {
+            streaming.push(candle.clone(), 7.0);
+        }
+
+        let mut forming = candles[4].clone();
+        forming.low = 5.5;
+        forming.close = 6.0;
+        assert!(streaming.push(forming.clone(), 7.0).is_empty());
+
+        forming.low = 2.0;
+        forming.close = 5.9;
+        let results = streaming.update_last(forming.clone(), 7.0);
+        assert_eq!(to_keys(&results), vec!["Hammer Bullish"]);
+
+        let results = streaming.update_last(forming, 7.0);
+        assert!(results.is_empty());
+    }
+
+    /// Bullish pattern which is a hammer above close 6.0 and a spike otherwise
+    struct TypeByClose;
+
+    impl Pattern<CandleInstance> for TypeByClose {
+        fn matches(
+            &self,
+            candles: &BTreeMap<u64, CandleInstance>,
+            _level: f64,
+        ) -> Option<PatternResult> {
+            let (time_key, last) = candles.last_key_value()?;
+            let pattern_type = if last.close > 6.0 {
+                PatternType::Hammer
+            } else {
+                PatternType::AtrSpike
+            };
+
+            Some(PatternResult {
+                name: format!("{:?}", pattern_type),
+                direction: SignalDirection::Bullish,
+                description: String::new(),
+                confidence: None,
+                pattern_type,
+                evidence: PatternEvidence::new(*time_key, vec![*time_key]),
+            })
+        }
+
+        fn lookback(&self) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn update_last_emits_other_pattern_type_in_same_direction() {
+        let mut streaming = StreamingCandleAnalyzer::new(vec![
+            Box::new(TypeByClose) as Box<dyn Pattern<CandleInstance>>,
+        ]);
+        let mut forming = make_candles()[0].clone();
+
+        forming.close = 5.0;
+        let results = streaming.push(forming.clone(), 7.0);
+        assert_eq!(to_keys(&results), vec!["AtrSpike Bullish"]);
+
+        forming.close = 6.5;
+        let results = streaming.update_last(forming.clone(), 7.0);
+        assert_eq!(to_keys(&results), vec!["Hammer Bullish"]);
+
+        assert!(streaming.update_last(forming, 7.0).is_empty());
+    }
+
+    /// Pattern without a lookback, so the streaming analyzer has to keep everything
+    struct WholeHistory;
+
+    impl Pattern<CandleInstance> for WholeHistory {
+        fn matches(
+            &self,
+            _candles: &BTreeMap<u64, CandleInstance>,
+            _level: f64,
+        ) -> Option<PatternResult> {
+            None
+        }
+    }
+
+    #[test]
+    fn unbounded_pattern_keeps_whole_history() {
+        let mut streaming = StreamingCandleAnalyzer::new(make_patterns());
+        streaming.register_pattern(WholeHistory);
+
+        for candle in make_candles() {
+            streaming.push(candle, 7.0);
+        }
+
+        assert_eq!(streaming.get_candles().len(), 10);
+    }
+
+    #[test]
+    fn level_change_emits_results_again() {
+        let mut streaming = StreamingCandleAnalyzer::new(vec![
+            Box::new(RetestPattern::default()) as Box<dyn Pattern<CandleInstance>>,
+        ]);
+        let candles = make_candles();
+
+        for candle in candles.iter().take(3) {
+            streaming.push(candle.clone(), 7.0);
+        }
+
+        let results = streaming.push(candles[3].clone(), 7.0);
+        assert_eq!(to_keys(&results), vec!["CloseRetest Bullish"]);
+        assert!(streaming.update_last(candles[3].clone(), 7.0).is_empty());
+
+        let results = streaming.update_last(candles[3].clone(), 7.05);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].evidence.level, Some(7.05));
+    }
+
+    #[test]
+    fn analyze_levels_tags_results_with_level() {
+        let analyzer = CandleAnalyzer::new(vec![
+            Box::new(PressureBuildupPattern::default()),
+            Box::new(AtrSpike {
+                period: 3,
+                multiplier: 1.0,
+                atr: Some(1.0),
+            }),
+        ]);
+
+        let candles: BTreeMap<u64, CandleInstance> = make_candles()
+            .into_iter()
+            .take(4)
+            .map(|mut c| {
+                c.high = 7.0;
+                (c.time_key, c)
+            })
+            .collect();
+
+        let levels = vec![
+            Level::resistance(7.0, 0),
+            Level::support(1.0, 0),
+            Level::resistance(7.05, 0),
+        ];
+
+        let results = analyzer.analyze_levels(&candles, &levels, &mut Vec::new());
+
+        let tagged: Vec<_> = results
+            .iter()
+            .map(|r| (format!("{:?}", r.result.pattern_type), r.level.as_ref().map(|l| l.price)))
+            .collect();
+
+        assert_eq!(
+            tagged,
+            vec![
+                ("PressureBuildup".to_string(), Some(7.0)),
+                ("PressureBuildup".to_string(), Some(7.05)),
+                ("AtrSpike".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_records_suppressed_signals() {
+        let mut analyzer = CandleAnalyzer::new(make_patterns());
+        analyzer.register_filter(SignalSpacingFilter::new(3));
+
+        let candles: BTreeMap<u64, CandleInstance> =
+            make_candles().into_iter().map(|c| (c.time_key, c)).collect();
+
+        let audit: Vec<String> = analyzer
+            .scan(&candles, 7.0)
+            .iter()
+            .map(|r| format!("{} {:?} {}", r.time_key, r.result.pattern_type, r.is_passed()))
+            .collect();
+
+        // Hammer at 4 comes one candle after the small bar approach at 3
+        assert_eq!(
+            audit,
+            vec![
+                "3 SmallBarApproach true",
+                "4 Hammer false",
+                "6 AtrSpike true",
+            ]
+        );
+    }
+
+    #[test]
+    fn analyze_returns_only_passed_results() {
+        let candles: BTreeMap<u64, CandleInstance> = make_candles()
+            .into_iter()
+            .take(5)
+            .map(|c| (c.time_key, c))
+            .collect();
+
+        let mut analyzer = CandleAnalyzer::new(make_patterns());
+        let mut accepted = Vec::new();
+        assert_eq!(
+            to_keys(&analyzer.analyze(&candles, 7.0, &mut accepted)),
+            vec!["Hammer Bullish"]
+        );
+        // Nothing is recorded without filters
+        assert!(accepted.is_empty());
+
+        analyzer.register_filter(MinConfidenceFilter::new(1.1));
+        assert!(analyzer.analyze(&candles, 7.0, &mut accepted).is_empty());
+
+        let audited = analyzer.analyze_audited(&candles, 7.0, &mut accepted);
+        assert_eq!(audited.len(), 1);
+        assert_eq!(audited[0].time_key, 4);
+        assert!(!audited[0].is_passed());
+        assert_eq!(audited[0].checks[0].filter, "MinConfidence");
+        assert!(
+            audited[0].checks[0]
+                .reason
+                .as_ref()
+                .is_some_and(|reason| reason.ends_with("is below 1.10"))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn pattern_result_json_schema() {
+        let result = PatternResult {
+            name: "Hammer".to_string(),
+            direction: SignalDirection::Bullish,
+            description: "Hammer candle".to_string(),
+            confidence: None,
+            pattern_type: PatternType::Hammer,
+            evidence: PatternEvidence::new(5, vec![5])
+                .with_level(100.0)
+                .with_measurement("body", 0.5),
+        };
+
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": "Hammer",
+                "direction": "Bullish",
+                "description": "Hammer candle",
+                "confidence": null,
+                "pattern_type": "Hammer",
+                "evidence": {
+                    "trigger_time_key": 5,
+                    "time_keys": [5],
+                    "level": 100.0,
+                    "tolerance_band": null,
+                    "measurements": { "body": 0.5 }
+                }
+            })
+        );
+
+        let restored: PatternResult = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.pattern_type, PatternType::Hammer);
+        assert_eq!(restored.direction, SignalDirection::Bullish);
+        assert_eq!(restored.evidence, result.evidence);
+    }
 }
@@ -1,4 +1,5 @@
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Atr(f64);
 
 impl Atr {
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+
+use crate::{Atr, candle::Candle};
+
+pub const ATR_DEFAULT_PERIOD: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtrSmoothing {
+    /// Wilder's smoothing (RMA): `atr = (prev_atr * (n - 1) + tr) / n`
+    Wilder,
+    /// Simple moving average of the last `n` true ranges
+    Sma,
+    /// Exponential moving average with `alpha = 2 / (n + 1)`
+    Ema,
+}
+
+/// Average True Range calculator.
+///
+/// True range takes the previous close into account, so gaps between sessions are part of the range.
+#[derive(Debug, Clone)]
+pub struct AtrCalculator {
+    pub period: usize,
+    pub smoothing: AtrSmoothing,
+    /// Candles with a true range above `outlier_multiplier` × median true range are skipped.
+    ///
+    /// `None` means all candles are used.
+    pub outlier_multiplier: Option<f64>,
+}
+
+impl Default for AtrCalculator {
+    fn default() -> Self {
+        Self {
+            period: ATR_DEFAULT_PERIOD,
+            smoothing: AtrSmoothing::Wilder,
+            outlier_multiplier: None,
+        }
+    }
+}
+
+impl AtrCalculator {
+    pub fn new(period: usize, smoothing: AtrSmoothing) -> Self {
+        Self {
+            period,
+            smoothing,
+            outlier_multiplier: None,
+        }
+    }
+
+    /// ATR after the last candle. `None` if there are fewer than `period` usable candles.
+    pub fn calculate<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<Atr> {
+        self.calc_series(candles).into_values().last()
+    }
+
+    /// ATR value after every candle once the first `period` usable candles are collected.
+    ///
+    /// Skipped (outlier) candles keep the previous ATR value.
+    pub fn calc_series<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> BTreeMap<u64, Atr> {
+        let mut result = BTreeMap::new();
+
+        if self.period == 0 {
+            return result;
+        }
+
+        let true_ranges = calc_true_ranges(candles);
+        let max_true_range = self.calc_max_true_range(&true_ranges);
+
+        let mut window: Vec<f64> = Vec::with_capacity(self.period);
+        let mut atr: Option<f64> = None;
+
+        for (time_key, true_range) in true_ranges {
+            if true_range > max_true_range {
+                if let Some(value) = atr {
+                    result.insert(time_key, Atr::new(value));
+                }
+                continue;
+            }
+
+            atr = match atr {
+                None => {
+                    window.push(true_range);
+                    if window.len() < self.period {
+                        continue;
+                    }
+                    Some(window.iter().sum::<f64>() / self.period as f64)
+                }
+                Some(prev) => Some(self.smooth(prev, true_range, &mut window)),
+            };
+
+            if let Some(value) = atr {
+                result.insert(time_key, Atr::new(value));
+            }
+        }
+
+        result
+    }
+
+    /// ATR of the last `period` candles only: the average true range every smoothing starts from.
+    ///
+    /// Reads at most `period + 1` candles, so it is cheap to call on every new candle.
+    /// Outliers are not skipped. `None` if there are fewer than `period` candles.
+    pub fn calc_recent<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<Atr> {
+        let mut recent: Vec<&T> = candles.values().rev().take(self.period + 1).collect();
+        recent.reverse();
+
+        self.calc_recent_slice(&recent)
+    }
+
+    /// [`AtrCalculator::calc_recent`] of candles ordered oldest first, e.g. a part of a longer history.
+    ///
+    /// The candle before the last `period` ones gives the previous close of the first true range.
+    pub fn calc_recent_slice<T: Candle>(&self, candles: &[&T]) -> Option<Atr> {
+        if self.period == 0 || candles.len() < self.period {
+            return None;
+        }
+
+        let start = candles.len() - self.period;
+        let mut prev_close = start.checked_sub(1).map(|index| candles[index].get_close());
+        let mut sum = 0.0;
+
+        for candle in &candles[start..] {
+            sum += calc_true_range(*candle, prev_close);
+            prev_close = Some(candle.get_close());
+        }
+
+        Some(Atr::new(sum / self.period as f64))
+    }
+
+    fn smooth(&self, prev: f64, true_range: f64, window: &mut Vec<f64>) -> f64 {
+        let period = self.period as f64;
+
+        match self.smoothing {
+            AtrSmoothing::Wilder => (prev * (period - 1.0) + true_range) / period,
+            AtrSmoothing::Ema => {
+                let alpha = 2.0 / (period + 1.0);
+                prev + alpha * (true_range - prev)
+            }
+            AtrSmoothing::Sma => {
+                window.remove(0);
+                window.push(true_range);
+                window.iter().sum::<f64>() / period
+            }
+        }
+    }
+
+    fn calc_max_true_range(&self, true_ranges: &[(u64, f64)]) -> f64 {
+        let Some(multiplier) = self.outlier_multiplier else {
+            return f64::MAX;
+        };
+
+        let values: Vec<f64> = true_ranges.iter().map(|(_, tr)| *tr).collect();
+
+        match calc_median(&values) {
+            Some(median) => median * multiplier,
+            None => f64::MAX,
+        }
+    }
+}
+
+/// True range of a candle: the largest of high - low, |high - prev close| and |low - prev close|.
+pub fn calc_true_range(candle: &impl Candle, prev_close: Option<f64>) -> f64 {
+    let range = candle.get_high() - candle.get_low();
+
+    match prev_close {
+        Some(prev_close) => range
+            .max((candle.get_high() - prev_close).abs())
+            .max((candle.get_low() - prev_close).abs()),
+        None => range,
+    }
+}
+
+/// True ranges of all candles keyed by time key. The first candle has no previous close so its range is used.
+pub fn calc_true_ranges<T: Candle>(candles: &BTreeMap<u64, T>) -> Vec<(u64, f64)> {
+    let mut prev_close = None;
+
+    candles
+        .iter()
+        .map(|(time_key, candle)| {
+            let true_range = calc_true_range(candle, prev_close);
+            prev_close = Some(candle.get_close());
+            (*time_key, true_range)
+        })
+        .collect()
+}
+
+pub fn calc_median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[middle - 1] + sorted[middle]) / 2.0)
+    } else {
+        Some(sorted[middle])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop_loss::TechStopLoss;
+    use crate::test_candles::make_candles;
+
+    #[test]
+    fn true_range_includes_gap() {
+        let candles = make_candles(&[(100.0, 101.0, 99.0, 100.0), (110.0, 111.0, 109.0, 110.0)]);
+        let true_ranges = calc_true_ranges(&candles);
+
+        assert_eq!(true_ranges, vec![(0, 2.0), (1, 11.0)]);
+    }
+
+    #[test]
+    fn wilder_smoothing() {
+        let candles = make_candles(&[
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 14.0, 10.0, 13.0),
+        ]);
+
+        let calculator = AtrCalculator::new(2, AtrSmoothing::Wilder);
+        let series = calculator.calc_series(&candles);
+
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[&1].get_value(), 2.0);
+        assert_eq!(series[&2].get_value(), 3.0);
+    }
+
+    #[test]
+    fn sma_and_ema_smoothing() {
+        let candles = make_candles(&[
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 16.0, 10.0, 15.0),
+        ]);
+
+        let sma = AtrCalculator::new(3, AtrSmoothing::Sma)
+            .calculate(&candles)
+            .unwrap();
+        assert_eq!(sma.get_value(), 10.0 / 3.0);
+
+        let ema = AtrCalculator::new(3, AtrSmoothing::Ema)
+            .calculate(&candles)
+            .unwrap();
+        assert_eq!(ema.get_value(), 4.0);
+    }
+
+    #[test]
+    fn outliers_are_skipped() {
+        let candles = make_candles(&[
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 11.0, 9.0, 10.0),
+            (10.0, 30.0, 9.0, 10.0),
+            (10.0, 11.0, 9.0, 10.0),
+        ]);
+
+        let calculator = AtrCalculator {
+            period: 3,
+            smoothing: AtrSmoothing::Sma,
+            outlier_multiplier: Some(3.0),
+        };
+
+        let atr = calculator.calculate(&candles).unwrap();
+        assert_eq!(atr.get_value(), 2.0);
+    }
+
+    #[test]
+    fn recent_atr_uses_close_before_window() {
+        let candles = make_candles(&[
+            (10.0, 30.0, 9.0, 10.0),
+            (10.0, 11.0, 9.0, 10.0),
+            (12.0, 13.0, 12.0, 12.5),
+        ]);
+
+        let calculator = AtrCalculator::new(2, AtrSmoothing::Wilder);
+        assert_eq!(calculator.calc_recent(&candles).unwrap().get_value(), 2.5);
+
+        let calculator = AtrCalculator::new(3, AtrSmoothing::Wilder);
+        assert_eq!(
+            calculator.calc_recent(&candles).unwrap().get_value(),
+            26.0 / 3.0
+        );
+        assert!(AtrCalculator::default().calc_recent(&candles).is_none());
+    }
+
+    #[test]
+    fn not_enough_candles() {
+        let candles = make_candles(&[(10.0, 11.0, 9.0, 10.0)]);
+        assert!(AtrCalculator::default().calculate(&candles).is_none());
+    }
+
+    #[test]
+    fn atr_feeds_tech_stop_loss() {
+        let candles = make_candles(&[(10.0, 11.0, 9.0, 10.0), (10.0, 11.0, 9.0, 10.0)]);
+        let atr = AtrCalculator::new(2, AtrSmoothing::Wilder)
+            .calculate(&candles)
+            .unwrap();
+
+        let stop_loss = TechStopLoss::from_crypto_and_us_stock_day_atr(atr);
+        assert!((stop_loss.get_value() - 0.3).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use super::Trade;
+use crate::analyzer::PatternType;
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeStats {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    /// Sum of all trade results in R
+    pub total_r: f64,
+    /// Share of winning trades (0.0 to 1.0)
+    pub win_rate: f64,
+    /// Gross profit / gross loss. `None` if there are no losing trades
+    pub profit_factor: Option<f64>,
+    /// Average trade result in R
+    pub expectancy_r: f64,
+    /// Largest peak to trough drop of the cumulative R curve
+    pub max_drawdown_r: f64,
+}
+
+impl TradeStats {
+    pub fn from_trades<'a>(trades: impl Iterator<Item = &'a Trade>) -> Self {
+        let mut result = Self::default();
+        let mut gross_profit = 0.0;
+        let mut gross_loss = 0.0;
+        let mut peak: f64 = 0.0;
+
+        for trade in trades {
+            let r = trade.get_r_multiple();
+
+            result.trades += 1;
+            result.total_r += r;
+
+            if r > 0.0 {
+                result.wins += 1;
+                gross_profit += r;
+            } else if r < 0.0 {
+                result.losses += 1;
+                gross_loss -= r;
+            }
+
+            peak = peak.max(result.total_r);
+            result.max_drawdown_r = result.max_drawdown_r.max(peak - result.total_r);
+        }
+
+        if result.trades > 0 {
+            result.win_rate = result.wins as f64 / result.trades as f64;
+            result.expectancy_r = result.total_r / result.trades as f64;
+        }
+
+        if gross_loss > 0.0 {
+            result.profit_factor = Some(gross_profit / gross_loss);
+        }
+
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BacktestReport {
+    pub trades: Vec<Trade>,
+    pub stats: TradeStats,
+    pub stats_by_pattern: HashMap<PatternType, TradeStats>,
+}
+
+impl BacktestReport {
+    pub fn from_trades(trades: Vec<Trade>) -> Self {
+        let stats = TradeStats::from_trades(trades.iter());
+
+        let mut by_pattern: HashMap<PatternType, Vec<&Trade>> = HashMap::new();
+        for trade in trades.iter() {
+            by_pattern
+                .entry(trade.pattern_type.clone())
+                .or_default()
+                .push(trade);
+        }
+
+        let stats_by_pattern = by_pattern
+            .into_iter()
+            .map(|(pattern_type, trades)| {
+                (pattern_type, TradeStats::from_trades(trades.into_iter()))
+            })
+            .collect();
+
+        Self {
+            trades,
+            stats,
+            stats_by_pattern,
+        }
+    }
+}
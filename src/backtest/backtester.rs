@@ -0,0 +1,520 @@
+use std::collections::BTreeMap;
+
+use super::{BacktestReport, Trade, TradeExitReason};
+use crate::analyzer::{CandleAnalyzer, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::filters::FilteredResult;
+use crate::levels::Level;
+use crate::stop_loss::{Luft, TechStopLoss};
+use crate::{DailyAtrBuilder, DailyAtrTracker};
+
+pub const BT_DEFAULT_TAKE_PROFIT_R: f64 = 3.0;
+pub const BT_DEFAULT_ORDER_TTL_BARS: usize = 3;
+
+#[derive(Debug, Clone)]
+pub enum StopSizing {
+    Fixed(TechStopLoss),
+    /// Tech stop from the daily ATR of the history seen so far
+    DailyAtr(DailyAtrBuilder),
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub stop_sizing: StopSizing,
+    /// `None` means the luft is derived from the tech stop (see `Luft::from(TechStopLoss)`)
+    pub luft: Option<Luft>,
+    /// Take profit distance in risks (R)
+    pub take_profit_r: f64,
+    /// Number of bars a limit order waits for a fill before it is cancelled
+    pub order_ttl_bars: usize,
+    /// `None` means trades are held until stop loss or take profit
+    pub max_bars_in_trade: Option<usize>,
+}
+
+impl BacktestConfig {
+    pub fn new(stop_sizing: StopSizing) -> Self {
+        Self {
+            stop_sizing,
+            luft: None,
+            take_profit_r: BT_DEFAULT_TAKE_PROFIT_R,
+            order_ttl_bars: BT_DEFAULT_ORDER_TTL_BARS,
+            max_bars_in_trade: None,
+        }
+    }
+}
+
+/// Event driven backtester.
+///
+/// Walks the history bar by bar and runs the analyzer on the candles closed so far.
+/// Only the last [`CandleAnalyzer::get_lookback`] candles are kept for the analyzer.
+/// Every run starts with no accepted signals, the ones of earlier runs are not seen by the filters.
+/// A level based signal places a limit order at level ± luft with a stop at the tech stop
+/// and a take profit at `take_profit_r`. Only one order or trade is active at a time.
+/// On the bar the order fills, take profit counts only when the bar closes beyond it.
+pub struct Backtester<TCandle: Candle> {
+    pub analyzer: CandleAnalyzer<TCandle>,
+    pub config: BacktestConfig,
+}
+
+struct PendingOrder {
+    pattern_type: PatternType,
+    direction: SignalDirection,
+    level: f64,
+    signal_time_key: u64,
+    entry_price: f64,
+    stop_price: f64,
+    take_profit_price: f64,
+    bars_waited: usize,
+}
+
+struct OpenTrade {
+    order: PendingOrder,
+    entry_time_key: u64,
+    entry_price: f64,
+    bars_in_trade: usize,
+}
+
+enum Position {
+    Flat,
+    Pending(PendingOrder),
+    Open(OpenTrade),
+}
+
+impl<TCandle: Candle + Clone> Backtester<TCandle> {
+    pub fn new(analyzer: CandleAnalyzer<TCandle>, config: BacktestConfig) -> Self {
+        Self { analyzer, config }
+    }
+
+    pub fn run(&self, candles: &BTreeMap<u64, TCandle>, levels: &[Level]) -> BacktestReport {
+        let lookback = self.analyzer.get_lookback();
+        let mut daily_atr = match &self.config.stop_sizing {
+            StopSizing::Fixed(_) => None,
+            StopSizing::DailyAtr(builder) => Some(DailyAtrTracker::new(builder.clone())),
+        };
+
+        let mut history = BTreeMap::new();
+        let mut accepted = Vec::new();
+        let mut trades = Vec::new();
+        let mut position = Position::Flat;
+
+        for (time_key, candle) in candles {
+            history.insert(*time_key, candle.clone());
+            if let Some(lookback) = lookback {
+                while history.len() > lookback.max(1) {
+                    history.pop_first();
+                }
+            }
+
+            if let Some(daily_atr) = daily_atr.as_mut() {
+                daily_atr.push(candle);
+            }
+
+            position = match position {
+                Position::Flat => Position::Flat,
+                Position::Pending(order) => self.process_pending(order, candle, &mut trades),
+                Position::Open(trade) => self.process_open(trade, candle, &mut trades),
+            };
+
+            if let Position::Flat = position
+                && let Some(order) =
+                    self.create_order(&history, levels, &mut accepted, daily_atr.as_ref())
+            {
+                position = Position::Pending(order);
+            }
+        }
+
+        if let (Position::Open(trade), Some((time_key, last))) =
+            (position, candles.last_key_value())
+        {
+            trades.push(close_trade(
+                trade,
+                *time_key,
+                last.get_close(),
+                TradeExitReason::EndOfData,
+            ));
+        }
+
+        BacktestReport::from_trades(trades)
+    }
+
+    fn create_order(
+        &self,
+        history: &BTreeMap<u64, TCandle>,
+        levels: &[Level],
+        accepted: &mut Vec<FilteredResult>,
+        daily_atr: Option<&DailyAtrTracker>,
+    ) -> Option<PendingOrder> {
+        let (signal_time_key, _) = history.last_key_value()?;
+
+        let signal = self
+            .analyzer
+            .analyze_levels(history, levels, accepted)
+            .into_iter()
+            .find(|r| r.level.is_some() && r.result.direction != SignalDirection::Neutral)?;
+
+        let tech_stop_loss = match (&self.config.stop_sizing, daily_atr) {
+            (StopSizing::Fixed(tech_stop_loss), _) => *tech_stop_loss,
+            (StopSizing::DailyAtr(_), daily_atr) => {
+                TechStopLoss::from_crypto_and_us_stock_day_atr(daily_atr?.get_daily_atr()?.atr)
+            }
+        };
+
+        let luft = self.config.luft.unwrap_or(Luft::from(tech_stop_loss));
+        let level = signal.level?.price;
+        let stop = tech_stop_loss.get_value();
+        let target = stop * self.config.take_profit_r;
+
+        let (entry_price, stop_price, take_profit_price) = match signal.result.direction {
+            SignalDirection::Bullish => {
+                let entry = level + luft.get_value();
+                (entry, entry - stop, entry + target)
+            }
+            _ => {
+                let entry = level - luft.get_value();
+                (entry, entry + stop, entry - target)
+            }
+        };
+
+        Some(PendingOrder {
+            pattern_type: signal.result.pattern_type,
+            direction: signal.result.direction,
+            level,
+            signal_time_key: *signal_time_key,
+            entry_price,
+            stop_price,
+            take_profit_price,
+            bars_waited: 0,
+        })
+    }
+
+    fn process_pending(
+        &self,
+        mut order: PendingOrder,
+        candle: &TCandle,
+        trades: &mut Vec<Trade>,
+    ) -> Position {
+        order.bars_waited += 1;
+
+        let fill_price = match order.direction {
+            SignalDirection::Bullish if candle.get_low() <= order.entry_price => {
+                Some(order.entry_price.min(candle.get_open()))
+            }
+            SignalDirection::Bearish if candle.get_high() >= order.entry_price => {
+                Some(order.entry_price.max(candle.get_open()))
+            }
+            _ => None,
+        };
+
+        let Some(entry_price) = fill_price else {
+            if order.bars_waited >= self.config.order_ttl_bars {
+                return Position::Flat;
+            }
+            return Position::Pending(order);
+        };
+
+        let trade = OpenTrade {
+            order,
+            entry_time_key: candle.get_time_key(),
+            entry_price,
+            bars_in_trade: 0,
+        };
+
+        self.process_open(trade, candle, trades)
+    }
+
+    fn process_open(
+        &self,
+        mut trade: OpenTrade,
+        candle: &TCandle,
+        trades: &mut Vec<Trade>,
+    ) -> Position {
+        let is_entry_bar = trade.entry_time_key == candle.get_time_key();
+        if !is_entry_bar {
+            trade.bars_in_trade += 1;
+        }
+
+        let stop_price = trade.order.stop_price;
+        let take_profit_price = trade.order.take_profit_price;
+        let time_key = candle.get_time_key();
+
+        // Stop loss is checked first: inside one bar we can't tell which was hit earlier.
+        // On the entry bar the extreme may have printed before the fill, so only a close beyond
+        // the take profit counts there
+        let exit = match trade.order.direction {
+            SignalDirection::Bullish => {
+                let take_profit_hit = if is_entry_bar {
+                    candle.get_close() >= take_profit_price
+                } else {
+                    candle.get_high() >= take_profit_price
+                };
+
+                if candle.get_low() <= stop_price {
+                    let price = if is_entry_bar {
+                        stop_price
+                    } else {
+                        stop_price.min(candle.get_open())
+                    };
+                    Some((price, TradeExitReason::StopLoss))
+                } else if take_profit_hit {
+                    Some((take_profit_price, TradeExitReason::TakeProfit))
+                } else {
+                    None
+                }
+            }
+            _ => {
+                let take_profit_hit = if is_entry_bar {
+                    candle.get_close() <= take_profit_price
+                } else {
+                    candle.get_low() <= take_profit_price
+                };
+
+                if candle.get_high() >= stop_price {
+                    let price = if is_entry_bar {
+                        stop_price
+                    } else {
+                        stop_price.max(candle.get_open())
+                    };
+                    Some((price, TradeExitReason::StopLoss))
+                } else if take_profit_hit {
+                    Some((take_profit_price, TradeExitReason::TakeProfit))
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some((price, reason)) = exit {
+            trades.push(close_trade(trade, time_key, price, reason));
+            return Position::Flat;
+        }
+
+        if let Some(max_bars) = self.config.max_bars_in_trade
+            && trade.bars_in_trade >= max_bars
+        {
+            trades.push(close_trade(
+                trade,
+                time_key,
+                candle.get_close(),
+                TradeExitReason::Timeout,
+            ));
+            return Position::Flat;
+        }
+
+        Position::Open(trade)
+    }
+}
+
+fn close_trade(
+    trade: OpenTrade,
+    time_key: u64,
+    exit_price: f64,
+    exit_reason: TradeExitReason,
+) -> Trade {
+    Trade {
+        pattern_type: trade.order.pattern_type,
+        direction: trade.order.direction,
+        level: trade.order.level,
+        signal_time_key: trade.order.signal_time_key,
+        entry_time_key: trade.entry_time_key,
+        exit_time_key: time_key,
+        entry_price: trade.entry_price,
+        stop_price: trade.order.stop_price,
+        take_profit_price: trade.order.take_profit_price,
+        exit_price,
+        exit_reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternEvidence, PatternResult};
+    use crate::candle::CandleInstance;
+    use crate::patterns::Pattern;
+    use crate::test_candles::make_candles;
+    use crate::{InstrumentType, TimeKeyFormat};
+
+    /// Fires on the given time keys only
+    struct SignalAt {
+        signals: Vec<(u64, SignalDirection)>,
+        pattern_type: PatternType,
+    }
+
+    impl Pattern<CandleInstance> for SignalAt {
+        fn matches(
+            &self,
+            candles: &BTreeMap<u64, CandleInstance>,
+            _level: f64,
+        ) -> Option<PatternResult> {
+            let (time_key, _) = candles.last_key_value()?;
+            let (_, direction) = self.signals.iter().find(|(k, _)| k == time_key)?;
+
+            Some(PatternResult {
+                name: "Test".to_string(),
+                direction: direction.clone(),
+                description: "".to_string(),
+                confidence: None,
+                pattern_type: self.pattern_type.clone(),
+                evidence: PatternEvidence::new(*time_key, vec![*time_key]),
+            })
+        }
+
+        fn lookback(&self) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    fn make_backtester(
+        signals: Vec<(u64, SignalDirection)>,
+        pattern_type: PatternType,
+    ) -> Backtester<CandleInstance> {
+        let mut analyzer = CandleAnalyzer::default();
+        analyzer.register_pattern(SignalAt {
+            signals,
+            pattern_type,
+        });
+
+        let config = BacktestConfig {
+            luft: Some(Luft::new(0.1)),
+            take_profit_r: 2.0,
+            ..BacktestConfig::new(StopSizing::Fixed(TechStopLoss::new(1.0)))
+        };
+
+        Backtester::new(analyzer, config)
+    }
+
+    #[test]
+    fn long_trade_hits_take_profit() {
+        let candles = make_candles(&[
+            (101.0, 102.0, 100.5, 101.5), // signal
+            (101.5, 101.6, 100.0, 100.5), // fill at 100.1
+            (100.5, 102.5, 100.4, 102.2), // take profit at 102.1
+        ]);
+
+        let backtester = make_backtester(vec![(0, SignalDirection::Bullish)], PatternType::Hammer);
+        let report = backtester.run(&candles, &[Level::support(100.0, 0)]);
+
+        assert_eq!(report.trades.len(), 1);
+        let trade = &report.trades[0];
+        assert_eq!(trade.entry_time_key, 1);
+        assert_eq!(trade.exit_reason, TradeExitReason::TakeProfit);
+        assert!((trade.entry_price - 100.1).abs() < 1e-9);
+        assert!((trade.get_r_multiple() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_look_ahead_and_stats() {
+        let candles = make_candles(&[
+            (98.0, 99.0, 97.5, 98.5),     // short signal
+            (98.5, 100.0, 98.4, 99.8),    // fill at 99.9
+            (99.8, 101.0, 99.5, 100.8),   // stop loss at 100.9
+            (100.8, 101.5, 100.5, 101.0), // long signal
+            (101.0, 101.2, 100.0, 100.6), // fill at 100.1
+            (100.6, 102.5, 100.4, 102.3), // take profit at 102.1
+            (102.3, 102.4, 99.0, 99.5),   // signal which can't be filled: no more candles
+        ]);
+
+        let backtester = make_backtester(
+            vec![
+                (0, SignalDirection::Bearish),
+                (3, SignalDirection::Bullish),
+                (6, SignalDirection::Bullish),
+            ],
+            PatternType::CloseRetest,
+        );
+        let report = backtester.run(&candles, &[Level::resistance(100.0, 0)]);
+
+        assert_eq!(report.trades.len(), 2);
+        assert_eq!(report.trades[0].exit_reason, TradeExitReason::StopLoss);
+        assert_eq!(report.trades[0].entry_time_key, 1);
+        assert_eq!(report.trades[0].exit_time_key, 2);
+        assert_eq!(report.trades[1].exit_reason, TradeExitReason::TakeProfit);
+
+        let stats = &report.stats;
+        assert_eq!(stats.trades, 2);
+        assert_eq!(stats.win_rate, 0.5);
+        assert!((stats.profit_factor.unwrap() - 2.0).abs() < 1e-9);
+        assert!((stats.expectancy_r - 0.5).abs() < 1e-9);
+        assert!((stats.max_drawdown_r - 1.0).abs() < 1e-9);
+
+        assert_eq!(report.stats_by_pattern[&PatternType::CloseRetest].trades, 2);
+    }
+
+    #[test]
+    fn entry_bar_high_is_not_take_profit() {
+        let candles = make_candles(&[
+            (101.0, 102.0, 100.5, 101.5), // signal
+            (101.5, 102.5, 100.0, 100.5), // high above take profit 102.1, fill at 100.1
+            (100.5, 101.0, 100.2, 100.8),
+            (100.8, 102.3, 100.7, 102.0), // take profit
+        ]);
+
+        let backtester = make_backtester(vec![(0, SignalDirection::Bullish)], PatternType::Hammer);
+        let report = backtester.run(&candles, &[Level::support(100.0, 0)]);
+
+        assert_eq!(report.trades.len(), 1);
+        let trade = &report.trades[0];
+        assert_eq!(trade.entry_time_key, 1);
+        assert_eq!(trade.exit_time_key, 3);
+        assert_eq!(trade.exit_reason, TradeExitReason::TakeProfit);
+    }
+
+    #[test]
+    fn unfilled_order_expires() {
+        let candles = make_candles(&[
+            (101.0, 102.0, 100.5, 101.5),
+            (101.5, 102.0, 101.0, 101.8),
+            (101.8, 102.0, 101.0, 101.8),
+            (101.8, 102.0, 101.0, 101.8),
+            (101.8, 102.0, 99.0, 99.5),
+        ]);
+
+        let backtester = make_backtester(vec![(0, SignalDirection::Bullish)], PatternType::Hammer);
+        let report = backtester.run(&candles, &[Level::support(100.0, 0)]);
+
+        assert!(report.trades.is_empty());
+    }
+
+    #[test]
+    fn daily_atr_stop_uses_closed_days() {
+        const DAY: u64 = 24 * 60 * 60;
+
+        let candles: BTreeMap<u64, CandleInstance> = make_candles(&[
+            (101.0, 102.0, 100.0, 101.0),
+            (101.0, 102.0, 100.0, 101.0),
+            (101.0, 102.0, 100.0, 101.0),
+            (101.0, 102.0, 100.5, 101.5), // signal, daily ATR 2.0
+            (100.5, 100.6, 100.0, 100.6), // fill at 100.1
+            (100.6, 100.8, 100.5, 100.7), // take profit at 100.7
+        ])
+        .into_values()
+        .map(|mut candle| {
+            candle.time_key = candle.time_key * DAY + DAY / 2;
+            (candle.time_key, candle)
+        })
+        .collect();
+
+        let mut analyzer = CandleAnalyzer::default();
+        analyzer.register_pattern(SignalAt {
+            signals: vec![(3 * DAY + DAY / 2, SignalDirection::Bullish)],
+            pattern_type: PatternType::Hammer,
+        });
+
+        let builder = DailyAtrBuilder {
+            period: 2,
+            ..DailyAtrBuilder::new(InstrumentType::Crypto, TimeKeyFormat::UnixSeconds)
+        };
+        let config = BacktestConfig {
+            luft: Some(Luft::new(0.1)),
+            take_profit_r: 2.0,
+            ..BacktestConfig::new(StopSizing::DailyAtr(builder))
+        };
+
+        let report = Backtester::new(analyzer, config).run(&candles, &[Level::support(100.0, 0)]);
+
+        assert_eq!(report.trades.len(), 1);
+        let trade = &report.trades[0];
+        assert!((trade.stop_price - 99.8).abs() < 1e-9);
+        assert_eq!(trade.exit_reason, TradeExitReason::TakeProfit);
+    }
+}
@@ -0,0 +1,6 @@
+mod backtester;
+pub use backtester::*;
+mod trade;
+pub use trade::*;
+mod backtest_report;
+pub use backtest_report::*;
@@ -0,0 +1,53 @@
+use crate::analyzer::{PatternType, SignalDirection};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TradeExitReason {
+    StopLoss,
+    TakeProfit,
+    /// Trade was closed at the close of the bar after `max_bars_in_trade` bars
+    Timeout,
+    /// Trade was still open at the end of the history and was closed at the last close
+    EndOfData,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    pub pattern_type: PatternType,
+    pub direction: SignalDirection,
+    pub level: f64,
+    pub signal_time_key: u64,
+    pub entry_time_key: u64,
+    pub exit_time_key: u64,
+    pub entry_price: f64,
+    pub stop_price: f64,
+    pub take_profit_price: f64,
+    pub exit_price: f64,
+    pub exit_reason: TradeExitReason,
+}
+
+impl Trade {
+    pub fn get_risk(&self) -> f64 {
+        (self.entry_price - self.stop_price).abs()
+    }
+
+    /// Result of the trade measured in risks (R)
+    pub fn get_r_multiple(&self) -> f64 {
+        let risk = self.get_risk();
+        if risk <= 0.0 {
+            return 0.0;
+        }
+
+        let profit = match self.direction {
+            SignalDirection::Bearish => self.entry_price - self.exit_price,
+            _ => self.exit_price - self.entry_price,
+        };
+
+        profit / risk
+    }
+
+    pub fn is_win(&self) -> bool {
+        self.get_r_multiple() > 0.0
+    }
+}
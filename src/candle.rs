@@ -7,7 +7,8 @@ pub trait Candle {
     fn get_volume(&self) -> f64;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CandleInstance {
     pub time_key: u64,
     pub open: f64,
@@ -17,6 +18,20 @@ pub struct CandleInstance {
     pub volume: f64,
 }
 
+impl CandleInstance {
+    /// Copy of any candle, e.g. to pass it where a `BTreeMap<u64, CandleInstance>` is needed
+    pub fn from_candle(candle: &impl Candle) -> Self {
+        Self {
+            time_key: candle.get_time_key(),
+            open: candle.get_open(),
+            high: candle.get_high(),
+            low: candle.get_low(),
+            close: candle.get_close(),
+            volume: candle.get_volume(),
+        }
+    }
+}
+
 impl Candle for CandleInstance {
     fn get_time_key(&self) -> u64 {
         self.time_key
@@ -0,0 +1,462 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use crate::candle::{Candle, CandleInstance};
+use crate::{ATR_DEFAULT_PERIOD, AtrCalculator, AtrSmoothing};
+
+pub const CV_DEFAULT_SPIKE_ATR_MULTIPLIER: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CandleIssueKind {
+    /// Open, high, low, close or volume is NaN or infinite
+    NonFiniteValue,
+    HighBelowLow,
+    OpenOutsideRange,
+    CloseOutsideRange,
+    NegativeVolume,
+    /// High equals low
+    ZeroRange,
+    /// Time key seen before. The first candle with the key is kept
+    DuplicateTimeKey,
+    /// Time key lower than the one of the previous candle
+    NonMonotonicTime,
+    /// Range above `spike_atr_multiplier` ATRs of the previous candles
+    Spike,
+}
+
+impl CandleIssueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleIssueKind::NonFiniteValue => "NonFiniteValue",
+            CandleIssueKind::HighBelowLow => "HighBelowLow",
+            CandleIssueKind::OpenOutsideRange => "OpenOutsideRange",
+            CandleIssueKind::CloseOutsideRange => "CloseOutsideRange",
+            CandleIssueKind::NegativeVolume => "NegativeVolume",
+            CandleIssueKind::ZeroRange => "ZeroRange",
+            CandleIssueKind::DuplicateTimeKey => "DuplicateTimeKey",
+            CandleIssueKind::NonMonotonicTime => "NonMonotonicTime",
+            CandleIssueKind::Spike => "Spike",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CandleIssue {
+    pub time_key: u64,
+    /// Position of the candle in the input, 0-based
+    pub index: usize,
+    pub kind: CandleIssueKind,
+}
+
+impl fmt::Display for CandleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "time key {}: {}", self.time_key, self.kind.as_str())
+    }
+}
+
+/// What to do with a candle which has issues.
+///
+/// Duplicates and candles out of order are dropped with every policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RepairPolicy {
+    Drop,
+    /// Stretch high and low over open and close, cut spikes to previous close ± `spike_atr_multiplier` ATRs,
+    /// set a negative or non-finite volume to zero. Candles with non-finite prices are dropped
+    Clamp,
+    /// Replace the candle with a flat one at the previous close and zero volume.
+    /// Dropped if there is no previous candle
+    ForwardFill,
+}
+
+#[derive(Debug, Clone)]
+pub struct CandleValidationResult {
+    pub candles: BTreeMap<u64, CandleInstance>,
+    /// Every issue found, in input order
+    pub issues: Vec<CandleIssue>,
+    /// Time keys of candles left out of `candles`
+    pub dropped: Vec<u64>,
+    /// Time keys of candles changed by the policy
+    pub repaired: Vec<u64>,
+}
+
+impl CandleValidationResult {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Checks input candles for values which make patterns produce nonsense and repairs them.
+///
+/// Zero-range candles are reported but kept by `Clamp` and `ForwardFill`, since they are valid for illiquid instruments.
+#[derive(Debug, Clone)]
+pub struct CandleValidator {
+    /// `None` disables spike detection
+    pub spike_atr_multiplier: Option<f64>,
+    /// Number of previous candles the spike ATR (simple average of true ranges) is taken from
+    pub atr_period: usize,
+    pub report_zero_range: bool,
+}
+
+impl Default for CandleValidator {
+    fn default() -> Self {
+        Self {
+            spike_atr_multiplier: Some(CV_DEFAULT_SPIKE_ATR_MULTIPLIER),
+            atr_period: ATR_DEFAULT_PERIOD,
+            report_zero_range: true,
+        }
+    }
+}
+
+impl CandleValidator {
+    /// Issues of the candles. Spikes are measured against the candles without issues
+    pub fn validate<'a, T: Candle + 'a>(
+        &self,
+        candles: impl IntoIterator<Item = &'a T>,
+    ) -> Vec<CandleIssue> {
+        self.sanitize(candles, RepairPolicy::Drop).issues
+    }
+
+    pub fn sanitize<'a, T: Candle + 'a>(
+        &self,
+        candles: impl IntoIterator<Item = &'a T>,
+        policy: RepairPolicy,
+    ) -> CandleValidationResult {
+        let mut result = CandleValidationResult {
+            candles: BTreeMap::new(),
+            issues: Vec::new(),
+            dropped: Vec::new(),
+            repaired: Vec::new(),
+        };
+        let mut last_time_key: Option<u64> = None;
+        let mut last_close: Option<f64> = None;
+        // The ATR candles and the one before them, which gives the previous close of the first one
+        let mut recent: VecDeque<CandleInstance> = VecDeque::with_capacity(self.atr_period + 1);
+
+        for (index, candle) in candles.into_iter().enumerate() {
+            let time_key = candle.get_time_key();
+            let atr = self.calc_atr(&recent);
+            let mut kinds = Vec::new();
+
+            if result.candles.contains_key(&time_key) {
+                kinds.push(CandleIssueKind::DuplicateTimeKey);
+            } else if last_time_key.is_some_and(|last| time_key < last) {
+                kinds.push(CandleIssueKind::NonMonotonicTime);
+            } else {
+                kinds = self.check_values(candle, last_close, atr);
+            }
+
+            result.issues.extend(kinds.iter().map(|kind| CandleIssue {
+                time_key,
+                index,
+                kind: *kind,
+            }));
+
+            let repaired = match kinds.as_slice() {
+                [] => Some(CandleInstance::from_candle(candle)),
+                [CandleIssueKind::DuplicateTimeKey] | [CandleIssueKind::NonMonotonicTime] => None,
+                [CandleIssueKind::ZeroRange] if policy != RepairPolicy::Drop => {
+                    Some(CandleInstance::from_candle(candle))
+                }
+                _ => match policy {
+                    RepairPolicy::Drop => None,
+                    RepairPolicy::Clamp => self.clamp(candle, last_close, atr),
+                    RepairPolicy::ForwardFill => last_close.map(|close| CandleInstance {
+                        time_key,
+                        open: close,
+                        high: close,
+                        low: close,
+                        close,
+                        volume: 0.0,
+                    }),
+                },
+            };
+
+            let Some(repaired) = repaired else {
+                result.dropped.push(time_key);
+                continue;
+            };
+
+            if !kinds.is_empty() && kinds != [CandleIssueKind::ZeroRange] {
+                result.repaired.push(time_key);
+            }
+
+            if self.atr_period > 0 {
+                if recent.len() > self.atr_period {
+                    recent.pop_front();
+                }
+                recent.push_back(repaired.clone());
+            }
+
+            last_time_key = Some(time_key);
+            last_close = Some(repaired.close);
+            result.candles.insert(time_key, repaired);
+        }
+
+        result
+    }
+
+    fn check_values(
+        &self,
+        candle: &impl Candle,
+        last_close: Option<f64>,
+        atr: Option<f64>,
+    ) -> Vec<CandleIssueKind> {
+        let (open, high, low, close) = (
+            candle.get_open(),
+            candle.get_high(),
+            candle.get_low(),
+            candle.get_close(),
+        );
+        let volume = candle.get_volume();
+
+        if [open, high, low, close, volume]
+            .iter()
+            .any(|v| !v.is_finite())
+        {
+            return vec![CandleIssueKind::NonFiniteValue];
+        }
+
+        let mut kinds = Vec::new();
+
+        if high < low {
+            kinds.push(CandleIssueKind::HighBelowLow);
+        }
+        if open > high || open < low {
+            kinds.push(CandleIssueKind::OpenOutsideRange);
+        }
+        if close > high || close < low {
+            kinds.push(CandleIssueKind::CloseOutsideRange);
+        }
+        if volume < 0.0 {
+            kinds.push(CandleIssueKind::NegativeVolume);
+        }
+        if high == low && self.report_zero_range {
+            kinds.push(CandleIssueKind::ZeroRange);
+        }
+
+        if let (Some(multiplier), Some(atr), Some(_)) = (self.spike_atr_multiplier, atr, last_close)
+            && high - low > multiplier * atr
+        {
+            kinds.push(CandleIssueKind::Spike);
+        }
+
+        kinds
+    }
+
+    fn clamp(
+        &self,
+        candle: &impl Candle,
+        last_close: Option<f64>,
+        atr: Option<f64>,
+    ) -> Option<CandleInstance> {
+        let (open, close) = (candle.get_open(), candle.get_close());
+        let (high, low) = (candle.get_high(), candle.get_low());
+
+        if ![open, high, low, close].iter().all(|v| v.is_finite()) {
+            return None;
+        }
+
+        let mut repaired = CandleInstance {
+            time_key: candle.get_time_key(),
+            open,
+            high: high.max(low).max(open).max(close),
+            low: low.min(high).min(open).min(close),
+            close,
+            volume: candle.get_volume(),
+        };
+
+        if !repaired.volume.is_finite() || repaired.volume < 0.0 {
+            repaired.volume = 0.0;
+        }
+
+        if let (Some(multiplier), Some(atr), Some(last_close)) =
+            (self.spike_atr_multiplier, atr, last_close)
+            && repaired.high - repaired.low > multiplier * atr
+        {
+            let (lower, upper) = (last_close - multiplier * atr, last_close + multiplier * atr);
+            repaired.open = repaired.open.clamp(lower, upper);
+            repaired.high = repaired.high.clamp(lower, upper);
+            repaired.low = repaired.low.clamp(lower, upper);
+            repaired.close = repaired.close.clamp(lower, upper);
+        }
+
+        Some(repaired)
+    }
+
+    /// ATR of the previous kept candles once there are `atr_period` of them
+    fn calc_atr(&self, recent: &VecDeque<CandleInstance>) -> Option<f64> {
+        let candles: Vec<&CandleInstance> = recent.iter().collect();
+        let atr = AtrCalculator::new(self.atr_period, AtrSmoothing::Sma)
+            .calc_recent_slice(&candles)?
+            .get_value();
+
+        (atr > 0.0).then_some(atr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_candle(time_key: u64, open: f64, high: f64, low: f64, close: f64) -> CandleInstance {
+        CandleInstance {
+            time_key,
+            open,
+            high,
+            low,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    /// Three normal candles with range 1.0 and the given ones
+    fn make_candles(after: &[CandleInstance]) -> Vec<CandleInstance> {
+        let mut candles: Vec<CandleInstance> = (1..=3)
+            .map(|time_key| make_candle(time_key, 100.0, 100.5, 99.5, 100.2))
+            .collect();
+        candles.extend_from_slice(after);
+        candles
+    }
+
+    fn make_validator() -> CandleValidator {
+        CandleValidator {
+            atr_period: 3,
+            ..CandleValidator::default()
+        }
+    }
+
+    fn to_kinds(issues: &[CandleIssue]) -> Vec<(u64, CandleIssueKind)> {
+        issues.iter().map(|i| (i.time_key, i.kind)).collect()
+    }
+
+    #[test]
+    fn reports_each_issue_with_time_key() {
+        let mut nan_volume = make_candle(7, 100.0, 100.5, 99.5, 100.2);
+        nan_volume.volume = f64::NAN;
+
+        let candles = make_candles(&[
+            make_candle(4, 100.0, 99.5, 100.5, 100.2),
+            make_candle(5, 101.0, 100.5, 99.5, 100.2),
+            make_candle(6, 100.0, 100.0, 100.0, 100.0),
+            nan_volume,
+            make_candle(7, 100.0, 100.5, 99.5, 100.2),
+            make_candle(2, 100.0, 100.5, 99.5, 100.2),
+            make_candle(8, 100.0, 106.0, 99.0, 105.0),
+        ]);
+
+        let issues = make_validator().validate(&candles);
+
+        assert_eq!(
+            to_kinds(&issues),
+            vec![
+                (4, CandleIssueKind::HighBelowLow),
+                (4, CandleIssueKind::OpenOutsideRange),
+                (4, CandleIssueKind::CloseOutsideRange),
+                (5, CandleIssueKind::OpenOutsideRange),
+                (6, CandleIssueKind::ZeroRange),
+                (7, CandleIssueKind::NonFiniteValue),
+                (2, CandleIssueKind::DuplicateTimeKey),
+                (8, CandleIssueKind::Spike),
+            ]
+        );
+        assert_eq!(issues[6].index, 8);
+        assert_eq!(issues[0].to_string(), "time key 4: HighBelowLow");
+    }
+
+    #[test]
+    fn clean_candles_pass_unchanged() {
+        let candles = make_candles(&[]);
+        let map: BTreeMap<u64, CandleInstance> =
+            candles.iter().map(|c| (c.time_key, c.clone())).collect();
+
+        let result = make_validator().sanitize(map.values(), RepairPolicy::Clamp);
+
+        assert!(result.is_clean());
+        assert_eq!(result.candles.len(), 3);
+        assert!(result.dropped.is_empty() && result.repaired.is_empty());
+    }
+
+    #[test]
+    fn drop_policy_removes_bad_candles() {
+        let candles = make_candles(&[
+            make_candle(4, 100.0, 99.5, 100.5, 100.2),
+            make_candle(5, 100.0, 100.0, 100.0, 100.0),
+            make_candle(3, 100.0, 100.5, 99.5, 100.2),
+        ]);
+
+        let result = make_validator().sanitize(&candles, RepairPolicy::Drop);
+
+        assert_eq!(result.dropped, vec![4, 5, 3]);
+        assert_eq!(
+            result.candles.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn clamp_policy_fixes_ranges_and_spikes() {
+        let candles = make_candles(&[
+            make_candle(4, 100.0, 99.5, 100.5, 100.2),
+            make_candle(5, 100.2, 120.0, 100.0, 101.0),
+            make_candle(6, 100.0, 100.0, 100.0, 100.0),
+            make_candle(7, f64::NAN, 100.5, 99.5, 100.2),
+        ]);
+
+        let result = make_validator().sanitize(&candles, RepairPolicy::Clamp);
+
+        let fixed = &result.candles[&4];
+        assert_eq!((fixed.high, fixed.low), (100.5, 99.5));
+
+        // ATR of candles 2..=4 is 1.0, so the spike is cut to 100.2 + 5.0
+        let spike = &result.candles[&5];
+        assert_eq!((spike.high, spike.low, spike.close), (105.2, 100.0, 101.0));
+
+        assert!(result.candles.contains_key(&6));
+        assert_eq!(result.repaired, vec![4, 5]);
+        assert_eq!(result.dropped, vec![7]);
+    }
+
+    #[test]
+    fn spike_atr_includes_gaps() {
+        // Ranges are 1.0, but every candle gaps 3.0 away from the previous close
+        let candles: Vec<CandleInstance> = (1..=4)
+            .map(|time_key| {
+                let price = if time_key % 2 == 0 { 103.0 } else { 100.0 };
+                make_candle(time_key, price, price + 0.5, price - 0.5, price)
+            })
+            .chain([make_candle(5, 103.0, 106.0, 100.0, 103.0)])
+            .collect();
+
+        // True ranges are 3.5, so a 6.0 wide candle is no spike
+        assert!(make_validator().validate(&candles).is_empty());
+    }
+
+    #[test]
+    fn forward_fill_uses_previous_close() {
+        let candles = vec![
+            make_candle(1, f64::INFINITY, 100.5, 99.5, 100.2),
+            make_candle(2, 100.0, 100.5, 99.5, 100.2),
+            make_candle(3, 100.0, 99.5, 100.5, 100.2),
+        ];
+
+        let result = make_validator().sanitize(&candles, RepairPolicy::ForwardFill);
+
+        assert_eq!(result.dropped, vec![1]);
+        assert_eq!(result.repaired, vec![3]);
+
+        let filled = &result.candles[&3];
+        assert_eq!(
+            (
+                filled.open,
+                filled.high,
+                filled.low,
+                filled.close,
+                filled.volume
+            ),
+            (100.2, 100.2, 100.2, 100.2, 0.0)
+        );
+    }
+}
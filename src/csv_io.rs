@@ -0,0 +1,423 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::analyzer::{PatternEvidence, PatternResult, PriceBand};
+use crate::candle::{Candle, CandleInstance};
+
+pub const CANDLES_CSV_HEADER: &str = "time_key,open,high,low,close,volume";
+pub const PATTERN_RESULTS_CSV_HEADER: &str = "name,direction,description,confidence,pattern_type,\
+trigger_time_key,time_keys,level,tolerance_lower,tolerance_upper,measurements";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvError {
+    /// 1-based line number of the record which failed to parse
+    pub line: usize,
+    pub message: String,
+}
+
+impl CsvError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Writes candles as `time_key,open,high,low,close,volume` rows ordered by time key.
+///
+/// Numbers are written in their shortest round-trip form, so [`read_candles_csv`] restores exactly the same values.
+pub fn write_candles_csv(candles: &BTreeMap<u64, impl Candle>) -> String {
+    let mut result = String::from(CANDLES_CSV_HEADER);
+    result.push('\n');
+
+    for candle in candles.values() {
+        result.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            candle.get_time_key(),
+            candle.get_open(),
+            candle.get_high(),
+            candle.get_low(),
+            candle.get_close(),
+            candle.get_volume()
+        ));
+    }
+
+    result
+}
+
+/// Reads candles written by [`write_candles_csv`]. The header row is required
+pub fn read_candles_csv(src: &str) -> Result<BTreeMap<u64, CandleInstance>, CsvError> {
+    let mut result = BTreeMap::new();
+
+    for (line, fields) in read_records(src, CANDLES_CSV_HEADER)? {
+        let candle = CandleInstance {
+            time_key: parse_field(&fields, 0, "time_key", line)?,
+            open: parse_field(&fields, 1, "open", line)?,
+            high: parse_field(&fields, 2, "high", line)?,
+            low: parse_field(&fields, 3, "low", line)?,
+            close: parse_field(&fields, 4, "close", line)?,
+            volume: parse_field(&fields, 5, "volume", line)?,
+        };
+
+        result.insert(candle.time_key, candle);
+    }
+
+    Ok(result)
+}
+
+/// Writes results as rows of [`PATTERN_RESULTS_CSV_HEADER`] columns.
+///
+/// Evidence goes to the last columns: `time_keys` as `1;2;3` and `measurements` as `atr=1.5;range=3`.
+/// Empty fields mean `None`. Text fields are quoted when they contain commas, quotes or line breaks.
+pub fn write_pattern_results_csv(results: &[PatternResult]) -> String {
+    let mut result = String::from(PATTERN_RESULTS_CSV_HEADER);
+    result.push('\n');
+
+    for item in results {
+        let evidence = &item.evidence;
+
+        let time_keys: Vec<String> = evidence.time_keys.iter().map(|k| k.to_string()).collect();
+        let measurements: Vec<String> = evidence
+            .measurements
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+
+        result.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{}\n",
+            quote_field(&item.name),
+            item.direction.as_str(),
+            quote_field(&item.description),
+            format_optional(item.confidence),
+            item.pattern_type.as_str(),
+            evidence.trigger_time_key,
+            time_keys.join(";"),
+            format_optional(evidence.level),
+            format_optional(evidence.tolerance_band.map(|b| b.lower)),
+            format_optional(evidence.tolerance_band.map(|b| b.upper)),
+            quote_field(&measurements.join(";"))
+        ));
+    }
+
+    result
+}
+
+/// Reads results written by [`write_pattern_results_csv`]. The header row is required
+pub fn read_pattern_results_csv(src: &str) -> Result<Vec<PatternResult>, CsvError> {
+    let mut result = Vec::new();
+
+    for (line, fields) in read_records(src, PATTERN_RESULTS_CSV_HEADER)? {
+        let tolerance_lower = parse_optional(&fields, 8, "tolerance_lower", line)?;
+        let tolerance_upper = parse_optional(&fields, 9, "tolerance_upper", line)?;
+
+        let evidence = PatternEvidence {
+            trigger_time_key: parse_field(&fields, 5, "trigger_time_key", line)?,
+            time_keys: parse_list(&fields[6], "time_keys", line)?,
+            level: parse_optional(&fields, 7, "level", line)?,
+            tolerance_band: match (tolerance_lower, tolerance_upper) {
+                (Some(lower), Some(upper)) => Some(PriceBand::new(lower, upper)),
+                _ => None,
+            },
+            measurements: parse_measurements(&fields[10], line)?,
+        };
+
+        result.push(PatternResult {
+            name: fields[0].clone(),
+            direction: parse_field(&fields, 1, "direction", line)?,
+            description: fields[2].clone(),
+            confidence: parse_optional(&fields, 3, "confidence", line)?,
+            pattern_type: parse_field(&fields, 4, "pattern_type", line)?,
+            evidence,
+        });
+    }
+
+    Ok(result)
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn parse_optional<T: std::str::FromStr>(
+    fields: &[String],
+    index: usize,
+    name: &str,
+    line: usize,
+) -> Result<Option<T>, CsvError>
+where
+    T::Err: fmt::Display,
+{
+    if fields[index].trim().is_empty() {
+        return Ok(None);
+    }
+
+    parse_field(fields, index, name, line).map(Some)
+}
+
+fn parse_list(value: &str, name: &str, line: usize) -> Result<Vec<u64>, CsvError> {
+    value
+        .split(';')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|_| CsvError::new(line, format!("Invalid {} item '{}'", name, item)))
+        })
+        .collect()
+}
+
+fn parse_measurements(value: &str, line: usize) -> Result<BTreeMap<String, f64>, CsvError> {
+    let mut result = BTreeMap::new();
+
+    for item in value.split(';').filter(|item| !item.trim().is_empty()) {
+        let parsed = item
+            .split_once('=')
+            .and_then(|(name, value)| Some((name.trim(), value.trim().parse::<f64>().ok()?)));
+
+        match parsed {
+            Some((name, value)) => {
+                result.insert(name.to_string(), value);
+            }
+            None => {
+                return Err(CsvError::new(
+                    line,
+                    format!("Invalid measurement '{}'", item),
+                ));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn quote_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(
+    fields: &[String],
+    index: usize,
+    name: &str,
+    line: usize,
+) -> Result<T, CsvError>
+where
+    T::Err: fmt::Display,
+{
+    let value = fields
+        .get(index)
+        .ok_or_else(|| CsvError::new(line, format!("Missing field {}", name)))?;
+
+    value
+        .trim()
+        .parse()
+        .map_err(|err| CsvError::new(line, format!("Invalid {} '{}': {}", name, value, err)))
+}
+
+/// Splits data rows into fields and checks the header. Returns the line each record starts at
+fn read_records(src: &str, header: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let columns = header.split(',').count();
+    let mut records = parse_records(src, ',')?.into_iter();
+
+    match records.next() {
+        Some((_, fields)) if fields.join(",") == header => {}
+        Some((line, _)) => {
+            return Err(CsvError::new(line, format!("Expected header '{}'", header)));
+        }
+        None => return Err(CsvError::new(1, "Empty input")),
+    }
+
+    let mut result = Vec::new();
+
+    for (line, fields) in records {
+        if fields.len() != columns {
+            return Err(CsvError::new(
+                line,
+                format!("Expected {} fields, got {}", columns, fields.len()),
+            ));
+        }
+
+        result.push((line, fields));
+    }
+
+    Ok(result)
+}
+
+/// RFC 4180 style parser. Quoted fields may contain delimiters, doubled quotes and line breaks. Blank lines are skipped
+pub(crate) fn parse_records(
+    src: &str,
+    delimiter: char,
+) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            _ if c == delimiter => fields.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                if !fields.is_empty() || !field.is_empty() {
+                    fields.push(std::mem::take(&mut field));
+                    records.push((record_line, std::mem::take(&mut fields)));
+                }
+                line += 1;
+                record_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(CsvError::new(record_line, "Unterminated quoted field"));
+    }
+
+    if !fields.is_empty() || !field.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternType, SignalDirection};
+
+    fn make_candles() -> BTreeMap<u64, CandleInstance> {
+        [
+            (202401020930, 100.0, 101.25, 99.5, 100.75, 1500.0),
+            (202401020931, 100.75, 102.0, 100.1, 101.9, 0.0),
+            (202401020932, 0.1 + 0.2, 0.35, 0.25, 0.3, 12.5),
+        ]
+        .iter()
+        .map(|&(time_key, open, high, low, close, volume)| {
+            (
+                time_key,
+                CandleInstance {
+                    time_key,
+                    open,
+                    high,
+                    low,
+                    close,
+                    volume,
+                },
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn candles_round_trip() {
+        let candles = make_candles();
+
+        let csv = write_candles_csv(&candles);
+        assert!(csv.starts_with(
+            "time_key,open,high,low,close,volume\n202401020930,100,101.25,99.5,100.75,1500\n"
+        ));
+
+        let restored = read_candles_csv(&csv).unwrap();
+
+        assert_eq!(restored.len(), candles.len());
+        for (key, candle) in candles.iter() {
+            let other = &restored[key];
+            assert_eq!(other.open, candle.open);
+            assert_eq!(other.high, candle.high);
+            assert_eq!(other.low, candle.low);
+            assert_eq!(other.close, candle.close);
+            assert_eq!(other.volume, candle.volume);
+        }
+    }
+
+    #[test]
+    fn pattern_results_round_trip() {
+        let results = vec![
+            PatternResult {
+                name: "Hammer".to_string(),
+                direction: SignalDirection::Bullish,
+                description: "Hammer at 100, \"strong\" wick".to_string(),
+                confidence: Some(0.8),
+                pattern_type: PatternType::Hammer,
+                evidence: PatternEvidence::new(202401020931, vec![202401020931])
+                    .with_level(100.0)
+                    .with_measurement("body", 0.25)
+                    .with_measurement("lower_wick", 1.5),
+            },
+            PatternResult {
+                name: "Retest".to_string(),
+                direction: SignalDirection::Bearish,
+                description: "Two lines\nof text".to_string(),
+                confidence: None,
+                pattern_type: PatternType::CloseRetest,
+                evidence: PatternEvidence::new(202401020932, vec![202401020930, 202401020932])
+                    .with_level(101.0)
+                    .with_tolerance_band(98.98, 103.02)
+                    .with_measurement("bump_count", 2.0),
+            },
+        ];
+
+        let csv = write_pattern_results_csv(&results);
+        let restored = read_pattern_results_csv(&csv).unwrap();
+
+        assert_eq!(restored.len(), 2);
+        for (restored, original) in restored.iter().zip(results.iter()) {
+            assert_eq!(restored.name, original.name);
+            assert_eq!(restored.direction, original.direction);
+            assert_eq!(restored.description, original.description);
+            assert_eq!(restored.confidence, original.confidence);
+            assert_eq!(restored.pattern_type, original.pattern_type);
+            assert_eq!(restored.evidence, original.evidence);
+        }
+    }
+
+    #[test]
+    fn reports_line_of_invalid_row() {
+        let csv = "time_key,open,high,low,close,volume\n1,1,2,0.5,1.5,10\n\n3,1,abc,0.5,1.5,10\n";
+
+        let err = read_candles_csv(csv).unwrap_err();
+
+        assert_eq!(err.line, 4);
+        assert!(err.message.contains("high"));
+    }
+
+    #[test]
+    fn rejects_unknown_header() {
+        let err = read_candles_csv("date,o,h,l,c,v\n").unwrap_err();
+
+        assert_eq!(err.line, 1);
+    }
+}
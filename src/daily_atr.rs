@@ -0,0 +1,455 @@
+use std::collections::BTreeMap;
+
+use chrono_tz::America::New_York;
+
+use crate::candle::{Candle, CandleInstance};
+use crate::{
+    Atr, InstrumentType, TimeKeyFormat, UsMarketMoment, calc_median, calc_true_range,
+    calc_true_ranges, to_day_key,
+};
+
+pub const DAILY_ATR_DEFAULT_PERIOD: usize = 5;
+pub const DAILY_ATR_DEFAULT_MAX_RANGE_MULTIPLIER: f64 = 2.0;
+pub const DAILY_ATR_DEFAULT_MIN_RANGE_MULTIPLIER: f64 = 0.3;
+
+/// Builds a daily ATR from intraday candles.
+///
+/// US stocks are aggregated over regular hours only (New York trading days), crypto over 24h UTC days.
+/// The last aggregated day is treated as today and is not part of the ATR.
+/// Day ranges are true ranges, so overnight gaps from the previous day's close count.
+#[derive(Debug, Clone)]
+pub struct DailyAtrBuilder {
+    pub instrument_type: InstrumentType,
+    pub key_format: TimeKeyFormat,
+    /// Number of normal days averaged
+    pub period: usize,
+    /// Days with a true range above `max_range_multiplier` × median range are paranormal and skipped
+    pub max_range_multiplier: f64,
+    /// Days with a true range below `min_range_multiplier` × median range are paranormal and skipped
+    pub min_range_multiplier: f64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DailyAtr {
+    pub atr: Atr,
+    /// Time keys (yyyyMMdd) of days which formed the ATR
+    pub days_used: Vec<u64>,
+    /// Time keys (yyyyMMdd) of paranormal days which were skipped
+    pub days_skipped: Vec<u64>,
+    /// Today's bar aggregated so far
+    pub today: Option<CandleInstance>,
+    /// Close of the last day before today
+    pub prev_close: Option<f64>,
+}
+
+impl DailyAtr {
+    /// True range of today so far, so a gap from the previous close counts like the ATR days do
+    pub fn get_today_range(&self) -> f64 {
+        match &self.today {
+            Some(today) => calc_true_range(today, self.prev_close),
+            None => 0.0,
+        }
+    }
+
+    /// Part of the ATR already passed today (`1.0` means 100%)
+    pub fn get_used_ratio(&self) -> f64 {
+        if self.atr.get_value() <= 0.0 {
+            return 0.0;
+        }
+
+        self.get_today_range() / self.atr.get_value()
+    }
+
+    pub fn get_used_percent(&self) -> f64 {
+        self.get_used_ratio() * 100.0
+    }
+
+    /// Range left for today. Zero if the ATR is already used up
+    pub fn get_remaining(&self) -> f64 {
+        (self.atr.get_value() - self.get_today_range()).max(0.0)
+    }
+}
+
+impl DailyAtrBuilder {
+    pub fn new(instrument_type: InstrumentType, key_format: TimeKeyFormat) -> Self {
+        Self {
+            instrument_type,
+            key_format,
+            period: DAILY_ATR_DEFAULT_PERIOD,
+            max_range_multiplier: DAILY_ATR_DEFAULT_MAX_RANGE_MULTIPLIER,
+            min_range_multiplier: DAILY_ATR_DEFAULT_MIN_RANGE_MULTIPLIER,
+        }
+    }
+
+    /// `None` if there are fewer than `period` normal days before today
+    pub fn build<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<DailyAtr> {
+        let mut days = aggregate_daily_bars(candles, self.instrument_type, self.key_format);
+        let today = days.pop_last().map(|(_, bar)| bar);
+
+        let mut result = self.build_from_closed_days(&days)?;
+        result.today = today;
+        Some(result)
+    }
+
+    /// Today's bar of [`DailyAtrBuilder::build`] and the last candle of the day before.
+    ///
+    /// Only the candles of today and the one before are read, from the end of the history.
+    pub fn get_today<'c, T: Candle>(
+        &self,
+        candles: &'c BTreeMap<u64, T>,
+    ) -> Option<(CandleInstance, Option<&'c T>)> {
+        let mut today_key = None;
+        let mut today_candles = Vec::new();
+        let mut last_closed = None;
+
+        for candle in candles.values().rev() {
+            let Some(day_key) = get_day_key(candle, self.instrument_type, self.key_format) else {
+                continue;
+            };
+
+            if *today_key.get_or_insert(day_key) != day_key {
+                last_closed = Some(candle);
+                break;
+            }
+
+            today_candles.push(candle);
+        }
+
+        let mut days = BTreeMap::new();
+        for candle in today_candles.into_iter().rev() {
+            add_to_day(&mut days, today_key?, candle);
+        }
+
+        let (_, today) = days.pop_last()?;
+        Some((today, last_closed))
+    }
+
+    /// ATR of daily bars which are all closed. `today` of the result is `None`
+    pub fn build_from_closed_days(&self, days: &BTreeMap<u64, CandleInstance>) -> Option<DailyAtr> {
+        let true_ranges = calc_true_ranges(days);
+        let ranges: Vec<f64> = true_ranges.iter().map(|(_, range)| *range).collect();
+        let median = calc_median(&ranges)?;
+        let max_range = median * self.max_range_multiplier;
+        let min_range = median * self.min_range_multiplier;
+
+        let mut days_used = Vec::new();
+        let mut days_skipped = Vec::new();
+        let mut sum = 0.0;
+
+        for (day_key, range) in true_ranges.into_iter().rev() {
+            if days_used.len() == self.period {
+                break;
+            }
+
+            if range > max_range || range < min_range {
+                days_skipped.push(day_key);
+                continue;
+            }
+
+            sum += range;
+            days_used.push(day_key);
+        }
+
+        if days_used.len() < self.period || self.period == 0 {
+            return None;
+        }
+
+        days_used.reverse();
+        days_skipped.reverse();
+
+        Some(DailyAtr {
+            atr: Atr::new(sum / self.period as f64),
+            days_used,
+            days_skipped,
+            today: None,
+            prev_close: days.last_key_value().map(|(_, bar)| bar.close),
+        })
+    }
+}
+
+/// [`DailyAtrBuilder::build`] for candles arriving one at a time, e.g. in a backtest.
+///
+/// Daily bars are updated in place and the ATR of the closed days is rebuilt only when a new day starts.
+#[derive(Debug, Clone)]
+pub struct DailyAtrTracker {
+    pub builder: DailyAtrBuilder,
+    days: BTreeMap<u64, CandleInstance>,
+    closed: Option<DailyAtr>,
+}
+
+impl DailyAtrTracker {
+    pub fn new(builder: DailyAtrBuilder) -> Self {
+        Self {
+            builder,
+            days: BTreeMap::new(),
+            closed: None,
+        }
+    }
+
+    pub fn push(&mut self, candle: &impl Candle) {
+        let Some(day_key) = get_day_key(
+            candle,
+            self.builder.instrument_type,
+            self.builder.key_format,
+        ) else {
+            return;
+        };
+
+        let is_today = match self.days.last_key_value() {
+            Some((last_key, _)) => *last_key == day_key,
+            None => true,
+        };
+
+        add_to_day(&mut self.days, day_key, candle);
+
+        if !is_today {
+            let mut closed_days = self.days.clone();
+            closed_days.pop_last();
+            self.closed = self.builder.build_from_closed_days(&closed_days);
+        }
+    }
+
+    /// Same as [`DailyAtrBuilder::build`] over all pushed candles
+    pub fn get_daily_atr(&self) -> Option<DailyAtr> {
+        let mut result = self.closed.clone()?;
+        result.today = self.days.last_key_value().map(|(_, bar)| bar.clone());
+        Some(result)
+    }
+}
+
+/// Aggregates intraday candles into daily bars keyed by yyyyMMdd.
+///
+/// For US stocks only regular hours candles are used and days are New York trading days.
+/// Crypto days are 24h UTC days. Candles with time keys not valid for `key_format` are skipped.
+pub fn aggregate_daily_bars<T: Candle>(
+    candles: &BTreeMap<u64, T>,
+    instrument_type: InstrumentType,
+    key_format: TimeKeyFormat,
+) -> BTreeMap<u64, CandleInstance> {
+    let mut result: BTreeMap<u64, CandleInstance> = BTreeMap::new();
+
+    for candle in candles.values() {
+        if let Some(day_key) = get_day_key(candle, instrument_type, key_format) {
+            add_to_day(&mut result, day_key, candle);
+        }
+    }
+
+    result
+}
+
+/// Day (yyyyMMdd) the candle belongs to. `None` for US stocks outside regular hours
+fn get_day_key(
+    candle: &impl Candle,
+    instrument_type: InstrumentType,
+    key_format: TimeKeyFormat,
+) -> Option<u64> {
+    let dt = key_format.to_date_time(candle.get_time_key())?;
+
+    match instrument_type {
+        InstrumentType::UsStocks => {
+            if !UsMarketMoment::from(dt).is_working() {
+                return None;
+            }
+            Some(to_day_key(&dt.to_chrono_utc().with_timezone(&New_York)))
+        }
+        InstrumentType::Crypto => Some(to_day_key(&dt.to_chrono_utc())),
+    }
+}
+
+fn add_to_day(days: &mut BTreeMap<u64, CandleInstance>, day_key: u64, candle: &impl Candle) {
+    match days.get_mut(&day_key) {
+        Some(bar) => {
+            bar.high = bar.high.max(candle.get_high());
+            bar.low = bar.low.min(candle.get_low());
+            bar.close = candle.get_close();
+            bar.volume += candle.get_volume();
+        }
+        None => {
+            days.insert(
+                day_key,
+                CandleInstance {
+                    time_key: day_key,
+                    open: candle.get_open(),
+                    high: candle.get_high(),
+                    low: candle.get_low(),
+                    close: candle.get_close(),
+                    volume: candle.get_volume(),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stop_loss::TechStopLoss;
+
+    // 2025-04-07 (Monday) 09:30 New York
+    const MONDAY_OPEN: u64 = 1744032600;
+    const HOUR: u64 = 3600;
+    const DAY: u64 = 24 * HOUR;
+
+    fn add_day(candles: &mut BTreeMap<u64, CandleInstance>, day_start: u64, low: f64, high: f64) {
+        // pre-market candle with a huge range which has to be ignored
+        let pre_market = day_start - 2 * HOUR;
+        candles.insert(
+            pre_market,
+            CandleInstance {
+                time_key: pre_market,
+                open: 100.0,
+                high: 1000.0,
+                low: 1.0,
+                close: 100.0,
+                volume: 1.0,
+            },
+        );
+
+        let middle = (low + high) / 2.0;
+        for (i, (l, h)) in [(low, middle), (middle, high)].iter().enumerate() {
+            let time_key = day_start + i as u64 * HOUR;
+            candles.insert(
+                time_key,
+                CandleInstance {
+                    time_key,
+                    open: *l,
+                    high: *h,
+                    low: *l,
+                    close: *h,
+                    volume: 10.0,
+                },
+            );
+        }
+    }
+
+    fn make_candles(ranges: &[(f64, f64)]) -> BTreeMap<u64, CandleInstance> {
+        let mut candles = BTreeMap::new();
+        for (i, (low, high)) in ranges.iter().enumerate() {
+            // skip weekends
+            let day = i as u64 + (i as u64 / 5) * 2;
+            add_day(&mut candles, MONDAY_OPEN + day * DAY, *low, *high);
+        }
+        candles
+    }
+
+    #[test]
+    fn aggregates_regular_hours_only() {
+        let candles = make_candles(&[(100.0, 104.0), (101.0, 103.0)]);
+        let days = aggregate_daily_bars(
+            &candles,
+            InstrumentType::UsStocks,
+            TimeKeyFormat::UnixSeconds,
+        );
+
+        assert_eq!(days.len(), 2);
+        let monday = &days[&20250407];
+        assert_eq!(monday.low, 100.0);
+        assert_eq!(monday.high, 104.0);
+        assert_eq!(monday.volume, 20.0);
+    }
+
+    #[test]
+    fn crypto_uses_whole_day() {
+        let candles = make_candles(&[(100.0, 104.0)]);
+        let days =
+            aggregate_daily_bars(&candles, InstrumentType::Crypto, TimeKeyFormat::UnixSeconds);
+
+        assert_eq!(days[&20250407].high, 1000.0);
+    }
+
+    #[test]
+    fn skips_paranormal_days_and_reports_used_atr() {
+        let candles = make_candles(&[
+            (100.0, 104.0),
+            (100.0, 104.0),
+            (84.0, 104.0), // paranormal: too wide
+            (100.0, 104.0),
+            (103.5, 104.0), // paranormal: too narrow
+            (102.0, 104.0), // today
+        ]);
+
+        let builder = DailyAtrBuilder {
+            period: 3,
+            ..DailyAtrBuilder::new(InstrumentType::UsStocks, TimeKeyFormat::UnixSeconds)
+        };
+        let daily_atr = builder.build(&candles).unwrap();
+
+        assert_eq!(daily_atr.atr.get_value(), 4.0);
+        assert_eq!(daily_atr.days_used, vec![20250407, 20250408, 20250410]);
+        assert_eq!(daily_atr.days_skipped, vec![20250409, 20250411]);
+        assert_eq!(daily_atr.get_used_percent(), 50.0);
+        assert_eq!(daily_atr.get_remaining(), 2.0);
+
+        let stop_loss = TechStopLoss::from_crypto_and_us_stock_day_atr(daily_atr.atr);
+        assert!((stop_loss.get_value() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn overnight_gaps_are_part_of_range() {
+        let candles = make_candles(&[
+            (100.0, 104.0),
+            (110.0, 114.0),
+            (100.0, 104.0),
+            (100.0, 102.0), // today
+        ]);
+
+        let builder = DailyAtrBuilder {
+            period: 2,
+            ..DailyAtrBuilder::new(InstrumentType::UsStocks, TimeKeyFormat::UnixSeconds)
+        };
+        let daily_atr = builder.build(&candles).unwrap();
+
+        // Gap up from 104.0 to 110.0 and down from 114.0 to 100.0
+        assert_eq!(daily_atr.atr.get_value(), 12.0);
+        assert_eq!(daily_atr.days_used, vec![20250408, 20250409]);
+        // Today's low is 4.0 below the previous close of 104.0
+        assert_eq!(daily_atr.get_today_range(), 4.0);
+    }
+
+    #[test]
+    fn tracker_gives_same_atr_as_builder() {
+        let candles = make_candles(&[
+            (100.0, 104.0),
+            (100.0, 104.0),
+            (84.0, 104.0),
+            (100.0, 104.0),
+            (103.5, 104.0),
+            (100.0, 102.0),
+        ]);
+
+        let builder = DailyAtrBuilder {
+            period: 3,
+            ..DailyAtrBuilder::new(InstrumentType::UsStocks, TimeKeyFormat::UnixSeconds)
+        };
+        let mut tracker = DailyAtrTracker::new(builder.clone());
+        let mut history = BTreeMap::new();
+
+        let to_key = |daily_atr: Option<DailyAtr>| {
+            daily_atr.map(|d| {
+                let today = d.today.map(|t| (t.time_key, t.high, t.low));
+                (d.atr.get_value(), d.days_used, d.days_skipped, today)
+            })
+        };
+
+        for (time_key, candle) in candles.iter() {
+            history.insert(*time_key, candle.clone());
+            tracker.push(candle);
+
+            assert_eq!(
+                to_key(tracker.get_daily_atr()),
+                to_key(builder.build(&history))
+            );
+        }
+
+        assert!(tracker.get_daily_atr().is_some());
+    }
+
+    #[test]
+    fn not_enough_normal_days() {
+        let candles = make_candles(&[(100.0, 104.0), (100.0, 104.0)]);
+        let builder = DailyAtrBuilder::new(InstrumentType::UsStocks, TimeKeyFormat::UnixSeconds);
+
+        assert!(builder.build(&candles).is_none());
+    }
+}
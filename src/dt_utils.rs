@@ -1,5 +1,6 @@
-use chrono_tz::{America::New_York, Tz};
-use rust_extensions::{chrono::Timelike, date_time::DateTimeAsMicroseconds};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::sessions::{Exchange, SessionPhase};
 
 pub enum UsMarketMoment {
     DayOff,
@@ -9,33 +10,14 @@ pub enum UsMarketMoment {
 }
 
 impl UsMarketMoment {
+    /// Uses the NYSE calendar, so holidays are `DayOff` and early closes end `Working` at 13:00
     pub fn from(dt: DateTimeAsMicroseconds) -> Self {
-        use rust_extensions::chrono::Datelike;
-        let eastern_time: rust_extensions::chrono::DateTime<Tz> =
-            dt.to_chrono_utc().with_timezone(&New_York);
-
-        match eastern_time.weekday() {
-            rust_extensions::chrono::Weekday::Sat => return Self::DayOff,
-            rust_extensions::chrono::Weekday::Sun => return Self::DayOff,
-            _ => {}
-        }
-
-        let time = eastern_time.time();
-
-        let now_time = time.hour() * 100 + time.minute();
-
-        if 0930 <= now_time && now_time < 1600 {
-            return Self::Working;
-        }
-
-        if 0400 <= now_time && now_time < 930 {
-            return Self::PreMarket;
-        }
-        if 1600 <= now_time && now_time < 2000 {
-            return Self::PostMarket;
+        match Exchange::Nyse.get_phase(dt) {
+            SessionPhase::Regular => Self::Working,
+            SessionPhase::PreMarket => Self::PreMarket,
+            SessionPhase::PostMarket => Self::PostMarket,
+            _ => Self::DayOff,
         }
-
-        Self::DayOff
     }
 
     pub fn is_working(&self) -> bool {
@@ -46,3 +28,23 @@ impl UsMarketMoment {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holidays_and_early_closes_are_not_working() {
+        // 2025-11-27 11:00 New York, Thanksgiving
+        let thanksgiving = DateTimeAsMicroseconds::new(1764259200 * 1_000_000);
+        assert!(!UsMarketMoment::from(thanksgiving).is_working());
+
+        // 2025-11-28 14:00 New York, Black Friday closes at 13:00
+        let black_friday = DateTimeAsMicroseconds::new(1764356400 * 1_000_000);
+        assert!(!UsMarketMoment::from(black_friday).is_working());
+
+        // 2025-11-26 14:00 New York
+        let usual_day = DateTimeAsMicroseconds::new(1764183600 * 1_000_000);
+        assert!(UsMarketMoment::from(usual_day).is_working());
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use super::{FilteredResult, SignalFilter};
+use crate::analyzer::PatternResult;
+use crate::candle::Candle;
+
+/// Suppresses signals with a confidence below `min_confidence`.
+#[derive(Debug, Clone)]
+pub struct MinConfidenceFilter {
+    pub min_confidence: f64,
+    /// Let through patterns which do not calculate a confidence
+    pub pass_unscored: bool,
+}
+
+impl MinConfidenceFilter {
+    pub fn new(min_confidence: f64) -> Self {
+        Self {
+            min_confidence,
+            pass_unscored: true,
+        }
+    }
+}
+
+impl<TCandle: Candle> SignalFilter<TCandle> for MinConfidenceFilter {
+    fn name(&self) -> &str {
+        "MinConfidence"
+    }
+
+    fn check(
+        &self,
+        _candles: &BTreeMap<u64, TCandle>,
+        signal: &PatternResult,
+        _accepted: &[FilteredResult],
+    ) -> Result<(), String> {
+        match signal.confidence {
+            Some(confidence) if confidence < self.min_confidence => Err(format!(
+                "confidence {:.2} is below {:.2}",
+                confidence, self.min_confidence
+            )),
+            None if !self.pass_unscored => Err("no confidence".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(1)
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use super::{FilteredResult, SignalFilter};
+use crate::analyzer::PatternResult;
+use crate::candle::{Candle, CandleInstance};
+use crate::{DailyAtr, DailyAtrBuilder};
+
+pub const DAILY_ATR_FILTER_DEFAULT_MAX_USED_RATIO: f64 = 0.8;
+/// Number of histories, e.g. symbols, whose ATR of the closed days is kept
+pub const DAILY_ATR_FILTER_CACHE_SIZE: usize = 64;
+
+/// Suppresses signals once today's range has used up too much of the daily ATR.
+///
+/// Signals are suppressed as well while there are not enough days for the ATR.
+/// The ATR of the closed days is built once a day per history, e.g. per symbol,
+/// and reused while the history before today stays the same.
+#[derive(Debug)]
+pub struct DailyAtrFilter {
+    pub builder: DailyAtrBuilder,
+    /// Share of the daily ATR (`1.0` means 100%) today's range may reach
+    pub max_used_ratio: f64,
+    /// Most recently built last
+    closed_days: Mutex<VecDeque<ClosedDaysAtr>>,
+}
+
+/// ATR of the days before today
+#[derive(Debug)]
+struct ClosedDaysAtr {
+    key: ClosedDaysKey,
+    daily_atr: Option<DailyAtr>,
+}
+
+/// Tells histories of the same day apart
+#[derive(Debug, PartialEq)]
+struct ClosedDaysKey {
+    today_key: u64,
+    first: Option<CandleInstance>,
+    /// Last candle before today
+    last_closed: Option<CandleInstance>,
+    /// Number of candles up to the last one before today
+    closed_count: usize,
+}
+
+impl Clone for DailyAtrFilter {
+    fn clone(&self) -> Self {
+        Self {
+            builder: self.builder.clone(),
+            max_used_ratio: self.max_used_ratio,
+            closed_days: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl DailyAtrFilter {
+    pub fn new(builder: DailyAtrBuilder) -> Self {
+        Self {
+            builder,
+            max_used_ratio: DAILY_ATR_FILTER_DEFAULT_MAX_USED_RATIO,
+            closed_days: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Same as [`DailyAtrBuilder::build`], with the ATR of the closed days taken from the cache
+    pub fn get_daily_atr<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<DailyAtr> {
+        let (today, last_closed) = self.builder.get_today(candles)?;
+        let closed_count = match last_closed {
+            Some(last_closed) => {
+                candles.len() - candles.range(last_closed.get_time_key() + 1..).count()
+            }
+            None => 0,
+        };
+
+        let key = ClosedDaysKey {
+            today_key: today.time_key,
+            first: candles.values().next().map(CandleInstance::from_candle),
+            last_closed: last_closed.map(CandleInstance::from_candle),
+            closed_count,
+        };
+
+        let cached = self
+            .lock_closed_days()
+            .iter()
+            .find(|cached| cached.key == key)
+            .map(|cached| cached.daily_atr.clone());
+
+        let daily_atr = match cached {
+            Some(daily_atr) => daily_atr,
+            None => {
+                // Built without holding the lock, so other histories are not blocked meanwhile
+                let daily_atr = self.builder.build(candles);
+
+                let mut closed_days = self.lock_closed_days();
+                if !closed_days.iter().any(|cached| cached.key == key) {
+                    if closed_days.len() >= DAILY_ATR_FILTER_CACHE_SIZE {
+                        closed_days.pop_front();
+                    }
+                    closed_days.push_back(ClosedDaysAtr {
+                        key,
+                        daily_atr: daily_atr.clone(),
+                    });
+                }
+
+                daily_atr
+            }
+        };
+
+        let mut result = daily_atr?;
+        result.today = Some(today);
+        Some(result)
+    }
+
+    fn lock_closed_days(&self) -> MutexGuard<'_, VecDeque<ClosedDaysAtr>> {
+        self.closed_days
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<TCandle: Candle> SignalFilter<TCandle> for DailyAtrFilter {
+    fn name(&self) -> &str {
+        "DailyAtr"
+    }
+
+    fn check(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        _signal: &PatternResult,
+        _accepted: &[FilteredResult],
+    ) -> Result<(), String> {
+        let Some(daily_atr) = self.get_daily_atr(candles) else {
+            return Err("not enough days for the daily ATR".to_string());
+        };
+
+        let used_ratio = daily_atr.get_used_ratio();
+        if used_ratio > self.max_used_ratio {
+            return Err(format!(
+                "{:.0}% of the daily ATR is used",
+                used_ratio * 100.0
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternEvidence, PatternType, SignalDirection};
+    use crate::candle::CandleInstance;
+    use crate::{InstrumentType, TimeKeyFormat};
+
+    // 2025-04-07 00:00 UTC
+    const DAY_START: u64 = 1743984000;
+    const DAY: u64 = 24 * 60 * 60;
+
+    /// Three days with range 2.0 and today with the given range
+    fn make_candles(today_range: f64) -> BTreeMap<u64, CandleInstance> {
+        (0..4)
+            .map(|day| {
+                let time_key = DAY_START + day * DAY + 12 * 60 * 60;
+                let range = if day == 3 { today_range } else { 2.0 };
+                let candle = CandleInstance {
+                    time_key,
+                    open: 100.0,
+                    high: 100.0 + range / 2.0,
+                    low: 100.0 - range / 2.0,
+                    close: 100.0,
+                    volume: 1.0,
+                };
+                (time_key, candle)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn used_up_atr_suppresses_signal() {
+        let filter = DailyAtrFilter::new(DailyAtrBuilder {
+            period: 3,
+            ..DailyAtrBuilder::new(InstrumentType::Crypto, TimeKeyFormat::UnixSeconds)
+        });
+        let signal = PatternResult {
+            name: "CloseRetest".to_string(),
+            direction: SignalDirection::Bullish,
+            description: String::new(),
+            confidence: None,
+            pattern_type: PatternType::CloseRetest,
+            evidence: PatternEvidence::new(0, vec![]),
+        };
+
+        assert!(filter.check(&make_candles(1.0), &signal, &[]).is_ok());
+        assert_eq!(
+            filter.check(&make_candles(1.8), &signal, &[]),
+            Err("90% of the daily ATR is used".to_string())
+        );
+
+        let mut short = make_candles(1.0);
+        short.pop_first();
+        assert!(filter.check(&short, &signal, &[]).is_err());
+    }
+
+    #[test]
+    fn cached_atr_follows_history() {
+        let filter = DailyAtrFilter::new(DailyAtrBuilder {
+            period: 2,
+            ..DailyAtrBuilder::new(InstrumentType::Crypto, TimeKeyFormat::UnixSeconds)
+        });
+
+        // Three candles a day, each one wider than the one before
+        let candles: BTreeMap<u64, CandleInstance> = (0..15)
+            .map(|i| {
+                let time_key = DAY_START + (i / 3) * DAY + (i % 3) * 6 * 60 * 60;
+                let range = 1.0 + (i % 3) as f64 + (i / 3) as f64 * 0.1;
+                let candle = CandleInstance {
+                    time_key,
+                    open: 100.0,
+                    high: 100.0 + range / 2.0,
+                    low: 100.0 - range / 2.0,
+                    close: 100.0,
+                    volume: 1.0,
+                };
+                (time_key, candle)
+            })
+            .collect();
+
+        let to_key = |daily_atr: Option<DailyAtr>| {
+            daily_atr.map(|d| (d.atr.get_value(), d.days_used, d.today))
+        };
+
+        let mut history = BTreeMap::new();
+        for (time_key, candle) in candles.iter() {
+            history.insert(*time_key, candle.clone());
+
+            assert_eq!(
+                to_key(filter.get_daily_atr(&history)),
+                to_key(filter.builder.build(&history))
+            );
+        }
+
+        // Same day, but the oldest day is gone
+        history.pop_first();
+        assert_eq!(
+            to_key(filter.get_daily_atr(&history)),
+            to_key(filter.builder.build(&history))
+        );
+    }
+
+    #[test]
+    fn cached_atr_is_kept_per_history() {
+        let filter = DailyAtrFilter::new(DailyAtrBuilder {
+            period: 3,
+            ..DailyAtrBuilder::new(InstrumentType::Crypto, TimeKeyFormat::UnixSeconds)
+        });
+        let narrow = make_candles(1.0);
+        let wide: BTreeMap<u64, CandleInstance> = make_candles(1.0)
+            .into_iter()
+            .map(|(time_key, candle)| {
+                let candle = CandleInstance {
+                    high: candle.high + 1.0,
+                    low: candle.low - 1.0,
+                    ..candle
+                };
+                (time_key, candle)
+            })
+            .collect();
+
+        // Two symbols scanned in turn keep their own ATR of the closed days
+        for _ in 0..2 {
+            for candles in [&narrow, &wide] {
+                assert_eq!(
+                    filter.get_daily_atr(candles).map(|d| d.atr.get_value()),
+                    filter.builder.build(candles).map(|d| d.atr.get_value())
+                );
+            }
+        }
+
+        assert_eq!(filter.lock_closed_days().len(), 2);
+    }
+}
@@ -0,0 +1,12 @@
+mod signal_filter;
+pub use signal_filter::*;
+mod confidence_filter;
+pub use confidence_filter::*;
+mod session_filter;
+pub use session_filter::*;
+mod trend_filter;
+pub use trend_filter::*;
+mod daily_atr_filter;
+pub use daily_atr_filter::*;
+mod signal_spacing_filter;
+pub use signal_spacing_filter::*;
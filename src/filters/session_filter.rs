@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use super::{FilteredResult, SignalFilter};
+use crate::analyzer::PatternResult;
+use crate::candle::Candle;
+use crate::{TimeKeyFormat, UsMarketMoment};
+
+/// Suppresses signals completed outside the US regular session (see [`UsMarketMoment`]).
+#[derive(Debug, Clone)]
+pub struct RegularSessionFilter {
+    pub key_format: TimeKeyFormat,
+}
+
+impl RegularSessionFilter {
+    pub fn new(key_format: TimeKeyFormat) -> Self {
+        Self { key_format }
+    }
+}
+
+impl<TCandle: Candle> SignalFilter<TCandle> for RegularSessionFilter {
+    fn name(&self) -> &str {
+        "RegularSession"
+    }
+
+    fn check(
+        &self,
+        _candles: &BTreeMap<u64, TCandle>,
+        signal: &PatternResult,
+        _accepted: &[FilteredResult],
+    ) -> Result<(), String> {
+        let time_key = signal.evidence.trigger_time_key;
+        let Some(dt) = self.key_format.to_date_time(time_key) else {
+            return Err(format!("invalid time key {}", time_key));
+        };
+
+        if !UsMarketMoment::from(dt).is_working() {
+            return Err("outside regular session".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternEvidence, PatternType, SignalDirection};
+    use crate::candle::CandleInstance;
+
+    fn check_at(time_key: u64) -> Result<(), String> {
+        let signal = PatternResult {
+            name: "AtrSpike".to_string(),
+            direction: SignalDirection::Bullish,
+            description: String::new(),
+            confidence: None,
+            pattern_type: PatternType::AtrSpike,
+            evidence: PatternEvidence::new(time_key, vec![time_key]),
+        };
+
+        let candles: BTreeMap<u64, CandleInstance> = BTreeMap::new();
+        RegularSessionFilter::new(TimeKeyFormat::YearMonthDayHourMinute).check(
+            &candles,
+            &signal,
+            &[],
+        )
+    }
+
+    #[test]
+    fn only_regular_hours_pass() {
+        // 2025-04-07 (Monday) 10:00 and 08:00 New York
+        assert!(check_at(202504071400).is_ok());
+        assert_eq!(
+            check_at(202504071200),
+            Err("outside regular session".to_string())
+        );
+        assert!(check_at(202504071399).is_err());
+    }
+}
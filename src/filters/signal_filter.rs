@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+
+use crate::analyzer::PatternResult;
+use crate::candle::Candle;
+
+/// Decides whether a pattern result is worth trading.
+pub trait SignalFilter<TCandle: Candle>: Send + Sync {
+    /// Name recorded in the audit of every checked result
+    fn name(&self) -> &str;
+
+    /// `Err` with the reason when the signal is suppressed.
+    ///
+    /// `candles` end with the candle the signal was found on. `accepted` are earlier signals
+    /// which passed the whole pipeline, oldest first.
+    fn check(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        signal: &PatternResult,
+        accepted: &[FilteredResult],
+    ) -> Result<(), String>;
+
+    /// Number of most recent candles `check` looks at. `None` means the whole history is needed.
+    fn lookback(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Outcome of one filter for one signal
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilterCheck {
+    pub filter: String,
+    pub passed: bool,
+    /// Why the signal was suppressed. `None` when it passed
+    pub reason: Option<String>,
+}
+
+/// Pattern result with the outcome of every filter of the pipeline.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilteredResult {
+    /// Time key of the candle the pattern was evaluated on
+    pub time_key: u64,
+    pub result: PatternResult,
+    /// Checks in the order the filters were registered
+    pub checks: Vec<FilterCheck>,
+}
+
+impl FilteredResult {
+    /// `true` if no filter suppressed the signal
+    pub fn is_passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    pub fn get_failed(&self) -> Vec<&FilterCheck> {
+        self.checks.iter().filter(|check| !check.passed).collect()
+    }
+}
+
+/// Filters run one after another. Every filter is run even after one failed,
+/// so the audit shows all the reasons a signal was suppressed.
+pub struct SignalFilterPipeline<TCandle: Candle> {
+    filters: Vec<Box<dyn SignalFilter<TCandle>>>,
+}
+
+impl<TCandle: Candle> Default for SignalFilterPipeline<TCandle> {
+    fn default() -> Self {
+        Self {
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl<TCandle: Candle> SignalFilterPipeline<TCandle> {
+    pub fn new(filters: Vec<Box<dyn SignalFilter<TCandle>>>) -> Self {
+        Self { filters }
+    }
+
+    pub fn register_filter<F: SignalFilter<TCandle> + 'static>(&mut self, filter: F) {
+        self.filters.push(Box::new(filter));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Largest lookback of the filters. `None` if any filter needs the whole history
+    pub fn get_lookback(&self) -> Option<usize> {
+        self.filters
+            .iter()
+            .try_fold(0, |result, filter| Some(result.max(filter.lookback()?)))
+    }
+
+    pub fn apply(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        signal: PatternResult,
+        accepted: &[FilteredResult],
+    ) -> FilteredResult {
+        let checks = self
+            .filters
+            .iter()
+            .map(|filter| {
+                let outcome = filter.check(candles, &signal, accepted);
+                FilterCheck {
+                    filter: filter.name().to_string(),
+                    passed: outcome.is_ok(),
+                    reason: outcome.err(),
+                }
+            })
+            .collect();
+
+        FilteredResult {
+            time_key: get_time_key(candles, &signal),
+            result: signal,
+            checks,
+        }
+    }
+
+    /// [`SignalFilterPipeline::apply`] with `accepted` kept up to date: the signal is added when it passes.
+    ///
+    /// Signals accepted after the last candle are dropped first, so a replay of an older history starts over.
+    /// Signals before the first candle are dropped too, filter lookbacks already treat them as far enough.
+    /// A signal of the same pattern on the same candle, e.g. of a forming candle evaluated again,
+    /// replaces the earlier one instead of suppressing itself. Nothing is recorded without filters.
+    pub fn apply_and_accept(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        signal: PatternResult,
+        accepted: &mut Vec<FilteredResult>,
+    ) -> FilteredResult {
+        if self.is_empty() {
+            return self.apply(candles, signal, &[]);
+        }
+
+        let time_key = get_time_key(candles, &signal);
+        let first_key = candles
+            .first_key_value()
+            .map_or(time_key, |(first_key, _)| *first_key);
+
+        accepted.retain(|previous| {
+            previous.time_key >= first_key
+                && (previous.time_key < time_key
+                    || (previous.time_key == time_key
+                        && previous.result.pattern_type != signal.pattern_type))
+        });
+
+        let result = self.apply(candles, signal, accepted);
+        if result.is_passed() {
+            accepted.push(result.clone());
+        }
+
+        result
+    }
+}
+
+/// Time key of the candle the signal was evaluated on
+fn get_time_key<TCandle: Candle>(candles: &BTreeMap<u64, TCandle>, signal: &PatternResult) -> u64 {
+    candles
+        .last_key_value()
+        .map(|(time_key, _)| *time_key)
+        .unwrap_or(signal.evidence.trigger_time_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternEvidence, PatternType, SignalDirection};
+    use crate::candle::CandleInstance;
+    use crate::filters::MinConfidenceFilter;
+
+    struct BullishOnly;
+
+    impl SignalFilter<CandleInstance> for BullishOnly {
+        fn name(&self) -> &str {
+            "BullishOnly"
+        }
+
+        fn check(
+            &self,
+            _candles: &BTreeMap<u64, CandleInstance>,
+            signal: &PatternResult,
+            _accepted: &[FilteredResult],
+        ) -> Result<(), String> {
+            match signal.direction {
+                SignalDirection::Bullish => Ok(()),
+                _ => Err(format!("{:?} signal", signal.direction)),
+            }
+        }
+    }
+
+    fn make_signal(direction: SignalDirection, confidence: f64) -> PatternResult {
+        PatternResult {
+            name: "Hammer".to_string(),
+            direction,
+            description: String::new(),
+            confidence: Some(confidence),
+            pattern_type: PatternType::Hammer,
+            evidence: PatternEvidence::new(3, vec![3]),
+        }
+    }
+
+    #[test]
+    fn audit_lists_every_failed_filter() {
+        let mut pipeline = SignalFilterPipeline::new(vec![Box::new(BullishOnly)]);
+        pipeline.register_filter(MinConfidenceFilter::new(0.5));
+
+        let candles = BTreeMap::new();
+
+        let passed = pipeline.apply(&candles, make_signal(SignalDirection::Bullish, 0.7), &[]);
+        assert!(passed.is_passed());
+        assert_eq!(passed.checks.len(), 2);
+        assert_eq!(passed.time_key, 3);
+
+        let failed = pipeline.apply(&candles, make_signal(SignalDirection::Bearish, 0.4), &[]);
+        assert!(!failed.is_passed());
+
+        let reasons: Vec<(&str, Option<&str>)> = failed
+            .get_failed()
+            .iter()
+            .map(|check| (check.filter.as_str(), check.reason.as_deref()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                ("BullishOnly", Some("Bearish signal")),
+                ("MinConfidence", Some("confidence 0.40 is below 0.50")),
+            ]
+        );
+    }
+}
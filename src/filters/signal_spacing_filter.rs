@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use super::{FilteredResult, SignalFilter};
+use crate::analyzer::PatternResult;
+use crate::candle::Candle;
+
+/// Suppresses a signal found fewer than `min_candles` candles after the previous accepted one.
+#[derive(Debug, Clone)]
+pub struct SignalSpacingFilter {
+    pub min_candles: usize,
+    /// Measure the distance only to the previous signal of the same pattern type
+    pub same_pattern_only: bool,
+}
+
+impl SignalSpacingFilter {
+    pub fn new(min_candles: usize) -> Self {
+        Self {
+            min_candles,
+            same_pattern_only: false,
+        }
+    }
+}
+
+impl<TCandle: Candle> SignalFilter<TCandle> for SignalSpacingFilter {
+    fn name(&self) -> &str {
+        "SignalSpacing"
+    }
+
+    fn check(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        signal: &PatternResult,
+        accepted: &[FilteredResult],
+    ) -> Result<(), String> {
+        let previous = accepted.iter().rev().find(|previous| {
+            !self.same_pattern_only || previous.result.pattern_type == signal.pattern_type
+        });
+
+        let Some(previous) = previous else {
+            return Ok(());
+        };
+
+        let distance = candles.range(previous.time_key + 1..).count();
+        if distance < self.min_candles {
+            return Err(format!(
+                "{} candles after the previous signal, {} needed",
+                distance, self.min_candles
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// A previous signal older than `min_candles` candles is far enough, wherever it is
+    fn lookback(&self) -> Option<usize> {
+        Some(self.min_candles)
+    }
+}
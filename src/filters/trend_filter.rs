@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use super::{FilteredResult, SignalFilter};
+use crate::analyzer::{PatternResult, SignalDirection};
+use crate::candle::{Candle, CandleInstance};
+use crate::patterns::hhll::{HHLLTrendDetector, TrendDirection};
+use crate::{MultiTimeframeCandles, Timeframe};
+
+/// Suppresses signals against the trend of the last `window_size` candles.
+///
+/// Neutral signals always pass.
+pub struct TrendAlignmentFilter {
+    pub detector: HHLLTrendDetector,
+    pub window_size: usize,
+    /// Let signals through when there is no trend
+    pub allow_sideways: bool,
+}
+
+impl TrendAlignmentFilter {
+    pub fn new(detector: HHLLTrendDetector, window_size: usize) -> Self {
+        Self {
+            detector,
+            window_size,
+            allow_sideways: false,
+        }
+    }
+}
+
+impl<TCandle: Candle + Clone> SignalFilter<TCandle> for TrendAlignmentFilter {
+    fn name(&self) -> &str {
+        "TrendAlignment"
+    }
+
+    fn check(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        signal: &PatternResult,
+        _accepted: &[FilteredResult],
+    ) -> Result<(), String> {
+        let window: BTreeMap<u64, TCandle> = candles
+            .iter()
+            .rev()
+            .take(self.window_size)
+            .map(|(time_key, candle)| (*time_key, candle.clone()))
+            .collect();
+
+        let trend = if window.len() < self.window_size {
+            None
+        } else {
+            self.detector.detect_trend(&window)
+        };
+
+        check_alignment(&signal.direction, trend, self.allow_sideways)
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.window_size)
+    }
+}
+
+/// Suppresses signals against the trend of a higher timeframe, e.g. 5-minute signals against the daily trend.
+///
+/// Only higher timeframe bars closed by the signal candle are used, see [`MultiTimeframeCandles`].
+pub struct HigherTimeframeTrendFilter {
+    pub candles: MultiTimeframeCandles<CandleInstance>,
+    pub timeframe: Timeframe,
+    pub detector: HHLLTrendDetector,
+    /// Number of closed higher timeframe bars the trend is taken from
+    pub window_size: usize,
+    pub allow_sideways: bool,
+}
+
+impl HigherTimeframeTrendFilter {
+    pub fn new(
+        candles: MultiTimeframeCandles<CandleInstance>,
+        timeframe: Timeframe,
+        detector: HHLLTrendDetector,
+        window_size: usize,
+    ) -> Self {
+        Self {
+            candles,
+            timeframe,
+            detector,
+            window_size,
+            allow_sideways: false,
+        }
+    }
+}
+
+impl<TCandle: Candle> SignalFilter<TCandle> for HigherTimeframeTrendFilter {
+    fn name(&self) -> &str {
+        "HigherTimeframeTrend"
+    }
+
+    fn check(
+        &self,
+        candles: &BTreeMap<u64, TCandle>,
+        signal: &PatternResult,
+        _accepted: &[FilteredResult],
+    ) -> Result<(), String> {
+        let time_key = match candles.last_key_value() {
+            Some((time_key, _)) => *time_key,
+            None => signal.evidence.trigger_time_key,
+        };
+
+        let trend =
+            self.candles
+                .get_trend(self.timeframe, time_key, &self.detector, self.window_size);
+
+        check_alignment(&signal.direction, trend, self.allow_sideways)
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+fn check_alignment(
+    direction: &SignalDirection,
+    trend: Option<TrendDirection>,
+    allow_sideways: bool,
+) -> Result<(), String> {
+    let Some(trend) = trend else {
+        return match direction {
+            SignalDirection::Neutral => Ok(()),
+            _ => Err("not enough candles for the trend".to_string()),
+        };
+    };
+
+    match (direction, trend) {
+        (SignalDirection::Neutral, _)
+        | (SignalDirection::Bullish, TrendDirection::Up)
+        | (SignalDirection::Bearish, TrendDirection::Down) => Ok(()),
+        (_, TrendDirection::Sideways) if allow_sideways => Ok(()),
+        _ => Err(format!("{:?} signal in {:?} trend", direction, trend)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternEvidence, PatternType};
+    use crate::{Resampler, TimeKeyFormat};
+
+    // 2025-04-07 00:00 UTC
+    const DAY_START: u64 = 1743984000;
+    const HOUR: u64 = 60 * 60;
+
+    fn make_signal(direction: SignalDirection) -> PatternResult {
+        PatternResult {
+            name: "CloseRetest".to_string(),
+            direction,
+            description: String::new(),
+            confidence: None,
+            pattern_type: PatternType::CloseRetest,
+            evidence: PatternEvidence::new(0, vec![]),
+        }
+    }
+
+    /// Hourly candles rising by `step` every hour
+    fn make_candles(hours: u64, step: f64) -> BTreeMap<u64, CandleInstance> {
+        (0..hours)
+            .map(|i| {
+                let time_key = DAY_START + i * HOUR;
+                let price = 100.0 + i as f64 * step;
+                let candle = CandleInstance {
+                    time_key,
+                    open: price,
+                    high: price + 0.5,
+                    low: price - 0.5,
+                    close: price,
+                    volume: 1.0,
+                };
+                (time_key, candle)
+            })
+            .collect()
+    }
+
+    fn make_detector() -> HHLLTrendDetector {
+        HHLLTrendDetector {
+            min_confirmation_ratio: 1.0,
+        }
+    }
+
+    #[test]
+    fn signal_against_trend_is_suppressed() {
+        let candles = make_candles(5, 1.0);
+        let filter = TrendAlignmentFilter::new(make_detector(), 4);
+
+        assert!(
+            filter
+                .check(&candles, &make_signal(SignalDirection::Bullish), &[])
+                .is_ok()
+        );
+        assert_eq!(
+            filter.check(&candles, &make_signal(SignalDirection::Bearish), &[]),
+            Err("Bearish signal in Up trend".to_string())
+        );
+        assert!(
+            filter
+                .check(&candles, &make_signal(SignalDirection::Neutral), &[])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn sideways_passes_only_when_allowed() {
+        let candles = make_candles(5, 0.0);
+        let strict = TrendAlignmentFilter::new(make_detector(), 4);
+        let relaxed = TrendAlignmentFilter {
+            allow_sideways: true,
+            ..TrendAlignmentFilter::new(make_detector(), 4)
+        };
+
+        let signal = make_signal(SignalDirection::Bullish);
+        assert!(strict.check(&candles, &signal, &[]).is_err());
+        assert!(relaxed.check(&candles, &signal, &[]).is_ok());
+    }
+
+    #[test]
+    fn higher_timeframe_uses_closed_bars_only() {
+        let hourly = make_candles(12, 1.0);
+        let mut candles =
+            MultiTimeframeCandles::new(Timeframe::Hours(1), TimeKeyFormat::UnixSeconds, hourly);
+        let resampler = Resampler::new(
+            Timeframe::Hours(1),
+            Timeframe::Hours(4),
+            TimeKeyFormat::UnixSeconds,
+        )
+        .unwrap();
+        candles.add_resampled(&resampler).unwrap();
+
+        let filter =
+            HigherTimeframeTrendFilter::new(candles, Timeframe::Hours(4), make_detector(), 2);
+        let signal = make_signal(SignalDirection::Bullish);
+
+        // At 06:00 only the 00:00 bar is closed, at 07:00 the 04:00 bar closes too
+        let at_six = make_candles(7, 1.0);
+        assert!(filter.check(&at_six, &signal, &[]).is_err());
+
+        let at_seven = make_candles(8, 1.0);
+        assert!(filter.check(&at_seven, &signal, &[]).is_ok());
+    }
+}
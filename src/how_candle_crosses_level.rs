@@ -1,6 +1,7 @@
 use crate::candle::Candle;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HowCandleCrossesLevel {
     CandleIsBelow { distance: f64 },
     CandleTouchesBelow,
@@ -1,5 +1,177 @@
+use crate::sessions::Exchange;
+use crate::{calc_points_from_accuracy, round_to_digits};
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InstrumentType {
     UsStocks,
     Crypto,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AssetClass {
+    UsStocks,
+    Crypto,
+    Forex,
+    Futures,
+    Cfd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstrumentSpec {
+    /// `None` for specs built from price digits only
+    pub asset_class: Option<AssetClass>,
+    /// Number of price digits
+    pub digits: u32,
+    /// Minimal price change. Prices are rounded to it
+    pub tick_size: f64,
+    /// Minimal volume change. Position sizes are rounded down to it
+    pub lot_step: f64,
+    /// Money value of one price unit for one lot (e.g. 50.0 for ES futures, 100_000.0 for a standard forex lot)
+    pub contract_multiplier: f64,
+    /// Exchange whose trading hours the instrument follows. `None` if unknown
+    pub session: Option<Exchange>,
+}
+
+impl InstrumentSpec {
+    /// Spec which knows only price digits, without an asset class or a session.
+    /// Tick size is one point (10^-digits)
+    pub fn from_digits(digits: u32) -> Self {
+        Self {
+            asset_class: None,
+            digits,
+            tick_size: calc_points_from_accuracy(digits),
+            lot_step: 1.0,
+            contract_multiplier: 1.0,
+            session: None,
+        }
+    }
+
+    pub fn us_stock(digits: u32) -> Self {
+        Self {
+            asset_class: Some(AssetClass::UsStocks),
+            session: Some(Exchange::Nyse),
+            ..Self::from_digits(digits)
+        }
+    }
+
+    pub fn crypto(digits: u32, lot_step: f64) -> Self {
+        Self {
+            asset_class: Some(AssetClass::Crypto),
+            lot_step,
+            session: Some(Exchange::Crypto),
+            ..Self::from_digits(digits)
+        }
+    }
+
+    /// Lots of 100_000 units with a 0.01 lot step
+    pub fn forex(digits: u32) -> Self {
+        Self {
+            asset_class: Some(AssetClass::Forex),
+            lot_step: 0.01,
+            contract_multiplier: 100_000.0,
+            session: Some(Exchange::Forex),
+            ..Self::from_digits(digits)
+        }
+    }
+
+    pub fn future(digits: u32, tick_size: f64, contract_multiplier: f64) -> Self {
+        Self {
+            asset_class: Some(AssetClass::Futures),
+            tick_size,
+            contract_multiplier,
+            session: Some(Exchange::CmeGlobex),
+            ..Self::from_digits(digits)
+        }
+    }
+
+    pub fn cfd(digits: u32, lot_step: f64, contract_multiplier: f64, session: Exchange) -> Self {
+        Self {
+            asset_class: Some(AssetClass::Cfd),
+            lot_step,
+            contract_multiplier,
+            session: Some(session),
+            ..Self::from_digits(digits)
+        }
+    }
+
+    /// Rounds a price to the nearest tick
+    pub fn round_price(&self, price: f64) -> f64 {
+        if self.tick_size <= 0.0 {
+            return round_to_digits(price, self.digits);
+        }
+
+        round_to_digits(
+            (price / self.tick_size).round() * self.tick_size,
+            self.digits,
+        )
+    }
+
+    /// Rounds a volume down to the lot step
+    pub fn round_volume(&self, volume: f64) -> f64 {
+        if self.lot_step <= 0.0 {
+            return volume;
+        }
+
+        // Compensates for float errors like 4.9999999 lots which should be 5
+        let steps = (volume / self.lot_step + 1e-9).floor();
+        steps * self.lot_step
+    }
+
+    pub fn get_instrument_type(&self) -> Option<InstrumentType> {
+        match self.asset_class? {
+            AssetClass::UsStocks => Some(InstrumentType::UsStocks),
+            AssetClass::Crypto => Some(InstrumentType::Crypto),
+            _ => None,
+        }
+    }
+}
+
+/// Digits-only spec, see [`InstrumentSpec::from_digits`]
+impl From<u32> for InstrumentSpec {
+    fn from(digits: u32) -> Self {
+        Self::from_digits(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_spec_uses_point_as_tick() {
+        let spec: InstrumentSpec = 2.into();
+        assert_eq!(spec.round_price(105.0949), 105.09);
+        assert_eq!(spec.round_price(105.095), 105.1);
+        assert_eq!(spec.asset_class, None);
+        assert_eq!(spec.session, None);
+        assert!(spec.get_instrument_type().is_none());
+
+        let stock = InstrumentSpec::us_stock(2);
+        assert_eq!(stock.asset_class, Some(AssetClass::UsStocks));
+        assert!(matches!(
+            stock.get_instrument_type(),
+            Some(InstrumentType::UsStocks)
+        ));
+    }
+
+    #[test]
+    fn future_rounds_to_tick_size() {
+        let es = InstrumentSpec::future(2, 0.25, 50.0);
+
+        assert_eq!(es.round_price(5012.37), 5012.25);
+        assert_eq!(es.round_price(5012.38), 5012.5);
+        assert_eq!(es.round_volume(2.7), 2.0);
+    }
+
+    #[test]
+    fn forex_rounds_volume_to_lot_step() {
+        let eur_usd = InstrumentSpec::forex(5);
+
+        assert_eq!(eur_usd.round_volume(0.1299), 0.12);
+        assert_eq!(eur_usd.round_price(1.083456), 1.08346);
+        assert!(eur_usd.get_instrument_type().is_none());
+    }
+}
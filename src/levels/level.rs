@@ -0,0 +1,59 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelKind {
+    Support,
+    Resistance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelOrigin {
+    /// Level was set by hand (or computed outside of the crate)
+    Manual,
+    /// High of a candle which dominates its neighbours (fractal)
+    SwingHigh,
+    /// Low of a candle which dominates its neighbours (fractal)
+    SwingLow,
+    /// Swing level which was broken and then held from the other side
+    Mirror,
+    RoundNumber,
+    /// Several candles with highs or lows at exactly the same price
+    TouchCluster,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Level {
+    pub price: f64,
+    pub kind: LevelKind,
+    pub origin: LevelOrigin,
+    /// Time key of the candle which formed the level
+    pub created_at: u64,
+    /// How many candles touched the level since it was formed
+    pub touches: usize,
+    /// Level strength (0.0 to 1.0) based on touches, age and reaction size.
+    ///
+    /// `None` means strength was not calculated (e.g. manual levels).
+    pub strength: Option<f64>,
+}
+
+impl Level {
+    pub fn new(price: f64, kind: LevelKind, origin: LevelOrigin, created_at: u64) -> Self {
+        Self {
+            price,
+            kind,
+            origin,
+            created_at,
+            touches: 0,
+            strength: None,
+        }
+    }
+
+    pub fn support(price: f64, created_at: u64) -> Self {
+        Self::new(price, LevelKind::Support, LevelOrigin::Manual, created_at)
+    }
+
+    pub fn resistance(price: f64, created_at: u64) -> Self {
+        Self::new(price, LevelKind::Resistance, LevelOrigin::Manual, created_at)
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::BTreeMap;
+
+use super::{Level, LevelKind, LevelOrigin};
+use crate::candle::Candle;
+use crate::{AtrCalculator, AtrSmoothing};
+
+pub const LD_DEFAULT_SWING_STRENGTH: usize = 2;
+pub const LD_DEFAULT_MIN_CLUSTER_TOUCHES: usize = 2;
+pub const LD_DEFAULT_ATR_PERIOD: usize = 14;
+pub const LD_DEFAULT_REACTION_PERIOD: usize = 5;
+pub const LD_DEFAULT_AGE_HALF_LIFE: usize = 100;
+pub const LD_DEFAULT_MIRROR_ATR_FRACTION: f64 = 0.1;
+pub const LD_DEFAULT_MAX_ROUND_NUMBER_LEVELS: usize = 100;
+
+/// Number of touches which gives the maximum touch score
+const MAX_SCORED_TOUCHES: f64 = 5.0;
+/// Reaction (in ATRs) which gives the maximum reaction score
+const MAX_SCORED_REACTION_ATR: f64 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct LevelDetector {
+    /// Number of candles on each side a swing high/low has to dominate
+    pub swing_strength: usize,
+    /// Max distance between a price and a level to count as a touch. `0.0` means exact touches only
+    pub touch_tolerance: f64,
+    /// Minimal number of candles with the same high or low to form a touch cluster
+    pub min_cluster_touches: usize,
+    /// Step of round number levels (e.g. `1.0` or `0.5`). `None` disables round numbers
+    pub round_number_step: Option<f64>,
+    /// Round numbers are skipped when the step would give more levels inside the traded range
+    pub max_round_number_levels: usize,
+    pub atr_period: usize,
+    /// Number of candles after a touch used to measure the reaction
+    pub reaction_period: usize,
+    /// Age (in candles) at which the age component of strength halves
+    pub age_half_life: usize,
+    /// Max distance (as a fraction of ATR) of a retest from a broken level to make it a mirror level
+    pub mirror_atr_fraction: f64,
+}
+
+impl Default for LevelDetector {
+    fn default() -> Self {
+        Self {
+            swing_strength: LD_DEFAULT_SWING_STRENGTH,
+            touch_tolerance: 0.0,
+            min_cluster_touches: LD_DEFAULT_MIN_CLUSTER_TOUCHES,
+            round_number_step: None,
+            max_round_number_levels: LD_DEFAULT_MAX_ROUND_NUMBER_LEVELS,
+            atr_period: LD_DEFAULT_ATR_PERIOD,
+            reaction_period: LD_DEFAULT_REACTION_PERIOD,
+            age_half_life: LD_DEFAULT_AGE_HALF_LIFE,
+            mirror_atr_fraction: LD_DEFAULT_MIRROR_ATR_FRACTION,
+        }
+    }
+}
+
+impl LevelDetector {
+    /// Finds all kinds of levels and returns them sorted by price.
+    pub fn detect<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<Level> {
+        let mut result = self.find_swing_levels(candles);
+        result.extend(self.find_mirror_levels(candles));
+        result.extend(self.find_round_number_levels(candles));
+        result.extend(self.find_touch_clusters(candles));
+
+        result.sort_by(|a, b| a.price.total_cmp(&b.price));
+        result
+    }
+
+    /// Swing highs become resistance, swing lows become support.
+    pub fn find_swing_levels<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<Level> {
+        let candle_vec: Vec<&T> = candles.values().collect();
+        let atr = self.calc_atr(candles);
+        let mut result = Vec::new();
+
+        for index in find_swing_highs(&candle_vec, self.swing_strength) {
+            let price = candle_vec[index].get_high();
+            result.push(self.make_level(
+                &candle_vec,
+                atr,
+                price,
+                LevelKind::Resistance,
+                LevelOrigin::SwingHigh,
+                index,
+            ));
+        }
+
+        for index in find_swing_lows(&candle_vec, self.swing_strength) {
+            let price = candle_vec[index].get_low();
+            result.push(self.make_level(
+                &candle_vec,
+                atr,
+                price,
+                LevelKind::Support,
+                LevelOrigin::SwingLow,
+                index,
+            ));
+        }
+
+        result
+    }
+
+    /// Swing levels which were broken by a close and then retested and held from the other side.
+    pub fn find_mirror_levels<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<Level> {
+        let candle_vec: Vec<&T> = candles.values().collect();
+        let atr = self.calc_atr(candles);
+        let tolerance = self.touch_tolerance.max(atr * self.mirror_atr_fraction);
+        let mut result = Vec::new();
+
+        for index in find_swing_highs(&candle_vec, self.swing_strength) {
+            let price = candle_vec[index].get_high();
+            let retest =
+                find_mirror_retest(&candle_vec, index, price, tolerance, LevelKind::Support);
+            if let Some(retest_index) = retest {
+                result.push(self.make_level(
+                    &candle_vec,
+                    atr,
+                    price,
+                    LevelKind::Support,
+                    LevelOrigin::Mirror,
+                    retest_index,
+                ));
+            }
+        }
+
+        for index in find_swing_lows(&candle_vec, self.swing_strength) {
+            let price = candle_vec[index].get_low();
+            let retest =
+                find_mirror_retest(&candle_vec, index, price, tolerance, LevelKind::Resistance);
+            if let Some(retest_index) = retest {
+                result.push(self.make_level(
+                    &candle_vec,
+                    atr,
+                    price,
+                    LevelKind::Resistance,
+                    LevelOrigin::Mirror,
+                    retest_index,
+                ));
+            }
+        }
+
+        result
+    }
+
+    /// Round numbers inside the traded range. Levels above the last close are resistance, below are support.
+    ///
+    /// Returns nothing when the step is too small for the range, see [`LevelDetector::max_round_number_levels`].
+    pub fn find_round_number_levels<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<Level> {
+        let Some(step) = self.round_number_step else {
+            return Vec::new();
+        };
+
+        if !step.is_finite() || step <= 0.0 {
+            return Vec::new();
+        }
+
+        let candle_vec: Vec<&T> = candles.values().collect();
+        let Some(last) = candle_vec.last() else {
+            return Vec::new();
+        };
+
+        let atr = self.calc_atr(candles);
+        let min_low = candle_vec
+            .iter()
+            .map(|c| c.get_low())
+            .fold(f64::MAX, f64::min);
+        let max_high = candle_vec
+            .iter()
+            .map(|c| c.get_high())
+            .fold(f64::MIN, f64::max);
+        let last_close = last.get_close();
+
+        let first_index = (min_low / step).ceil();
+        let last_index = (max_high / step).floor();
+        if last_index - first_index + 1.0 > self.max_round_number_levels as f64 {
+            return Vec::new();
+        }
+
+        let mut result = Vec::new();
+        let mut step_index = first_index as i64;
+
+        while step_index <= last_index as i64 {
+            let price = step_index as f64 * step;
+
+            let kind = if price > last_close {
+                LevelKind::Resistance
+            } else {
+                LevelKind::Support
+            };
+
+            let created_index = candle_vec
+                .iter()
+                .position(|c| c.get_low() <= price && price <= c.get_high())
+                .unwrap_or(0);
+
+            result.push(self.make_level(
+                &candle_vec,
+                atr,
+                price,
+                kind,
+                LevelOrigin::RoundNumber,
+                created_index,
+            ));
+            step_index += 1;
+        }
+
+        result
+    }
+
+    /// Groups of candles with highs (resistance) or lows (support) at the same price,
+    /// the same exact touches `find_bpu_bsu` looks for.
+    pub fn find_touch_clusters<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<Level> {
+        let candle_vec: Vec<&T> = candles.values().collect();
+        let atr = self.calc_atr(candles);
+
+        let highs: Vec<(f64, usize)> = candle_vec
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.get_high(), i))
+            .collect();
+        let lows: Vec<(f64, usize)> = candle_vec
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.get_low(), i))
+            .collect();
+
+        let mut result = Vec::new();
+
+        for (prices, kind) in [(highs, LevelKind::Resistance), (lows, LevelKind::Support)] {
+            for cluster in cluster_prices(prices, self.touch_tolerance) {
+                if cluster.len() < self.min_cluster_touches {
+                    continue;
+                }
+
+                let price = cluster.iter().map(|(p, _)| p).sum::<f64>() / cluster.len() as f64;
+                let created_index = cluster.iter().map(|(_, i)| *i).min().unwrap_or(0);
+                let mut level = self.make_level(
+                    &candle_vec,
+                    atr,
+                    price,
+                    kind,
+                    LevelOrigin::TouchCluster,
+                    created_index,
+                );
+                level.touches = level.touches.max(cluster.len());
+                result.push(level);
+            }
+        }
+
+        result
+    }
+
+    fn calc_atr<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> f64 {
+        let period = self.atr_period.min(candles.len()).max(1);
+
+        AtrCalculator::new(period, AtrSmoothing::Sma)
+            .calc_recent(candles)
+            .map_or(0.0, |atr| atr.get_value())
+    }
+
+    fn make_level<T: Candle>(
+        &self,
+        candles: &[&T],
+        atr: f64,
+        price: f64,
+        kind: LevelKind,
+        origin: LevelOrigin,
+        created_index: usize,
+    ) -> Level {
+        let mut touches = 0;
+        let mut max_reaction: f64 = 0.0;
+
+        for (index, candle) in candles.iter().enumerate().skip(created_index) {
+            if !self.is_touch(*candle, price) {
+                continue;
+            }
+
+            touches += 1;
+
+            let after = &candles[index + 1..candles.len().min(index + 1 + self.reaction_period)];
+            let reaction = match kind {
+                LevelKind::Resistance => after
+                    .iter()
+                    .map(|c| price - c.get_low())
+                    .fold(0.0, f64::max),
+                LevelKind::Support => after
+                    .iter()
+                    .map(|c| c.get_high() - price)
+                    .fold(0.0, f64::max),
+            };
+            max_reaction = max_reaction.max(reaction);
+        }
+
+        let age = candles.len() - 1 - created_index;
+
+        let mut level = Level::new(price, kind, origin, candles[created_index].get_time_key());
+        level.touches = touches;
+        level.strength = Some(self.calc_strength(touches, age, max_reaction, atr));
+        level
+    }
+
+    fn is_touch(&self, candle: &impl Candle, price: f64) -> bool {
+        (candle.get_high() - price).abs() <= self.touch_tolerance
+            || (candle.get_low() - price).abs() <= self.touch_tolerance
+    }
+
+    fn calc_strength(&self, touches: usize, age: usize, reaction: f64, atr: f64) -> f64 {
+        let touch_score = (touches as f64 / MAX_SCORED_TOUCHES).min(1.0);
+        let age_score = 0.5f64.powf(age as f64 / self.age_half_life.max(1) as f64);
+        let reaction_score = if atr > 0.0 {
+            (reaction / atr / MAX_SCORED_REACTION_ATR).min(1.0)
+        } else {
+            0.0
+        };
+
+        ((touch_score + age_score + reaction_score) / 3.0).clamp(0.0, 1.0)
+    }
+}
+
+/// Indexes of candles whose high is above the highs of `strength` candles on each side.
+pub fn find_swing_highs(candles: &[&impl Candle], strength: usize) -> Vec<usize> {
+    find_swings(candles, strength, |c| c.get_high())
+}
+
+/// Indexes of candles whose low is below the lows of `strength` candles on each side.
+pub fn find_swing_lows(candles: &[&impl Candle], strength: usize) -> Vec<usize> {
+    find_swings(candles, strength, |c| -c.get_low())
+}
+
+fn find_swings<T: Candle>(
+    candles: &[&T],
+    strength: usize,
+    value: impl Fn(&T) -> f64,
+) -> Vec<usize> {
+    let strength = strength.max(1);
+    let mut result = Vec::new();
+
+    if candles.len() < strength * 2 + 1 {
+        return result;
+    }
+
+    for index in strength..candles.len() - strength {
+        let current = value(candles[index]);
+
+        let left_ok = candles[index - strength..index]
+            .iter()
+            .all(|c| value(c) < current);
+        let right_ok = candles[index + 1..=index + strength]
+            .iter()
+            .all(|c| value(c) <= current);
+
+        if left_ok && right_ok {
+            result.push(index);
+        }
+    }
+
+    result
+}
+
+fn find_mirror_retest(
+    candles: &[&impl Candle],
+    swing_index: usize,
+    price: f64,
+    tolerance: f64,
+    new_kind: LevelKind,
+) -> Option<usize> {
+    let break_index = (swing_index + 1..candles.len()).find(|i| {
+        let close = candles[*i].get_close();
+        match new_kind {
+            LevelKind::Support => close > price,
+            LevelKind::Resistance => close < price,
+        }
+    })?;
+
+    (break_index + 1..candles.len()).find(|i| {
+        let candle = candles[*i];
+        match new_kind {
+            LevelKind::Support => {
+                (candle.get_low() - price).abs() <= tolerance && candle.get_close() > price
+            }
+            LevelKind::Resistance => {
+                (candle.get_high() - price).abs() <= tolerance && candle.get_close() < price
+            }
+        }
+    })
+}
+
+fn cluster_prices(mut prices: Vec<(f64, usize)>, tolerance: f64) -> Vec<Vec<(f64, usize)>> {
+    prices.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut result: Vec<Vec<(f64, usize)>> = Vec::new();
+
+    for item in prices {
+        match result.last_mut() {
+            Some(cluster) if item.0 - cluster[0].0 <= tolerance => cluster.push(item),
+            _ => result.push(vec![item]),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_candles;
+
+    #[test]
+    fn finds_swing_high_and_low() {
+        let candles = make_candles(&[
+            (10.0, 11.0, 9.0, 10.5),
+            (10.5, 12.0, 10.0, 11.5),
+            (11.5, 15.0, 11.0, 14.0),
+            (14.0, 14.5, 12.0, 12.5),
+            (12.5, 13.0, 8.0, 8.5),
+            (8.5, 10.0, 8.2, 9.5),
+            (9.5, 11.0, 9.0, 10.5),
+        ]);
+
+        let detector = LevelDetector::default();
+        let levels = detector.find_swing_levels(&candles);
+
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].price, 15.0);
+        assert_eq!(levels[0].kind, LevelKind::Resistance);
+        assert_eq!(levels[0].origin, LevelOrigin::SwingHigh);
+        assert_eq!(levels[0].created_at, 2);
+        assert_eq!(levels[1].price, 8.0);
+        assert_eq!(levels[1].kind, LevelKind::Support);
+        assert_eq!(levels[1].created_at, 4);
+        assert!(levels.iter().all(|l| l.strength.is_some()));
+    }
+
+    #[test]
+    fn finds_exact_touch_cluster() {
+        let candles = make_candles(&[
+            (5.0, 7.0, 4.0, 6.0),
+            (4.0, 6.0, 3.0, 5.0),
+            (5.0, 7.0, 4.5, 6.0),
+            (5.0, 7.0, 4.2, 6.0),
+        ]);
+
+        let detector = LevelDetector::default();
+        let levels = detector.find_touch_clusters(&candles);
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].price, 7.0);
+        assert_eq!(levels[0].kind, LevelKind::Resistance);
+        assert_eq!(levels[0].touches, 3);
+        assert_eq!(levels[0].created_at, 0);
+    }
+
+    #[test]
+    fn finds_mirror_level() {
+        let candles = make_candles(&[
+            (10.0, 11.0, 9.0, 10.5),
+            (10.5, 11.5, 10.0, 11.0),
+            (11.0, 12.0, 10.5, 11.2),
+            (11.2, 11.6, 10.8, 11.0),
+            (11.0, 11.4, 10.6, 10.8),
+            (10.8, 12.5, 10.7, 12.4),
+            (12.4, 13.0, 12.2, 12.8),
+            (12.8, 12.9, 12.0, 12.6),
+        ]);
+
+        let detector = LevelDetector::default();
+        let levels = detector.find_mirror_levels(&candles);
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].price, 12.0);
+        assert_eq!(levels[0].kind, LevelKind::Support);
+        assert_eq!(levels[0].origin, LevelOrigin::Mirror);
+        assert_eq!(levels[0].created_at, 7);
+    }
+
+    #[test]
+    fn finds_round_numbers_inside_range() {
+        let candles = make_candles(&[(99.2, 101.3, 98.7, 100.4), (100.4, 102.1, 100.1, 101.5)]);
+
+        let detector = LevelDetector {
+            round_number_step: Some(1.0),
+            ..Default::default()
+        };
+        let levels = detector.find_round_number_levels(&candles);
+        let prices: Vec<f64> = levels.iter().map(|l| l.price).collect();
+
+        assert_eq!(prices, vec![99.0, 100.0, 101.0, 102.0]);
+        assert_eq!(levels[0].kind, LevelKind::Support);
+        assert_eq!(levels[3].kind, LevelKind::Resistance);
+        assert_eq!(levels[3].created_at, 1);
+    }
+
+    #[test]
+    fn too_small_round_number_step_is_rejected() {
+        let candles = make_candles(&[(99.2, 101.3, 98.7, 100.4), (100.4, 102.1, 100.1, 101.5)]);
+
+        let detector = LevelDetector {
+            round_number_step: Some(1e-9),
+            ..Default::default()
+        };
+        assert!(detector.find_round_number_levels(&candles).is_empty());
+
+        let detector = LevelDetector {
+            round_number_step: Some(1.0),
+            max_round_number_levels: 3,
+            ..Default::default()
+        };
+        assert!(detector.find_round_number_levels(&candles).is_empty());
+    }
+
+    #[test]
+    fn more_touches_give_stronger_level() {
+        let detector = LevelDetector::default();
+
+        let weak = detector.calc_strength(1, 10, 1.0, 1.0);
+        let strong = detector.calc_strength(4, 10, 1.0, 1.0);
+        let old = detector.calc_strength(4, 500, 1.0, 1.0);
+
+        assert!(strong > weak);
+        assert!(strong > old);
+    }
+}
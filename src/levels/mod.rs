@@ -0,0 +1,4 @@
+mod level;
+pub use level::*;
+mod level_detector;
+pub use level_detector::*;
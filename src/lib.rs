@@ -1,18 +1,47 @@
 pub mod analyzer;
+pub mod backtest;
 pub mod candle;
+pub mod filters;
+pub mod levels;
+pub mod loaders;
 mod how_candle_crosses_level;
 pub mod patterns;
+pub mod sessions;
 pub use how_candle_crosses_level::*;
 mod instrument_types;
 pub mod stop_loss;
+pub mod volume;
 pub use instrument_types::*;
 mod atr;
 pub use atr::*;
+mod atr_calculator;
+pub use atr_calculator::*;
 mod dt_utils;
 pub use dt_utils::*;
+mod time_key;
+pub use time_key::*;
+mod daily_atr;
+pub use daily_atr::*;
+mod timeframe;
+pub use timeframe::*;
+mod resample;
+pub use resample::*;
+mod multi_timeframe;
+pub use multi_timeframe::*;
+mod multi_symbol_scanner;
+pub use multi_symbol_scanner::*;
+mod candle_validator;
+pub use candle_validator::*;
+mod trade_setup;
+pub use trade_setup::*;
+mod csv_io;
+pub use csv_io::*;
 mod math;
 pub use math::*;
 
+#[cfg(test)]
+mod test_candles;
+
 pub fn in_range(value: f64, lower_bound: f64, upper_bound: f64) -> bool {
     value >= lower_bound && value <= upper_bound
 }
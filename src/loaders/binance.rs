@@ -0,0 +1,273 @@
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::TimeKeyFormat;
+use crate::candle::CandleInstance;
+
+use super::{LoadError, LoadResult, parse_number};
+
+/// Loads a Binance kline dump: the JSON array returned by `GET /api/v3/klines`.
+///
+/// Each kline is `[open_time_ms, "open", "high", "low", "close", "volume", close_time_ms, ...]`.
+/// Prices may be strings or numbers. Fields after volume are ignored.
+/// Malformed klines are reported with the line they start at. A JSON syntax error stops loading.
+pub fn load_binance_klines_json(src: &str, key_format: TimeKeyFormat) -> LoadResult {
+    let mut result = LoadResult::default();
+    let mut parser = JsonParser::new(src.trim_start_matches('\u{feff}'));
+
+    if let Err(err) = parser.parse_klines(&mut result, key_format) {
+        result.errors.push(err);
+    }
+
+    result
+}
+
+enum JsonValue {
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Other,
+}
+
+impl JsonValue {
+    fn as_text(&self) -> Option<&str> {
+        match self {
+            JsonValue::Number(value) | JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Just enough JSON to read arrays of numbers and strings
+struct JsonParser<'s> {
+    src: &'s [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl<'s> JsonParser<'s> {
+    fn new(src: &'s str) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn parse_klines(
+        &mut self,
+        result: &mut LoadResult,
+        key_format: TimeKeyFormat,
+    ) -> Result<(), LoadError> {
+        self.expect(b'[')?;
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return self.expect_end();
+        }
+
+        loop {
+            self.skip_whitespace();
+            let line = self.line;
+            let value = self.parse_value()?;
+            result.push(line, to_candle(&value, key_format));
+
+            match self.next_token() {
+                Some(b',') => continue,
+                Some(b']') => break,
+                _ => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+
+        self.expect_end()
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, LoadError> {
+        match self.peek() {
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+
+                loop {
+                    items.push(self.parse_value()?);
+
+                    match self.next_token() {
+                        Some(b',') => continue,
+                        Some(b']') => return Ok(JsonValue::Array(items)),
+                        _ => return Err(self.error("Expected ',' or ']'")),
+                    }
+                }
+            }
+            Some(b'"') => {
+                self.pos += 1;
+                let start = self.pos;
+
+                while let Some(&c) = self.src.get(self.pos) {
+                    match c {
+                        b'"' => {
+                            let value =
+                                String::from_utf8_lossy(&self.src[start..self.pos]).to_string();
+                            self.pos += 1;
+                            return Ok(JsonValue::String(value));
+                        }
+                        b'\\' => return Err(self.error("Escaped strings are not supported")),
+                        b'\n' => return Err(self.error("Unterminated string")),
+                        _ => self.pos += 1,
+                    }
+                }
+
+                Err(self.error("Unterminated string"))
+            }
+            Some(c) if c == b'-' || c.is_ascii_digit() => {
+                let start = self.pos;
+
+                while let Some(&c) = self.src.get(self.pos) {
+                    if !(c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.' | b'e' | b'E')) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+
+                let value = String::from_utf8_lossy(&self.src[start..self.pos]).to_string();
+                Ok(JsonValue::Number(value))
+            }
+            Some(_) => {
+                for literal in ["true", "false", "null"] {
+                    if self.src[self.pos..].starts_with(literal.as_bytes()) {
+                        self.pos += literal.len();
+                        return Ok(JsonValue::Other);
+                    }
+                }
+
+                Err(self.error("Unexpected character"))
+            }
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    /// Next non-whitespace byte without consuming it
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.src.get(self.pos).copied()
+    }
+
+    fn next_token(&mut self) -> Option<u8> {
+        let result = self.peek();
+        self.pos += 1;
+        result
+    }
+
+    fn expect(&mut self, token: u8) -> Result<(), LoadError> {
+        if self.next_token() == Some(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", token as char)))
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), LoadError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("Unexpected data after klines array")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.src.get(self.pos) {
+            match c {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> LoadError {
+        LoadError::new(self.line, message)
+    }
+}
+
+fn to_candle(value: &JsonValue, key_format: TimeKeyFormat) -> Result<CandleInstance, String> {
+    let JsonValue::Array(fields) = value else {
+        return Err("Kline is not an array".to_string());
+    };
+
+    if fields.len() < 6 {
+        return Err(format!(
+            "Kline has {} fields, expected at least 6",
+            fields.len()
+        ));
+    }
+
+    let text = |index: usize, name: &str| -> Result<&str, String> {
+        fields[index]
+            .as_text()
+            .ok_or_else(|| format!("Invalid {}", name))
+    };
+
+    let open_time = text(0, "open time")?;
+    let unix_microseconds = open_time
+        .parse::<i64>()
+        .ok()
+        .and_then(|ms| ms.checked_mul(1_000))
+        .ok_or_else(|| format!("Invalid open time '{}'", open_time))?;
+
+    Ok(CandleInstance {
+        time_key: key_format.from_date_time(DateTimeAsMicroseconds::new(unix_microseconds)),
+        open: parse_number(text(1, "open")?, "open")?,
+        high: parse_number(text(2, "high")?, "high")?,
+        low: parse_number(text(3, "low")?, "low")?,
+        close: parse_number(text(4, "close")?, "close")?,
+        volume: parse_number(text(5, "volume")?, "volume")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_klines() {
+        let src = r#"[
+  [1704205800000, "42000.10", "42100.00", "41950.50", "42080.00", "12.5", 1704205859999, "525000.0", 310, "6.1", "256000.0", "0"],
+  [1704205860000, 42080.0, 42120.0, 42050.0, 42110.0, 8.25, 1704205919999, "347000.0", 200, "4.0", "168000.0", "0"]
+]"#;
+
+        let candles = load_binance_klines_json(src, TimeKeyFormat::UnixSeconds)
+            .into_result()
+            .unwrap();
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[&1704205800].open, 42000.10);
+        assert_eq!(candles[&1704205860].volume, 8.25);
+    }
+
+    #[test]
+    fn reports_malformed_kline_line() {
+        let src = "[\n[1704205800000, \"1\", \"2\", \"0.5\", \"1.5\", \"10\"],\n[1704205860000, \"1\", \"2\"],\n[1704205920000, \"1\", null, \"0.5\", \"1.5\", \"10\"]\n]";
+
+        let result = load_binance_klines_json(src, TimeKeyFormat::UnixSeconds);
+
+        assert_eq!(result.candles.len(), 1);
+        let lines: Vec<_> = result.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+    }
+
+    #[test]
+    fn stops_on_syntax_error() {
+        let src = "[\n[1704205800000, \"1\", \"2\", \"0.5\", \"1.5\", \"10\"]\n[1704205860000]\n]";
+
+        let result = load_binance_klines_json(src, TimeKeyFormat::UnixSeconds);
+
+        assert_eq!(result.candles.len(), 1);
+        assert_eq!(
+            result.errors,
+            vec![LoadError::new(3, "Expected ',' or ']'")]
+        );
+    }
+}
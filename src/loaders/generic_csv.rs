@@ -0,0 +1,287 @@
+use chrono_tz::Tz;
+
+use crate::TimeKeyFormat;
+use crate::candle::CandleInstance;
+use crate::csv_io::parse_records;
+
+use super::{LoadError, LoadResult, TimestampFormat, parse_number};
+
+/// Column of a CSV file, by position or by header name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvColumn {
+    /// 0-based position
+    Index(usize),
+    /// Header name, compared case-insensitively. Requires a header row
+    Name(String),
+}
+
+impl From<usize> for CsvColumn {
+    fn from(index: usize) -> Self {
+        CsvColumn::Index(index)
+    }
+}
+
+impl From<&str> for CsvColumn {
+    fn from(name: &str) -> Self {
+        CsvColumn::Name(name.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvColumns {
+    /// Timestamp column. Holds only the time of day when `date` is set
+    pub time: CsvColumn,
+    /// Date column of files which keep date and time apart. The values are joined with a space before parsing
+    pub date: Option<CsvColumn>,
+    pub open: CsvColumn,
+    pub high: CsvColumn,
+    pub low: CsvColumn,
+    pub close: CsvColumn,
+    /// `None` loads zero volume
+    pub volume: Option<CsvColumn>,
+}
+
+impl Default for CsvColumns {
+    /// `time,open,high,low,close,volume` by position
+    fn default() -> Self {
+        Self {
+            time: CsvColumn::Index(0),
+            date: None,
+            open: CsvColumn::Index(1),
+            high: CsvColumn::Index(2),
+            low: CsvColumn::Index(3),
+            close: CsvColumn::Index(4),
+            volume: Some(CsvColumn::Index(5)),
+        }
+    }
+}
+
+/// Loads OHLCV rows of any delimited text file.
+#[derive(Debug, Clone)]
+pub struct GenericCsvLoader {
+    pub columns: CsvColumns,
+    pub delimiter: char,
+    /// Skip the first row. Required for [`CsvColumn::Name`] columns
+    pub has_header: bool,
+    pub timestamp_format: TimestampFormat,
+    /// Time zone of local timestamps
+    pub time_zone: Tz,
+    /// Format of the resulting time keys
+    pub key_format: TimeKeyFormat,
+}
+
+struct ResolvedColumns {
+    time: usize,
+    date: Option<usize>,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    volume: Option<usize>,
+}
+
+impl GenericCsvLoader {
+    pub fn new(timestamp_format: TimestampFormat, key_format: TimeKeyFormat) -> Self {
+        Self {
+            columns: CsvColumns::default(),
+            delimiter: ',',
+            has_header: true,
+            timestamp_format,
+            time_zone: Tz::UTC,
+            key_format,
+        }
+    }
+
+    pub fn load(&self, src: &str) -> LoadResult {
+        let src = src.trim_start_matches('\u{feff}');
+
+        let records = match parse_records(src, self.delimiter) {
+            Ok(records) => records,
+            Err(err) => return LoadResult::from_error(err),
+        };
+
+        let mut records = records.into_iter();
+
+        let header = if self.has_header {
+            records.next()
+        } else {
+            None
+        };
+
+        let columns = match self.resolve_columns(header.as_ref()) {
+            Ok(columns) => columns,
+            Err(err) => return LoadResult::from_error(err),
+        };
+
+        let mut result = LoadResult::default();
+
+        for (line, fields) in records {
+            result.push(line, self.parse_row(&fields, &columns));
+        }
+
+        result
+    }
+
+    fn resolve_columns(
+        &self,
+        header: Option<&(usize, Vec<String>)>,
+    ) -> Result<ResolvedColumns, LoadError> {
+        let resolve = |column: &CsvColumn| -> Result<usize, LoadError> {
+            match column {
+                CsvColumn::Index(index) => Ok(*index),
+                CsvColumn::Name(name) => {
+                    let (line, fields) = header.ok_or_else(|| {
+                        LoadError::new(1, format!("Column '{}' needs a header row", name))
+                    })?;
+
+                    fields
+                        .iter()
+                        .position(|f| f.trim().eq_ignore_ascii_case(name))
+                        .ok_or_else(|| {
+                            LoadError::new(*line, format!("No column '{}' in header", name))
+                        })
+                }
+            }
+        };
+
+        Ok(ResolvedColumns {
+            time: resolve(&self.columns.time)?,
+            date: self.columns.date.as_ref().map(resolve).transpose()?,
+            open: resolve(&self.columns.open)?,
+            high: resolve(&self.columns.high)?,
+            low: resolve(&self.columns.low)?,
+            close: resolve(&self.columns.close)?,
+            volume: self.columns.volume.as_ref().map(resolve).transpose()?,
+        })
+    }
+
+    fn parse_row(
+        &self,
+        fields: &[String],
+        columns: &ResolvedColumns,
+    ) -> Result<CandleInstance, String> {
+        let get = |index: usize| -> Result<&str, String> {
+            fields
+                .get(index)
+                .map(|f| f.as_str())
+                .ok_or_else(|| format!("Missing column {}, row has {} fields", index, fields.len()))
+        };
+
+        let time = match columns.date {
+            Some(date) => format!("{} {}", get(date)?.trim(), get(columns.time)?.trim()),
+            None => get(columns.time)?.to_string(),
+        };
+
+        let dt = self.timestamp_format.parse(&time, self.time_zone)?;
+
+        Ok(CandleInstance {
+            time_key: self.key_format.from_date_time(dt),
+            open: parse_number(get(columns.open)?, "open")?,
+            high: parse_number(get(columns.high)?, "high")?,
+            low: parse_number(get(columns.low)?, "low")?,
+            close: parse_number(get(columns.close)?, "close")?,
+            volume: match columns.volume {
+                Some(volume) => parse_number(get(volume)?, "volume")?,
+                None => 0.0,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_named_columns_with_split_date_and_time() {
+        let src = "Symbol;Date;Time;Open;High;Low;Close;Vol\n\
+                   AAPL;2024-01-02;09:30;185.5;186.0;185.1;185.9;12000\n\
+                   AAPL;2024-01-02;09:31;185.9;186.2;185.7;186.1;8000\n";
+
+        let loader = GenericCsvLoader {
+            columns: CsvColumns {
+                time: "time".into(),
+                date: Some("date".into()),
+                open: "open".into(),
+                high: "high".into(),
+                low: "low".into(),
+                close: "close".into(),
+                volume: Some("vol".into()),
+            },
+            delimiter: ';',
+            time_zone: chrono_tz::America::New_York,
+            ..GenericCsvLoader::new(
+                TimestampFormat::Pattern("%Y-%m-%d %H:%M".to_string()),
+                TimeKeyFormat::YearMonthDayHourMinute,
+            )
+        };
+
+        let result = loader.load(src);
+
+        assert!(result.is_ok());
+        let keys: Vec<_> = result.candles.keys().copied().collect();
+        // New York 09:30 is 14:30 UTC
+        assert_eq!(keys, vec![202401021430, 202401021431]);
+        assert_eq!(result.candles[&202401021431].volume, 8000.0);
+    }
+
+    #[test]
+    fn reports_bad_rows_and_keeps_the_rest() {
+        let src = "1704205800,1,2,0.5,1.5,10\n\
+                   1704205860,1,x,0.5,1.5,10\n\
+                   1704205920,1,2,0.5\n\
+                   1704205980,1,2,0.5,1.5,10\n";
+
+        let loader = GenericCsvLoader {
+            has_header: false,
+            ..GenericCsvLoader::new(TimestampFormat::UnixSeconds, TimeKeyFormat::UnixSeconds)
+        };
+
+        let result = loader.load(src);
+
+        assert_eq!(result.candles.len(), 2);
+        let lines: Vec<_> = result.errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3]);
+        assert!(result.errors[0].message.contains("high"));
+    }
+
+    #[test]
+    fn duplicate_time_key_keeps_first_candle() {
+        let src = "1704205800,1,2,0.5,1.5,10\n\
+                   1704205800,1,3,0.5,2.5,20\n\
+                   1704205860,1,2,0.5,1.5,10\n";
+
+        let loader = GenericCsvLoader {
+            has_header: false,
+            ..GenericCsvLoader::new(TimestampFormat::UnixSeconds, TimeKeyFormat::UnixSeconds)
+        };
+
+        let result = loader.load(src);
+
+        assert_eq!(result.candles.len(), 2);
+        assert_eq!(result.candles[&1704205800].close, 1.5);
+        assert_eq!(
+            result.errors,
+            vec![LoadError::new(2, "Duplicate time key 1704205800")]
+        );
+    }
+
+    #[test]
+    fn unknown_column_name_fails() {
+        let loader = GenericCsvLoader {
+            columns: CsvColumns {
+                volume: Some("volume".into()),
+                ..CsvColumns::default()
+            },
+            ..GenericCsvLoader::new(TimestampFormat::UnixSeconds, TimeKeyFormat::UnixSeconds)
+        };
+
+        let result = loader.load("t,o,h,l,c\n1,1,1,1,1\n");
+
+        assert!(result.candles.is_empty());
+        assert_eq!(
+            result.errors,
+            vec![LoadError::new(1, "No column 'volume' in header")]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::candle::CandleInstance;
+use crate::csv_io::CsvError;
+
+/// Row which could not be loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    /// 1-based line number of the row. For binary formats the record number, 0 for the file header
+    pub line: usize,
+    pub message: String,
+}
+
+impl LoadError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<CsvError> for LoadError {
+    fn from(err: CsvError) -> Self {
+        Self::new(err.line, err.message)
+    }
+}
+
+/// Candles loaded from a file.
+///
+/// Invalid rows are skipped and reported in `errors`, so one broken row does not discard the whole file.
+/// A row with the time key of an already loaded candle is reported too, the first candle is kept.
+/// Errors which make the rest of the file unreadable (bad header, truncated binary data) stop loading.
+#[derive(Debug, Clone, Default)]
+pub struct LoadResult {
+    pub candles: BTreeMap<u64, CandleInstance>,
+    pub errors: Vec<LoadError>,
+}
+
+impl LoadResult {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Candles if every row was loaded, otherwise the first error
+    pub fn into_result(self) -> Result<BTreeMap<u64, CandleInstance>, LoadError> {
+        match self.errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(self.candles),
+        }
+    }
+
+    pub(crate) fn push(&mut self, line: usize, candle: Result<CandleInstance, String>) {
+        match candle {
+            Ok(candle) => {
+                if self.candles.contains_key(&candle.time_key) {
+                    let message = format!("Duplicate time key {}", candle.time_key);
+                    self.errors.push(LoadError::new(line, message));
+                    return;
+                }
+
+                self.candles.insert(candle.time_key, candle);
+            }
+            Err(message) => self.errors.push(LoadError::new(line, message)),
+        }
+    }
+
+    pub(crate) fn from_error(err: impl Into<LoadError>) -> Self {
+        Self {
+            candles: BTreeMap::new(),
+            errors: vec![err.into()],
+        }
+    }
+}
+
+/// Parses a price or volume field
+pub(crate) fn parse_number(value: &str, name: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}'", name, value))
+}
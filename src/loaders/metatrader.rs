@@ -0,0 +1,313 @@
+use chrono_tz::Tz;
+use rust_extensions::chrono::DateTime;
+
+use crate::TimeKeyFormat;
+use crate::candle::CandleInstance;
+
+use super::{
+    CsvColumn, CsvColumns, GenericCsvLoader, LoadError, LoadResult, TimestampFormat, from_local,
+};
+
+const HST_HEADER_SIZE: usize = 148;
+const HST_V400_RECORD_SIZE: usize = 44;
+const HST_V401_RECORD_SIZE: usize = 60;
+
+/// Loads MetaTrader 4/5 exports.
+///
+/// MetaTrader writes bar times in the broker server time, so `time_zone` should be the server
+/// time zone (often `EET` for brokers which follow New York close).
+#[derive(Debug, Clone)]
+pub struct MetaTraderLoader {
+    pub time_zone: Tz,
+    /// Format of the resulting time keys
+    pub key_format: TimeKeyFormat,
+}
+
+impl MetaTraderLoader {
+    pub fn new(time_zone: Tz, key_format: TimeKeyFormat) -> Self {
+        Self {
+            time_zone,
+            key_format,
+        }
+    }
+
+    /// Loads an MT4 History Center CSV (`2024.01.02,09:30,open,high,low,close,volume`, no header)
+    /// or an MT5 bars export (`<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>`).
+    ///
+    /// MT5 real volume is used when the file has any, tick volume otherwise.
+    pub fn load_csv(&self, src: &str) -> LoadResult {
+        let src = src.trim_start_matches('\u{feff}');
+        let first_line = src
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or_default();
+
+        if !first_line.starts_with("<DATE>") {
+            return self.mt4_csv_loader().load(src);
+        }
+
+        let delimiter = if first_line.contains('\t') { '\t' } else { ',' };
+        let has_time = first_line.contains("<TIME>");
+
+        let result = self.mt5_csv_loader(delimiter, has_time, "<VOL>").load(src);
+        let has_real_volume = result.candles.values().any(|c| c.volume > 0.0);
+
+        if has_real_volume || !first_line.contains("<TICKVOL>") {
+            return result;
+        }
+
+        self.mt5_csv_loader(delimiter, has_time, "<TICKVOL>")
+            .load(src)
+    }
+
+    /// Loads an MT4 `.hst` history file (format versions 400 and 401)
+    pub fn load_hst(&self, data: &[u8]) -> LoadResult {
+        if data.len() < HST_HEADER_SIZE {
+            return LoadResult::from_error(LoadError::new(0, "File is shorter than HST header"));
+        }
+
+        let version = read_i32(data, 0);
+        let record_size = match version {
+            400 => HST_V400_RECORD_SIZE,
+            401 => HST_V401_RECORD_SIZE,
+            _ => {
+                return LoadResult::from_error(LoadError::new(
+                    0,
+                    format!("Unsupported HST version {}", version),
+                ));
+            }
+        };
+
+        let mut result = LoadResult::default();
+        let records = data[HST_HEADER_SIZE..].chunks(record_size);
+
+        for (index, record) in records.enumerate() {
+            let line = index + 1;
+
+            if record.len() < record_size {
+                result.errors.push(LoadError::new(line, "Truncated record"));
+                break;
+            }
+
+            let candle = if version == 400 {
+                self.parse_hst_v400(record)
+            } else {
+                self.parse_hst_v401(record)
+            };
+
+            result.push(line, candle);
+        }
+
+        result
+    }
+
+    fn mt4_csv_loader(&self) -> GenericCsvLoader {
+        GenericCsvLoader {
+            columns: CsvColumns {
+                time: CsvColumn::Index(1),
+                date: Some(CsvColumn::Index(0)),
+                open: CsvColumn::Index(2),
+                high: CsvColumn::Index(3),
+                low: CsvColumn::Index(4),
+                close: CsvColumn::Index(5),
+                volume: Some(CsvColumn::Index(6)),
+            },
+            delimiter: ',',
+            has_header: false,
+            timestamp_format: TimestampFormat::Pattern("%Y.%m.%d %H:%M".to_string()),
+            time_zone: self.time_zone,
+            key_format: self.key_format,
+        }
+    }
+
+    fn mt5_csv_loader(&self, delimiter: char, has_time: bool, volume: &str) -> GenericCsvLoader {
+        // Daily and higher exports have no <TIME> column
+        let (time, date, pattern) = if has_time {
+            ("<TIME>", Some("<DATE>".into()), "%Y.%m.%d %H:%M:%S")
+        } else {
+            ("<DATE>", None, "%Y.%m.%d")
+        };
+
+        GenericCsvLoader {
+            columns: CsvColumns {
+                time: time.into(),
+                date,
+                open: "<OPEN>".into(),
+                high: "<HIGH>".into(),
+                low: "<LOW>".into(),
+                close: "<CLOSE>".into(),
+                volume: Some(volume.into()),
+            },
+            delimiter,
+            has_header: true,
+            timestamp_format: TimestampFormat::Pattern(pattern.to_string()),
+            time_zone: self.time_zone,
+            key_format: self.key_format,
+        }
+    }
+
+    fn parse_hst_v400(&self, record: &[u8]) -> Result<CandleInstance, String> {
+        // time: i32, then open, low, high, close, volume as f64
+        Ok(CandleInstance {
+            time_key: self.hst_time_key(read_i32(record, 0) as i64)?,
+            open: read_f64(record, 4),
+            low: read_f64(record, 12),
+            high: read_f64(record, 20),
+            close: read_f64(record, 28),
+            volume: read_f64(record, 36),
+        })
+    }
+
+    fn parse_hst_v401(&self, record: &[u8]) -> Result<CandleInstance, String> {
+        // time: i64, open, high, low, close: f64, tick_volume: i64, spread: i32, real_volume: i64
+        let tick_volume = read_i64(record, 40);
+        let real_volume = read_i64(record, 52);
+
+        Ok(CandleInstance {
+            time_key: self.hst_time_key(read_i64(record, 0))?,
+            open: read_f64(record, 8),
+            high: read_f64(record, 16),
+            low: read_f64(record, 24),
+            close: read_f64(record, 32),
+            volume: if real_volume > 0 {
+                real_volume as f64
+            } else {
+                tick_volume as f64
+            },
+        })
+    }
+
+    fn hst_time_key(&self, seconds: i64) -> Result<u64, String> {
+        let local = DateTime::from_timestamp(seconds, 0)
+            .ok_or_else(|| format!("Invalid bar time {}", seconds))?
+            .naive_utc();
+
+        Ok(self
+            .key_format
+            .from_date_time(from_local(local, self.time_zone)?))
+    }
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_f64(data: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_hst(version: i32, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0u8; HST_HEADER_SIZE];
+        data[0..4].copy_from_slice(&version.to_le_bytes());
+
+        for record in records {
+            data.extend_from_slice(record);
+        }
+
+        data
+    }
+
+    fn make_v400_record(
+        time: i32,
+        open: f64,
+        low: f64,
+        high: f64,
+        close: f64,
+        volume: f64,
+    ) -> Vec<u8> {
+        let mut record = time.to_le_bytes().to_vec();
+        for value in [open, low, high, close, volume] {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        record
+    }
+
+    fn make_v401_record(time: i64, ohlc: [f64; 4], tick_volume: i64, real_volume: i64) -> Vec<u8> {
+        let mut record = time.to_le_bytes().to_vec();
+        for value in ohlc {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        record.extend_from_slice(&tick_volume.to_le_bytes());
+        record.extend_from_slice(&3i32.to_le_bytes());
+        record.extend_from_slice(&real_volume.to_le_bytes());
+        record
+    }
+
+    #[test]
+    fn loads_mt4_csv() {
+        let src = "2024.01.02,09:30,1.1000,1.1010,1.0990,1.1005,123\n\
+                   2024.01.02,09:31,1.1005,1.1012,1.1001,1.1008,98\n";
+
+        let loader = MetaTraderLoader::new(Tz::UTC, TimeKeyFormat::YearMonthDayHourMinute);
+        let candles = loader.load_csv(src).into_result().unwrap();
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[&202401020930].high, 1.1010);
+        assert_eq!(candles[&202401020931].volume, 98.0);
+    }
+
+    #[test]
+    fn loads_mt5_csv_with_tick_volume_fallback() {
+        let src = "<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>\n\
+                   2024.01.02\t11:30:00\t1.1000\t1.1010\t1.0990\t1.1005\t321\t0\t2\n";
+
+        // Server time EET (UTC+2 in winter)
+        let loader = MetaTraderLoader::new(chrono_tz::EET, TimeKeyFormat::YearMonthDayHourMinute);
+        let candles = loader.load_csv(src).into_result().unwrap();
+
+        assert_eq!(candles[&202401020930].volume, 321.0);
+    }
+
+    #[test]
+    fn loads_hst_v400() {
+        // 2024-01-02 09:30 server time
+        let data = make_hst(
+            400,
+            &[make_v400_record(1704187800, 1.1, 1.09, 1.11, 1.105, 50.0)],
+        );
+
+        let loader = MetaTraderLoader::new(Tz::UTC, TimeKeyFormat::YearMonthDayHourMinute);
+        let candles = loader.load_hst(&data).into_result().unwrap();
+
+        let candle = &candles[&202401020930];
+        assert_eq!(candle.low, 1.09);
+        assert_eq!(candle.high, 1.11);
+        assert_eq!(candle.volume, 50.0);
+    }
+
+    #[test]
+    fn loads_hst_v401_and_reports_truncated_record() {
+        let mut data = make_hst(
+            401,
+            &[
+                make_v401_record(1704187800, [1.1, 1.11, 1.09, 1.105], 40, 0),
+                make_v401_record(1704187860, [1.105, 1.12, 1.1, 1.11], 40, 7000),
+            ],
+        );
+        data.extend_from_slice(&[0u8; 10]);
+
+        let loader = MetaTraderLoader::new(Tz::UTC, TimeKeyFormat::UnixSeconds);
+        let result = loader.load_hst(&data);
+
+        assert_eq!(result.candles[&1704187800].volume, 40.0);
+        assert_eq!(result.candles[&1704187860].volume, 7000.0);
+        assert_eq!(result.errors, vec![LoadError::new(3, "Truncated record")]);
+    }
+
+    #[test]
+    fn rejects_unknown_hst_version() {
+        let result = MetaTraderLoader::new(Tz::UTC, TimeKeyFormat::UnixSeconds)
+            .load_hst(&make_hst(500, &[]));
+
+        assert!(result.candles.is_empty());
+        assert_eq!(result.errors.len(), 1);
+    }
+}
@@ -0,0 +1,12 @@
+mod load_result;
+pub use load_result::*;
+mod timestamp_format;
+pub use timestamp_format::*;
+mod generic_csv;
+pub use generic_csv::*;
+mod metatrader;
+pub use metatrader::*;
+mod tradingview;
+pub use tradingview::*;
+mod binance;
+pub use binance::*;
@@ -0,0 +1,122 @@
+use chrono_tz::Tz;
+use rust_extensions::chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+/// How timestamps are written in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    UnixSeconds,
+    UnixMilliseconds,
+    UnixMicroseconds,
+    /// ISO 8601 with offset, e.g. `2024-01-02T09:30:00-05:00`
+    Rfc3339,
+    /// chrono `strftime` pattern of a local time, e.g. `%Y-%m-%d %H:%M:%S`.
+    /// Date-only patterns like `%Y%m%d` give midnight
+    Pattern(String),
+}
+
+impl TimestampFormat {
+    /// Local times are read in `time_zone`. Unix and RFC 3339 timestamps ignore it
+    pub fn parse(&self, value: &str, time_zone: Tz) -> Result<DateTimeAsMicroseconds, String> {
+        let value = value.trim();
+
+        match self {
+            TimestampFormat::UnixSeconds => parse_unix(value, 1_000_000),
+            TimestampFormat::UnixMilliseconds => parse_unix(value, 1_000),
+            TimestampFormat::UnixMicroseconds => parse_unix(value, 1),
+            TimestampFormat::Rfc3339 => DateTime::parse_from_rfc3339(value)
+                .map(|dt| DateTimeAsMicroseconds::new(dt.timestamp_micros()))
+                .map_err(|err| format!("Invalid timestamp '{}': {}", value, err)),
+            TimestampFormat::Pattern(pattern) => {
+                let local = match NaiveDateTime::parse_from_str(value, pattern) {
+                    Ok(local) => local,
+                    Err(_) => NaiveDate::parse_from_str(value, pattern)
+                        .map_err(|err| format!("Invalid timestamp '{}': {}", value, err))?
+                        .and_hms_opt(0, 0, 0)
+                        .unwrap(),
+                };
+
+                from_local(local, time_zone)
+            }
+        }
+    }
+}
+
+/// Converts a local time to an instant. Times skipped by a DST change are rejected,
+/// ambiguous ones resolve to the earlier instant
+pub(crate) fn from_local(
+    local: NaiveDateTime,
+    time_zone: Tz,
+) -> Result<DateTimeAsMicroseconds, String> {
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|dt| DateTimeAsMicroseconds::new(dt.timestamp_micros()))
+        .ok_or_else(|| format!("Local time {} does not exist in {}", local, time_zone))
+}
+
+fn parse_unix(value: &str, multiplier: i64) -> Result<DateTimeAsMicroseconds, String> {
+    // Some exports write unix time as a float, e.g. `1704205800.0`
+    let parsed = match value.parse::<i64>() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && v.fract() == 0.0)
+            .map(|v| v as i64),
+    };
+
+    parsed
+        .and_then(|v| v.checked_mul(multiplier))
+        .map(DateTimeAsMicroseconds::new)
+        .ok_or_else(|| format!("Invalid timestamp '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_pattern_in_time_zone() {
+        let format = TimestampFormat::Pattern("%Y-%m-%d %H:%M".to_string());
+
+        let dt = format
+            .parse("2024-01-02 09:30", chrono_tz::America::New_York)
+            .unwrap();
+
+        // 14:30 UTC
+        assert_eq!(dt.unix_microseconds, 1_704_205_800_000_000);
+    }
+
+    #[test]
+    fn parses_date_only_pattern() {
+        let format = TimestampFormat::Pattern("%Y%m%d".to_string());
+
+        let dt = format.parse("20240102", Tz::UTC).unwrap();
+
+        assert_eq!(dt.unix_microseconds, 1_704_153_600_000_000);
+    }
+
+    #[test]
+    fn unix_and_rfc3339_agree() {
+        let unix = TimestampFormat::UnixMilliseconds
+            .parse("1704205800000", Tz::UTC)
+            .unwrap();
+        let iso = TimestampFormat::Rfc3339
+            .parse("2024-01-02T09:30:00-05:00", Tz::UTC)
+            .unwrap();
+
+        assert_eq!(unix.unix_microseconds, iso.unix_microseconds);
+    }
+
+    #[test]
+    fn rejects_time_in_dst_gap() {
+        let format = TimestampFormat::Pattern("%Y-%m-%d %H:%M".to_string());
+
+        assert!(
+            format
+                .parse("2024-03-10 02:30", chrono_tz::America::New_York)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::TimeKeyFormat;
+
+use super::{CsvColumns, GenericCsvLoader, LoadResult, TimestampFormat};
+
+/// Loads a TradingView "Export chart data" CSV.
+///
+/// The header is `time,open,high,low,close` followed by an optional `Volume` column and indicator columns,
+/// which are ignored. Times are either unix seconds or ISO 8601 with offset, depending on the export settings.
+pub fn load_tradingview_csv(src: &str, key_format: TimeKeyFormat) -> LoadResult {
+    let src = src.trim_start_matches('\u{feff}');
+    let mut lines = src.lines().filter(|l| !l.trim().is_empty());
+
+    let header = lines.next().unwrap_or_default();
+    let has_volume = header
+        .split(',')
+        .any(|name| name.trim().eq_ignore_ascii_case("volume"));
+
+    let is_unix_time = lines
+        .next()
+        .and_then(|l| l.split(',').next())
+        .map(|time| time.trim().chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(true);
+
+    let timestamp_format = if is_unix_time {
+        TimestampFormat::UnixSeconds
+    } else {
+        TimestampFormat::Rfc3339
+    };
+
+    let loader = GenericCsvLoader {
+        columns: CsvColumns {
+            time: "time".into(),
+            date: None,
+            open: "open".into(),
+            high: "high".into(),
+            low: "low".into(),
+            close: "close".into(),
+            volume: has_volume.then(|| "volume".into()),
+        },
+        ..GenericCsvLoader::new(timestamp_format, key_format)
+    };
+
+    loader.load(src)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_unix_time_export() {
+        let src = "time,open,high,low,close,Volume,MA\n\
+                   1704205800,185.5,186,185.1,185.9,12000,185.2\n\
+                   1704205860,185.9,186.2,185.7,186.1,8000,\n";
+
+        let candles = load_tradingview_csv(src, TimeKeyFormat::UnixSeconds)
+            .into_result()
+            .unwrap();
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[&1704205860].volume, 8000.0);
+    }
+
+    #[test]
+    fn loads_iso_time_export_without_volume() {
+        let src = "time,open,high,low,close\n\
+                   2024-01-02T09:30:00-05:00,185.5,186,185.1,185.9\n";
+
+        let candles = load_tradingview_csv(src, TimeKeyFormat::YearMonthDayHourMinute)
+            .into_result()
+            .unwrap();
+
+        let candle = &candles[&202401021430];
+        assert_eq!(candle.close, 185.9);
+        assert_eq!(candle.volume, 0.0);
+    }
+}
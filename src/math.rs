@@ -1,5 +1,8 @@
-pub fn calc_points_tolerance(digits: u32, points_tolerance: u32) -> f64 {
-    let point_tolerance = calc_points_from_accuracy(digits) * (points_tolerance as f64) ; 
+use crate::InstrumentSpec;
+
+/// Price distance of `points_tolerance` ticks
+pub fn calc_points_tolerance(spec: impl Into<InstrumentSpec>, points_tolerance: u32) -> f64 {
+    let point_tolerance = spec.into().tick_size * (points_tolerance as f64) ; 
     point_tolerance
 }
 
@@ -7,7 +10,12 @@ pub fn calc_points_from_accuracy(digits: u32) -> f64 {
     f64::powi(10.0, - (digits as i32)) 
 }
 
-pub fn round_to_precision(value: f64, digits: u32) -> f64 {
+/// Rounds a price to the instrument tick size
+pub fn round_to_precision(value: f64, spec: impl Into<InstrumentSpec>) -> f64 {
+    spec.into().round_price(value)
+}
+
+pub fn round_to_digits(value: f64, digits: u32) -> f64 {
     let factor = 10f64.powi(digits as i32);
     (value * factor).round() / factor
 }
@@ -27,4 +35,11 @@ mod tests {
         let result = calc_points_tolerance(0, 10); // 10^0 * 10 = 10.0
         assert!((result - 10.0).abs() < f64::EPSILON);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_calc_point_tolerance_with_tick_size() {
+        let spec = InstrumentSpec::future(2, 0.25, 50.0);
+        let result = calc_points_tolerance(spec, 2);
+        assert!((result - 0.5).abs() < f64::EPSILON);
+    }
+}
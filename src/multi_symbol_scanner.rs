@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use crate::analyzer::{CandleAnalyzer, LevelPatternResult};
+use crate::candle::Candle;
+use crate::filters::FilteredResult;
+use crate::levels::Level;
+
+/// Candles and levels of one symbol
+#[derive(Debug, Clone)]
+pub struct SymbolScanInput<'a, TCandle: Candle> {
+    pub symbol: String,
+    pub candles: &'a BTreeMap<u64, TCandle>,
+    pub levels: &'a [Level],
+}
+
+/// Results of one symbol, strongest first
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolScanResult {
+    pub symbol: String,
+    pub results: Vec<LevelPatternResult>,
+    /// Best confidence among the results. Results without a confidence count as `0.0`
+    pub score: f64,
+}
+
+/// Runs one [`CandleAnalyzer`] over many symbols on a pool of threads.
+///
+/// Each symbol is analyzed with [`CandleAnalyzer::analyze_levels`], so the filters of the analyzer apply.
+/// Signals accepted for a symbol are kept between scans and are not shared with other symbols.
+/// Symbols without results are left out.
+pub struct MultiSymbolScanner<TCandle: Candle> {
+    pub analyzer: CandleAnalyzer<TCandle>,
+    /// Number of worker threads. `0` means the available parallelism
+    pub threads: usize,
+    accepted: Mutex<BTreeMap<String, Vec<FilteredResult>>>,
+}
+
+impl<TCandle: Candle + Sync> MultiSymbolScanner<TCandle> {
+    pub fn new(analyzer: CandleAnalyzer<TCandle>) -> Self {
+        Self {
+            analyzer,
+            threads: 0,
+            accepted: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Results grouped by symbol, ranked by score, then by number of results and by symbol
+    pub fn scan(&self, inputs: &[SymbolScanInput<TCandle>]) -> Vec<SymbolScanResult> {
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            threads => threads,
+        }
+        .min(inputs.len())
+        .max(1);
+
+        let next = AtomicUsize::new(0);
+
+        let mut result: Vec<SymbolScanResult> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut found = Vec::new();
+
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(input) = inputs.get(index) else {
+                                break;
+                            };
+
+                            if let Some(symbol_result) = self.scan_symbol(input) {
+                                found.push(symbol_result);
+                            }
+                        }
+
+                        found
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("scanner worker panicked"))
+                .collect()
+        });
+
+        result.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(b.results.len().cmp(&a.results.len()))
+                .then(a.symbol.cmp(&b.symbol))
+        });
+
+        result
+    }
+
+    fn scan_symbol(&self, input: &SymbolScanInput<TCandle>) -> Option<SymbolScanResult> {
+        let mut accepted = self
+            .lock_accepted()
+            .remove(&input.symbol)
+            .unwrap_or_default();
+        let mut results = self
+            .analyzer
+            .analyze_levels(input.candles, input.levels, &mut accepted);
+        self.lock_accepted().insert(input.symbol.clone(), accepted);
+
+        if results.is_empty() {
+            return None;
+        }
+
+        results.sort_by(|a, b| get_confidence(b).total_cmp(&get_confidence(a)));
+
+        Some(SymbolScanResult {
+            symbol: input.symbol.clone(),
+            score: get_confidence(&results[0]),
+            results,
+        })
+    }
+
+    fn lock_accepted(&self) -> MutexGuard<'_, BTreeMap<String, Vec<FilteredResult>>> {
+        self.accepted.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn get_confidence(result: &LevelPatternResult) -> f64 {
+    result.result.confidence.unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::PatternType;
+    use crate::candle::CandleInstance;
+    use crate::filters::SignalSpacingFilter;
+    use crate::patterns::{AtrSpike, Pattern};
+    use crate::test_candles;
+
+    /// Three candles with range 1.0 and a last one with the given range
+    fn make_candles(last_range: f64) -> BTreeMap<u64, CandleInstance> {
+        let prices: Vec<(f64, f64, f64, f64)> = [1.0, 1.0, 1.0, last_range]
+            .iter()
+            .map(|range| (100.0, 100.0 + range, 100.0, 100.0 + range))
+            .collect();
+
+        test_candles::make_candles(&prices)
+    }
+
+    fn make_scanner(threads: usize) -> MultiSymbolScanner<CandleInstance> {
+        let patterns: Vec<Box<dyn Pattern<CandleInstance>>> = vec![Box::new(AtrSpike {
+            period: 3,
+            multiplier: 1.5,
+            atr: Some(1.0),
+        })];
+
+        MultiSymbolScanner {
+            threads,
+            ..MultiSymbolScanner::new(CandleAnalyzer::new(patterns))
+        }
+    }
+
+    #[test]
+    fn analyzer_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<CandleAnalyzer<CandleInstance>>();
+        assert_send_sync::<MultiSymbolScanner<CandleInstance>>();
+    }
+
+    #[test]
+    fn results_are_grouped_and_ranked() {
+        let ranges = [("AAA", 2.0), ("BBB", 1.0), ("CCC", 2.3), ("DDD", 5.0)];
+        let candles: Vec<(&str, BTreeMap<u64, CandleInstance>)> = ranges
+            .iter()
+            .map(|(symbol, range)| (*symbol, make_candles(*range)))
+            .collect();
+
+        let inputs: Vec<SymbolScanInput<CandleInstance>> = candles
+            .iter()
+            .map(|(symbol, candles)| SymbolScanInput {
+                symbol: symbol.to_string(),
+                candles,
+                levels: &[],
+            })
+            .collect();
+
+        for threads in [1, 3, 0] {
+            let result = make_scanner(threads).scan(&inputs);
+
+            let ranked: Vec<(&str, f64)> = result
+                .iter()
+                .map(|r| (r.symbol.as_str(), (r.score * 10.0).round() / 10.0))
+                .collect();
+            assert_eq!(ranked, vec![("DDD", 1.0), ("CCC", 0.8), ("AAA", 0.5)]);
+            assert_eq!(
+                result[0].results[0].result.pattern_type,
+                PatternType::AtrSpike
+            );
+        }
+    }
+
+    #[test]
+    fn accepted_signals_are_kept_per_symbol() {
+        fn make_inputs(
+            candles: &BTreeMap<u64, CandleInstance>,
+        ) -> Vec<SymbolScanInput<'_, CandleInstance>> {
+            ["AAA", "BBB"]
+                .iter()
+                .map(|symbol| SymbolScanInput {
+                    symbol: symbol.to_string(),
+                    candles,
+                    levels: &[],
+                })
+                .collect()
+        }
+
+        let mut scanner = make_scanner(2);
+        scanner
+            .analyzer
+            .register_filter(SignalSpacingFilter::new(3));
+
+        let first = make_candles(5.0);
+        let (last_key, last) = first.last_key_value().unwrap();
+        let mut next = first.clone();
+        next.insert(
+            last_key + 1,
+            CandleInstance {
+                time_key: last_key + 1,
+                ..last.clone()
+            },
+        );
+
+        // The spike of one symbol does not suppress the same spike of another one
+        assert_eq!(scanner.scan(&make_inputs(&first)).len(), 2);
+        // The next spike comes one candle after the previous one of the same symbol
+        assert!(scanner.scan(&make_inputs(&next)).is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::candle::{Candle, CandleInstance};
+use crate::patterns::hhll::{HHLLTrendDetector, TrendDirection};
+use crate::{Atr, AtrCalculator, ResampleError, Resampler, TimeKeyFormat, Timeframe};
+
+/// Candles of one instrument on several timeframes.
+///
+/// Higher timeframe bars are keyed by their start in the same `key_format` as the base candles.
+/// Context for a base candle only uses higher timeframe bars which ended no later than that candle,
+/// so a 5-minute candle at 10:00 sees yesterday's daily bar but not today's one.
+///
+/// Resampled bars end where their [`Resampler`] bucket ends, so a daily bar of a 25-hour DST day
+/// closes an hour later than usual.
+#[derive(Debug, Clone)]
+pub struct MultiTimeframeCandles<T: Candle> {
+    pub base_timeframe: Timeframe,
+    pub key_format: TimeKeyFormat,
+    pub base: BTreeMap<u64, T>,
+    pub higher: BTreeMap<Timeframe, BTreeMap<u64, CandleInstance>>,
+    /// End (unix microseconds) of every higher timeframe bar by its start key.
+    /// Bars without an end here last the timeframe duration
+    pub bar_ends: BTreeMap<Timeframe, BTreeMap<u64, i64>>,
+}
+
+impl<T: Candle> MultiTimeframeCandles<T> {
+    pub fn new(
+        base_timeframe: Timeframe,
+        key_format: TimeKeyFormat,
+        base: BTreeMap<u64, T>,
+    ) -> Self {
+        Self {
+            base_timeframe,
+            key_format,
+            base,
+            higher: BTreeMap::new(),
+            bar_ends: BTreeMap::new(),
+        }
+    }
+
+    /// Builds the `resampler.target` series from the base candles.
+    ///
+    /// Fails if the resampler uses another key format than the base candles.
+    pub fn add_resampled(&mut self, resampler: &Resampler) -> Result<(), ResampleError> {
+        if resampler.key_format != self.key_format {
+            return Err(ResampleError::KeyFormatMismatch {
+                expected: self.key_format,
+                actual: resampler.key_format,
+            });
+        }
+
+        let result = resampler.resample(&self.base)?;
+
+        let mut ends = BTreeMap::new();
+        for time_key in result.candles.keys() {
+            let Some(start) = resampler.key_format.to_date_time(*time_key) else {
+                continue;
+            };
+            let (_, end) = resampler.get_bucket(start)?;
+            ends.insert(*time_key, end.timestamp_micros());
+        }
+
+        self.higher.insert(resampler.target, result.candles);
+        self.bar_ends.insert(resampler.target, ends);
+        Ok(())
+    }
+
+    /// Adds a series loaded separately. Keys must be bar starts in `key_format`.
+    ///
+    /// Every bar is taken to last the timeframe duration.
+    pub fn insert_timeframe(
+        &mut self,
+        timeframe: Timeframe,
+        candles: BTreeMap<u64, CandleInstance>,
+    ) {
+        self.higher.insert(timeframe, candles);
+        self.bar_ends.remove(&timeframe);
+    }
+
+    pub fn get_timeframes(&self) -> Vec<Timeframe> {
+        self.higher.keys().copied().collect()
+    }
+
+    /// Last `count` bars of the timeframe closed by the end of the base candle at `time_key`
+    pub fn get_closed(
+        &self,
+        timeframe: Timeframe,
+        time_key: u64,
+        count: usize,
+    ) -> BTreeMap<u64, CandleInstance> {
+        let Some((series, last_key)) = self.get_closed_bound(timeframe, time_key) else {
+            return BTreeMap::new();
+        };
+
+        series
+            .range(..=last_key)
+            .rev()
+            .take(count)
+            .map(|(key, candle)| (*key, candle.clone()))
+            .collect()
+    }
+
+    /// Last closed bar of the timeframe, e.g. the previous day for `Timeframe::Day`
+    pub fn get_last_closed(&self, timeframe: Timeframe, time_key: u64) -> Option<&CandleInstance> {
+        let (series, last_key) = self.get_closed_bound(timeframe, time_key)?;
+        series
+            .range(..=last_key)
+            .next_back()
+            .map(|(_, candle)| candle)
+    }
+
+    /// Trend of the last `window_size` closed bars of the timeframe
+    pub fn get_trend(
+        &self,
+        timeframe: Timeframe,
+        time_key: u64,
+        detector: &HHLLTrendDetector,
+        window_size: usize,
+    ) -> Option<TrendDirection> {
+        let closed = self.get_closed(timeframe, time_key, window_size);
+        if closed.len() < window_size {
+            return None;
+        }
+
+        detector.detect_trend(&closed)
+    }
+
+    /// ATR of the closed bars of the timeframe. `None` if there are not enough of them
+    pub fn get_atr(
+        &self,
+        timeframe: Timeframe,
+        time_key: u64,
+        calculator: &AtrCalculator,
+    ) -> Option<Atr> {
+        let (series, last_key) = self.get_closed_bound(timeframe, time_key)?;
+        let closed: BTreeMap<u64, CandleInstance> = series
+            .range(..=last_key)
+            .map(|(key, candle)| (*key, candle.clone()))
+            .collect();
+
+        calculator.calculate(&closed)
+    }
+
+    /// Series of the timeframe and the latest start of a bar which is closed at the base candle end
+    fn get_closed_bound(
+        &self,
+        timeframe: Timeframe,
+        time_key: u64,
+    ) -> Option<(&BTreeMap<u64, CandleInstance>, u64)> {
+        let series = self.higher.get(&timeframe)?;
+        let candle_start = self.key_format.to_date_time(time_key)?;
+
+        let candle_end =
+            candle_start.unix_microseconds + self.base_timeframe.get_duration_microseconds();
+        let candle_end_key = self
+            .key_format
+            .from_date_time(DateTimeAsMicroseconds::new(candle_end));
+
+        // Only the few bars overlapping the candle end are skipped
+        let (last_key, _) = series.range(..candle_end_key).rev().find(|(start, _)| {
+            self.get_bar_end(timeframe, **start)
+                .is_some_and(|bar_end| bar_end <= candle_end)
+        })?;
+
+        Some((series, *last_key))
+    }
+
+    /// End of the bar in unix microseconds, see [`MultiTimeframeCandles::bar_ends`]
+    fn get_bar_end(&self, timeframe: Timeframe, start_key: u64) -> Option<i64> {
+        if let Some(end) = self
+            .bar_ends
+            .get(&timeframe)
+            .and_then(|ends| ends.get(&start_key))
+        {
+            return Some(*end);
+        }
+
+        let start = self.key_format.to_date_time(start_key)?;
+        Some(start.unix_microseconds + timeframe.get_duration_microseconds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AtrSmoothing;
+
+    // 2025-04-07 00:00 UTC
+    const DAY_START: u64 = 1743984000;
+    const HOUR: u64 = 60 * 60;
+    const DAY: u64 = 24 * HOUR;
+
+    /// Hourly candles, each day 1.0 higher with a daily range of 2.0
+    fn make_candles(days: u64) -> MultiTimeframeCandles<CandleInstance> {
+        let base = (0..days * 24)
+            .map(|i| {
+                let time_key = DAY_START + i * HOUR;
+                let price = 100.0 + (i / 24) as f64;
+                let candle = CandleInstance {
+                    time_key,
+                    open: price,
+                    high: price + if i % 24 == 12 { 1.0 } else { 0.5 },
+                    low: price - if i % 24 == 6 { 1.0 } else { 0.5 },
+                    close: price + 0.25,
+                    volume: 1.0,
+                };
+                (time_key, candle)
+            })
+            .collect();
+
+        let mut candles =
+            MultiTimeframeCandles::new(Timeframe::Hours(1), TimeKeyFormat::UnixSeconds, base);
+        let resampler = Resampler::new(
+            Timeframe::Hours(1),
+            Timeframe::Day,
+            TimeKeyFormat::UnixSeconds,
+        )
+        .unwrap();
+        candles.add_resampled(&resampler).unwrap();
+        candles
+    }
+
+    #[test]
+    fn daily_bar_is_visible_only_after_close() {
+        let candles = make_candles(3);
+
+        assert!(
+            candles
+                .get_last_closed(Timeframe::Day, DAY_START + 22 * HOUR)
+                .is_none()
+        );
+
+        let last_hour = candles
+            .get_last_closed(Timeframe::Day, DAY_START + 23 * HOUR)
+            .unwrap();
+        assert_eq!(last_hour.time_key, DAY_START);
+
+        let next_morning = candles
+            .get_last_closed(Timeframe::Day, DAY_START + DAY + 10 * HOUR)
+            .unwrap();
+        assert_eq!(next_morning.time_key, DAY_START);
+        assert_eq!(next_morning.high, 101.0);
+        assert_eq!(next_morning.low, 99.0);
+    }
+
+    #[test]
+    fn daily_bar_of_dst_day_closes_after_25_hours() {
+        // 2024-11-02 00:00 New York (EDT). Clocks go back an hour on 2024-11-03
+        const NY_DAY_START: u64 = 1730520000;
+
+        let base = (0..3 * 24)
+            .map(|i| {
+                let time_key = NY_DAY_START + i * HOUR;
+                let candle = CandleInstance {
+                    time_key,
+                    open: 100.0,
+                    high: 100.5,
+                    low: 99.5,
+                    close: 100.0,
+                    volume: 1.0,
+                };
+                (time_key, candle)
+            })
+            .collect();
+
+        let mut candles =
+            MultiTimeframeCandles::new(Timeframe::Hours(1), TimeKeyFormat::UnixSeconds, base);
+        let resampler = Resampler {
+            time_zone: chrono_tz::America::New_York,
+            ..Resampler::new(
+                Timeframe::Hours(1),
+                Timeframe::Day,
+                TimeKeyFormat::UnixSeconds,
+            )
+            .unwrap()
+        };
+        candles.add_resampled(&resampler).unwrap();
+
+        let dst_day = NY_DAY_START + DAY;
+
+        // 22:00 EST is the 23rd hour of the 25-hour day
+        let before_close = candles
+            .get_last_closed(Timeframe::Day, dst_day + 23 * HOUR)
+            .unwrap();
+        assert_eq!(before_close.time_key, NY_DAY_START);
+
+        let last_hour = candles
+            .get_last_closed(Timeframe::Day, dst_day + 24 * HOUR)
+            .unwrap();
+        assert_eq!(last_hour.time_key, dst_day);
+    }
+
+    #[test]
+    fn resampler_with_other_key_format_is_rejected() {
+        let mut candles = make_candles(1);
+        let resampler = Resampler::new(
+            Timeframe::Hours(1),
+            Timeframe::Hours(4),
+            TimeKeyFormat::UnixMilliseconds,
+        )
+        .unwrap();
+
+        assert_eq!(
+            candles.add_resampled(&resampler),
+            Err(ResampleError::KeyFormatMismatch {
+                expected: TimeKeyFormat::UnixSeconds,
+                actual: TimeKeyFormat::UnixMilliseconds,
+            })
+        );
+        assert_eq!(candles.get_timeframes(), vec![Timeframe::Day]);
+    }
+
+    #[test]
+    fn closed_bars_are_limited_by_count() {
+        let candles = make_candles(4);
+        let time_key = DAY_START + 3 * DAY + 5 * HOUR;
+
+        let keys: Vec<u64> = candles
+            .get_closed(Timeframe::Day, time_key, 2)
+            .into_keys()
+            .collect();
+        assert_eq!(keys, vec![DAY_START + DAY, DAY_START + 2 * DAY]);
+
+        assert!(
+            candles
+                .get_closed(Timeframe::Hours(4), time_key, 2)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn trend_and_atr_of_closed_days() {
+        let candles = make_candles(4);
+        let detector = HHLLTrendDetector {
+            min_confirmation_ratio: 1.0,
+        };
+        let time_key = DAY_START + 3 * DAY + 5 * HOUR;
+
+        assert_eq!(
+            candles.get_trend(Timeframe::Day, time_key, &detector, 3),
+            Some(TrendDirection::Up)
+        );
+        assert_eq!(
+            candles.get_trend(Timeframe::Day, time_key, &detector, 4),
+            None
+        );
+
+        let atr = candles
+            .get_atr(
+                Timeframe::Day,
+                time_key,
+                &AtrCalculator::new(2, AtrSmoothing::Sma),
+            )
+            .unwrap();
+        assert_eq!(atr.get_value(), 2.0);
+    }
+}
@@ -1,7 +1,8 @@
 use std::collections::BTreeMap;
 use super::Pattern;
-use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
 use crate::candle::Candle;
+use crate::{AtrCalculator, AtrSmoothing};
 
 pub struct AtrSpike {
     pub period: usize,
@@ -11,20 +12,11 @@ pub struct AtrSpike {
 }
 
 impl AtrSpike {
+    /// Average true range of the last `period` candles, see [`AtrCalculator::calc_recent`]
     pub fn calc_candle_atr<T: Candle>(candles: &BTreeMap<u64, T>, period: usize) -> Option<f64> {
-        if candles.len() < period {
-            return None;
-        }
-
-        let atr = candles
-            .iter()
-            .rev()
-            .take(period)
-            .map(|(_, c)| c.get_high() - c.get_low())
-            .sum::<f64>()
-            / period as f64;
-
-        Some(atr)
+        AtrCalculator::new(period, AtrSmoothing::Sma)
+            .calc_recent(candles)
+            .map(|atr| atr.get_value())
     }
 }
 
@@ -34,7 +26,7 @@ impl<TCandle: Candle> Pattern<TCandle> for AtrSpike {
             Some(val) => val,
             None => AtrSpike::calc_candle_atr(candles, self.period)?,
         };
-        let (_, last) = candles.iter().last()?;
+        let (trigger_time_key, last) = candles.iter().last()?;
         let range = last.get_high() - last.get_low();
         let threshold = self.multiplier * atr;
 
@@ -44,6 +36,15 @@ impl<TCandle: Candle> Pattern<TCandle> for AtrSpike {
 
         let confidence = ((range / atr) - self.multiplier).clamp(0.0, 1.0);
 
+        let atr_candles = if self.atr.is_some() { 1 } else { self.period };
+        let mut time_keys: Vec<u64> = candles.keys().rev().take(atr_candles).copied().collect();
+        time_keys.reverse();
+
+        let evidence = PatternEvidence::new(*trigger_time_key, time_keys)
+            .with_measurement("range", range)
+            .with_measurement("atr", atr)
+            .with_measurement("range_atr_ratio", range / atr);
+
         Some(PatternResult {
             name: "ATR Spike".to_string(),
             direction: SignalDirection::Neutral,
@@ -53,8 +54,21 @@ impl<TCandle: Candle> Pattern<TCandle> for AtrSpike {
             ),
             confidence: Some(confidence),
             pattern_type: PatternType::AtrSpike,
+            evidence,
         })
     }
+
+    fn lookback(&self) -> Option<usize> {
+        match self.atr {
+            Some(_) => Some(1),
+            // One more candle for the close before the ATR window
+            None => Some(self.period + 1),
+        }
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -65,8 +79,8 @@ mod tests {
     fn make_candle(high: f64, low: f64) -> CandleInstance {
         CandleInstance {
             time_key: 0,
-            open: 0.0,
-            close: 0.0,
+            open: low,
+            close: high,
             high,
             low,
             volume: 1.0,
@@ -87,6 +101,21 @@ mod tests {
         assert_eq!(atr, Some(10.0));
     }
 
+    #[test]
+    fn test_atr_calc_includes_gap() {
+        let candles = vec![
+            make_candle(110.0, 100.0),
+            make_candle(111.0, 109.0),
+            make_candle(131.0, 129.0),
+        ];
+        let candles: BTreeMap<u64, CandleInstance> =
+            candles.into_iter().enumerate().map(|(i, c)| (c.time_key + i as u64, c)).collect();
+
+        // The gap from the 111.0 close makes the last true range 20.0 instead of 2.0
+        let atr = AtrSpike::calc_candle_atr(&candles, 2);
+        assert_eq!(atr, Some(11.0));
+    }
+
     #[test]
     fn test_atr_calc_not_enough_data() {
         let candles = vec![make_candle(110.0, 100.0), make_candle(120.0, 110.0)];
@@ -108,4 +137,32 @@ mod tests {
         let atr = AtrSpike::calc_candle_atr(&candles, 3);
         assert_eq!(atr, Some(0.0));
     }
+
+    #[test]
+    fn spike_reports_range_atr_ratio() {
+        let candles: BTreeMap<u64, CandleInstance> = [
+            make_candle(101.0, 100.0),
+            make_candle(101.0, 100.0),
+            make_candle(104.0, 100.0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, mut c)| {
+            c.time_key = i as u64;
+            (c.time_key, c)
+        })
+        .collect();
+
+        let pattern = AtrSpike {
+            period: 3,
+            multiplier: 1.5,
+            atr: None,
+        };
+        let result = pattern.matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.evidence.trigger_time_key, 2);
+        assert_eq!(result.evidence.time_keys, vec![0, 1, 2]);
+        assert_eq!(result.evidence.level, None);
+        assert_eq!(result.evidence.get_measurement("range_atr_ratio"), Some(2.0));
+    }
 }
@@ -0,0 +1,519 @@
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
+use crate::candle::{Candle, CandleInstance};
+use crate::patterns::{Pattern, PressureBuildupPattern};
+use crate::stop_loss::Luft;
+use crate::{ATR_DEFAULT_PERIOD, AtrCalculator, AtrSmoothing};
+use std::collections::BTreeMap;
+
+pub const BREAKOUT_DEFAULT_MIN_CLOSE_ATR: f64 = 0.1;
+pub const BREAKOUT_DEFAULT_RETEST_PERIOD: usize = 10;
+
+/// Breakout found by [`BreakoutPattern::find_breakout`] or [`BreakoutPattern::find_breakout_retest`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreakoutMatch {
+    pub direction: SignalDirection,
+    pub breakout_time_key: u64,
+    /// Candle which came back to the flipped level. `None` for a plain breakout
+    pub retest_time_key: Option<u64>,
+    /// Distance from the level to the breakout close
+    pub close_distance: f64,
+    pub atr: f64,
+    /// Breakout volume to the average volume of `atr_period` candles before it. `None` without volume data
+    pub volume_ratio: Option<f64>,
+    /// The build-up detector matched on the candles before the breakout
+    pub after_buildup: bool,
+    /// From the breakout candle to the last candle, oldest first
+    pub time_keys: Vec<u64>,
+}
+
+/// Close beyond the level after a close on the other side.
+///
+/// Optional filters reject breakouts closing far from the extreme, on low volume or returning
+/// inside within `hold_candles`. A later touch of the flipped level closing on the breakout side
+/// is reported as [`PatternType::BreakoutRetest`].
+#[derive(Debug, Clone)]
+pub struct BreakoutPattern {
+    /// Close must be beyond the level by more than this. A retest touches the level within it
+    pub luft: Luft,
+    /// Close must be beyond the level by at least this many ATRs
+    pub min_close_atr: f64,
+    pub atr_period: usize,
+    /// Precomputed ATR value. `None` means it is calculated from `atr_period` candles before the breakout
+    pub atr: Option<f64>,
+    /// Close is within this fraction of the range from the breakout side extreme
+    pub close_near_extreme: Option<f64>,
+    /// Volume is at least this many times the average volume
+    pub min_volume_ratio: Option<f64>,
+    /// Number of candles after the breakout which have to close beyond the level
+    pub hold_candles: usize,
+    /// Number of candles after the breakout a retest is searched in. `None` disables retests
+    pub retest_period: Option<usize>,
+    /// Checked on the candles before the breakout to fill [`BreakoutMatch::after_buildup`]
+    pub buildup: Option<PressureBuildupPattern>,
+}
+
+impl Default for BreakoutPattern {
+    fn default() -> Self {
+        Self {
+            luft: Luft::new(0.0),
+            min_close_atr: BREAKOUT_DEFAULT_MIN_CLOSE_ATR,
+            atr_period: ATR_DEFAULT_PERIOD,
+            atr: None,
+            close_near_extreme: None,
+            min_volume_ratio: None,
+            hold_candles: 0,
+            retest_period: Some(BREAKOUT_DEFAULT_RETEST_PERIOD),
+            buildup: Some(PressureBuildupPattern::default()),
+        }
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for BreakoutPattern {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Option<PatternResult> {
+        let (breakout, pattern_type) = match self.find_breakout(candles, level) {
+            Some(breakout) => (breakout, PatternType::Breakout),
+            None => (
+                self.find_breakout_retest(candles, level)?,
+                PatternType::BreakoutRetest,
+            ),
+        };
+
+        let side = match breakout.direction {
+            SignalDirection::Bullish => "above",
+            _ => "below",
+        };
+        let mut description = match breakout.retest_time_key {
+            Some(_) => format!("Retest of {:.2} after breakout {}", level, side),
+            None => format!(
+                "Breakout {} {:.2} by {:.2}",
+                side, level, breakout.close_distance
+            ),
+        };
+        if breakout.after_buildup {
+            description.push_str(" after build-up");
+        }
+
+        let luft = self.luft.get_value();
+        let mut evidence = PatternEvidence::new(*breakout.time_keys.last()?, breakout.time_keys)
+            .with_level(level)
+            .with_tolerance_band(level - luft, level + luft)
+            .with_measurement("close_distance", breakout.close_distance)
+            .with_measurement("close_distance_atr", breakout.close_distance / breakout.atr)
+            .with_measurement("atr", breakout.atr)
+            .with_measurement(
+                "after_buildup",
+                if breakout.after_buildup { 1.0 } else { 0.0 },
+            );
+        if let Some(volume_ratio) = breakout.volume_ratio {
+            evidence = evidence.with_measurement("volume_ratio", volume_ratio);
+        }
+
+        Some(PatternResult {
+            name: format!("{:?}", pattern_type),
+            direction: breakout.direction,
+            description,
+            confidence: None,
+            pattern_type,
+            evidence,
+        })
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.get_lookback())
+    }
+}
+
+impl BreakoutPattern {
+    pub fn new(luft: Luft) -> Self {
+        Self {
+            luft,
+            ..Default::default()
+        }
+    }
+
+    /// Candles before the breakout, the breakout and the hold or retest candles after it
+    pub fn get_lookback(&self) -> usize {
+        self.get_candles_before() + 1 + self.hold_candles.max(self.retest_period.unwrap_or(0))
+    }
+
+    /// Breakout `hold_candles` before the last candle, with all candles after it closing beyond the level
+    pub fn find_breakout(
+        &self,
+        candles: &BTreeMap<u64, impl Candle>,
+        level: f64,
+    ) -> Option<BreakoutMatch> {
+        let window: Vec<_> = self.get_window(candles)?;
+        let index = window.len().checked_sub(1 + self.hold_candles)?;
+        let breakout = self.check_breakout(&window, index, level)?;
+        let side = get_side(&breakout.direction);
+
+        window[index + 1..]
+            .iter()
+            .all(|(_, c)| side * (c.get_close() - level) > 0.0)
+            .then_some(breakout)
+    }
+
+    /// Last candle touches the level within luft and closes on the side of an earlier breakout.
+    ///
+    /// Candles between the breakout and the retest have to close beyond the level without touching it,
+    /// and there have to be at least `hold_candles` of them.
+    pub fn find_breakout_retest(
+        &self,
+        candles: &BTreeMap<u64, impl Candle>,
+        level: f64,
+    ) -> Option<BreakoutMatch> {
+        let retest_period = self.retest_period?;
+        let window: Vec<_> = self.get_window(candles)?;
+        let last = window.len() - 1;
+        let (retest_time_key, retest) = window[last];
+        let luft = self.luft.get_value();
+
+        for index in
+            (last.saturating_sub(retest_period)..last.saturating_sub(self.hold_candles)).rev()
+        {
+            let Some(mut breakout) = self.check_breakout(&window, index, level) else {
+                continue;
+            };
+            let side = get_side(&breakout.direction);
+            let held = window[index + 1..last].iter().all(|(_, c)| {
+                let extreme = if side > 0.0 {
+                    c.get_low()
+                } else {
+                    c.get_high()
+                };
+                side * (c.get_close() - level) > 0.0 && side * (extreme - level) > luft
+            });
+
+            // Low after a break up, high after a break down
+            let extreme = if side > 0.0 {
+                retest.get_low()
+            } else {
+                retest.get_high()
+            };
+            let is_retest =
+                side * (extreme - level) <= luft && side * (retest.get_close() - level) > 0.0;
+
+            if held && is_retest {
+                breakout.retest_time_key = Some(*retest_time_key);
+                return Some(breakout);
+            }
+        }
+
+        None
+    }
+
+    fn get_candles_before(&self) -> usize {
+        // One more candle gives the previous close of the first ATR candle
+        let atr_candles = if self.atr.is_some() {
+            1
+        } else {
+            self.atr_period.max(1) + 1
+        };
+        let buildup_candles = self
+            .buildup
+            .as_ref()
+            .and_then(Pattern::<CandleInstance>::lookback)
+            .unwrap_or(0);
+
+        atr_candles.max(buildup_candles)
+    }
+
+    /// Last [`BreakoutPattern::get_lookback`] candles, oldest first, or less at the start of the history
+    fn get_window<'c, T: Candle>(
+        &self,
+        candles: &'c BTreeMap<u64, T>,
+    ) -> Option<Vec<(&'c u64, &'c T)>> {
+        if candles.is_empty() || (self.atr.is_none() && self.atr_period == 0) {
+            return None;
+        }
+
+        let mut window: Vec<_> = candles.iter().rev().take(self.get_lookback()).collect();
+        window.reverse();
+        Some(window)
+    }
+
+    /// Checks the breakout filters for the candle at `index` of the window
+    fn check_breakout<T: Candle>(
+        &self,
+        window: &[(&u64, &T)],
+        index: usize,
+        level: f64,
+    ) -> Option<BreakoutMatch> {
+        let (time_key, candle) = window[index];
+        let prev = window.get(index.checked_sub(1)?)?.1;
+
+        let side = if candle.get_close() > level {
+            1.0
+        } else if candle.get_close() < level {
+            -1.0
+        } else {
+            return None;
+        };
+
+        if side * (prev.get_close() - level) > 0.0 {
+            return None;
+        }
+
+        let atr = match self.atr {
+            Some(val) => val,
+            None => {
+                let atr_candles: Vec<&T> = window[index.saturating_sub(self.atr_period + 1)..index]
+                    .iter()
+                    .map(|(_, c)| *c)
+                    .collect();
+
+                AtrCalculator::new(self.atr_period, AtrSmoothing::Sma)
+                    .calc_recent_slice(&atr_candles)?
+                    .get_value()
+            }
+        };
+
+        let close_distance = side * (candle.get_close() - level);
+        if close_distance <= self.luft.get_value() || close_distance < self.min_close_atr * atr {
+            return None;
+        }
+
+        if let Some(max_gap) = self.close_near_extreme {
+            let gap = if side > 0.0 {
+                candle.get_high() - candle.get_close()
+            } else {
+                candle.get_close() - candle.get_low()
+            };
+
+            if gap > max_gap * (candle.get_high() - candle.get_low()) {
+                return None;
+            }
+        }
+
+        let volume_candles = &window[index.saturating_sub(self.atr_period)..index];
+        let average_volume = volume_candles
+            .iter()
+            .map(|(_, c)| c.get_volume())
+            .sum::<f64>()
+            / volume_candles.len() as f64;
+        let volume_ratio = (average_volume > 0.0).then(|| candle.get_volume() / average_volume);
+
+        if let Some(min_volume_ratio) = self.min_volume_ratio
+            && !volume_ratio.is_some_and(|ratio| ratio >= min_volume_ratio)
+        {
+            return None;
+        }
+
+        let after_buildup = self.buildup.as_ref().is_some_and(|buildup| {
+            let before: BTreeMap<u64, CandleInstance> = window[..index]
+                .iter()
+                .map(|(key, c)| (**key, CandleInstance::from_candle(*c)))
+                .collect();
+            buildup.matches(&before, level).is_some()
+        });
+
+        Some(BreakoutMatch {
+            direction: if side > 0.0 {
+                SignalDirection::Bullish
+            } else {
+                SignalDirection::Bearish
+            },
+            breakout_time_key: *time_key,
+            retest_time_key: None,
+            close_distance,
+            atr,
+            volume_ratio,
+            after_buildup,
+            time_keys: window[index..].iter().map(|(key, _)| **key).collect(),
+        })
+    }
+}
+
+fn get_side(direction: &SignalDirection) -> f64 {
+    match direction {
+        SignalDirection::Bearish => -1.0,
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_candles_with_volume;
+
+    const LEVEL: f64 = 100.0;
+
+    /// Candles with range 1.0 and volume 1.0, `history` of them below the level, then the given ones
+    fn make_candles(
+        history: &[f64],
+        prices: &[(f64, f64, f64, f64, f64)],
+    ) -> BTreeMap<u64, CandleInstance> {
+        let candles: Vec<(f64, f64, f64, f64, f64)> = history
+            .iter()
+            .map(|close| (close - 0.3, close + 0.4, close - 0.6, *close, 1.0))
+            .chain(prices.iter().copied())
+            .collect();
+
+        make_candles_with_volume(&candles)
+    }
+
+    const NEAR_LEVEL: [f64; 5] = [98.8, 98.9, 98.8, 98.9, 98.8];
+
+    fn make_pattern() -> BreakoutPattern {
+        BreakoutPattern {
+            atr_period: 3,
+            retest_period: Some(3),
+            ..BreakoutPattern::new(Luft::new(0.1))
+        }
+    }
+
+    #[test]
+    fn breakout_after_buildup() {
+        let candles = make_candles(&NEAR_LEVEL, &[(98.8, 100.6, 98.7, 100.5, 1.0)]);
+
+        let result = make_pattern().matches(&candles, LEVEL).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::Breakout);
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert_eq!(result.evidence.get_measurement("after_buildup"), Some(1.0));
+        assert!(result.description.ends_with("after build-up"));
+    }
+
+    #[test]
+    fn breakout_without_buildup() {
+        let candles = make_candles(
+            &[96.0, 96.1, 96.0, 96.2, 98.8],
+            &[(98.8, 100.6, 98.7, 100.5, 1.0)],
+        );
+
+        let breakout = make_pattern().find_breakout(&candles, LEVEL).unwrap();
+
+        assert!(!breakout.after_buildup);
+        assert_eq!(breakout.breakout_time_key, 5);
+    }
+
+    #[test]
+    fn breakout_down() {
+        let candles = make_candles(&[101.2, 101.1, 101.2], &[(101.2, 101.3, 99.4, 99.5, 1.0)]);
+
+        let breakout = make_pattern().find_breakout(&candles, LEVEL).unwrap();
+
+        assert_eq!(breakout.direction, SignalDirection::Bearish);
+        assert!((breakout.close_distance - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn atr_includes_gaps() {
+        // Gap up from 96.0 makes the first true range of the ATR 5.6
+        let candles = make_candles(
+            &[96.0, 101.2, 101.1, 101.2],
+            &[(101.2, 101.3, 99.4, 99.5, 1.0)],
+        );
+
+        let breakout = make_pattern().find_breakout(&candles, LEVEL).unwrap();
+        assert!((breakout.atr - 7.6 / 3.0).abs() < 1e-9);
+
+        let strict = BreakoutPattern {
+            min_close_atr: 0.4,
+            ..make_pattern()
+        };
+        assert!(strict.find_breakout(&candles, LEVEL).is_none());
+    }
+
+    #[test]
+    fn close_within_luft_is_not_breakout() {
+        let candles = make_candles(&NEAR_LEVEL, &[(98.8, 100.6, 98.7, 100.05, 1.0)]);
+
+        assert!(make_pattern().find_breakout(&candles, LEVEL).is_none());
+    }
+
+    #[test]
+    fn close_far_from_extreme_is_filtered() {
+        let candles = make_candles(&NEAR_LEVEL, &[(98.8, 101.5, 98.7, 100.5, 1.0)]);
+
+        let filtered = BreakoutPattern {
+            close_near_extreme: Some(0.25),
+            ..make_pattern()
+        };
+
+        assert!(make_pattern().find_breakout(&candles, LEVEL).is_some());
+        assert!(filtered.find_breakout(&candles, LEVEL).is_none());
+    }
+
+    #[test]
+    fn low_volume_is_filtered() {
+        let quiet = make_candles(&NEAR_LEVEL, &[(98.8, 100.6, 98.7, 100.5, 1.0)]);
+        let loud = make_candles(&NEAR_LEVEL, &[(98.8, 100.6, 98.7, 100.5, 3.0)]);
+
+        let pattern = BreakoutPattern {
+            min_volume_ratio: Some(1.5),
+            ..make_pattern()
+        };
+
+        assert!(pattern.find_breakout(&quiet, LEVEL).is_none());
+        assert_eq!(
+            pattern.find_breakout(&loud, LEVEL).unwrap().volume_ratio,
+            Some(3.0)
+        );
+    }
+
+    #[test]
+    fn hold_candles_reject_immediate_return() {
+        let pattern = BreakoutPattern {
+            hold_candles: 1,
+            ..make_pattern()
+        };
+
+        let held = make_candles(
+            &NEAR_LEVEL,
+            &[
+                (98.8, 100.6, 98.7, 100.5, 1.0),
+                (100.5, 100.7, 100.2, 100.3, 1.0),
+            ],
+        );
+        let returned = make_candles(
+            &NEAR_LEVEL,
+            &[
+                (98.8, 100.6, 98.7, 100.5, 1.0),
+                (100.5, 100.7, 99.7, 99.8, 1.0),
+            ],
+        );
+
+        let breakout = pattern.find_breakout(&held, LEVEL).unwrap();
+        assert_eq!(breakout.breakout_time_key, 5);
+        assert_eq!(breakout.time_keys, vec![5, 6]);
+        assert!(pattern.matches(&returned, LEVEL).is_none());
+    }
+
+    #[test]
+    fn retest_of_flipped_level() {
+        let candles = make_candles(
+            &NEAR_LEVEL,
+            &[
+                (98.8, 100.6, 98.7, 100.5, 1.0),
+                (100.5, 100.9, 100.3, 100.7, 1.0),
+                (100.7, 100.8, 100.05, 100.4, 1.0),
+            ],
+        );
+
+        let result = make_pattern().matches(&candles, LEVEL).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::BreakoutRetest);
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert_eq!(result.evidence.time_keys, vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn retest_closing_inside_is_not_entry() {
+        let candles = make_candles(
+            &NEAR_LEVEL,
+            &[
+                (98.8, 100.6, 98.7, 100.5, 1.0),
+                (100.5, 100.9, 100.3, 100.7, 1.0),
+                (100.7, 100.8, 99.6, 99.9, 1.0),
+            ],
+        );
+
+        assert!(
+            make_pattern()
+                .find_breakout_retest(&candles, LEVEL)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::hhll::TrendDirection;
+use crate::{ATR_DEFAULT_PERIOD, calc_true_range};
+
+pub const CANDLESTICK_DEFAULT_TREND_PERIOD: usize = 5;
+
+/// What candle sizes are compared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeReference {
+    /// Average true range of the candles before the pattern
+    Atr,
+    /// Average body of the candles before the pattern
+    AverageBody,
+}
+
+/// Thresholds shared by the candlestick patterns.
+///
+/// Sizes are fractions of the reference size (see [`SizeReference`]), except `shadow_to_body`.
+#[derive(Debug, Clone)]
+pub struct CandlestickConfig {
+    pub reference: SizeReference,
+    /// Number of candles before the pattern the reference size is calculated from
+    pub period: usize,
+    /// Number of candles before the pattern the prior trend is taken from
+    pub trend_period: usize,
+    /// Doji body is at most this
+    pub doji_body: f64,
+    /// Small body (harami, stars) is at most this
+    pub small_body: f64,
+    /// Long body (marubozu, soldiers, first candle of stars) is at least this
+    pub long_body: f64,
+    /// Short shadow is at most this
+    pub short_shadow: f64,
+    /// Long shadow is at least this
+    pub long_shadow: f64,
+    /// Shadow of hammer-like candles is at least this many bodies
+    pub shadow_to_body: f64,
+    /// Prices closer than this are treated as equal (tweezers)
+    pub tolerance: f64,
+}
+
+impl CandlestickConfig {
+    pub fn atr(period: usize) -> Self {
+        Self {
+            reference: SizeReference::Atr,
+            period,
+            trend_period: CANDLESTICK_DEFAULT_TREND_PERIOD,
+            doji_body: 0.05,
+            small_body: 0.3,
+            long_body: 0.6,
+            short_shadow: 0.1,
+            long_shadow: 0.5,
+            shadow_to_body: 2.0,
+            tolerance: 0.05,
+        }
+    }
+
+    pub fn average_body(period: usize) -> Self {
+        Self {
+            reference: SizeReference::AverageBody,
+            period,
+            trend_period: CANDLESTICK_DEFAULT_TREND_PERIOD,
+            doji_body: 0.1,
+            small_body: 0.5,
+            long_body: 1.3,
+            short_shadow: 0.2,
+            long_shadow: 1.0,
+            shadow_to_body: 2.0,
+            tolerance: 0.1,
+        }
+    }
+
+    /// Candles needed to check a pattern of `pattern_len` candles
+    pub fn get_lookback(&self, pattern_len: usize) -> usize {
+        pattern_len + self.period.max(self.trend_period + 1)
+    }
+
+    /// Takes the last `pattern_len` candles and measures the candles before them.
+    /// `None` if there is not enough history or the reference size is zero
+    pub(crate) fn get_window<T: Candle>(
+        &self,
+        candles: &BTreeMap<u64, T>,
+        pattern_len: usize,
+    ) -> Option<CandlestickWindow> {
+        let needed = self.get_lookback(pattern_len);
+        if candles.len() < needed || pattern_len == 0 || self.period == 0 {
+            return None;
+        }
+
+        let mut last: Vec<(&u64, &T)> = candles.iter().rev().take(needed).collect();
+        last.reverse();
+
+        let (before, pattern) = last.split_at(needed - pattern_len);
+        let reference_candles = &before[before.len() - self.period..];
+
+        let reference = match self.reference {
+            SizeReference::Atr => {
+                let offset = before.len() - self.period;
+                reference_candles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, c))| {
+                        let prev_close = (offset + i)
+                            .checked_sub(1)
+                            .map(|prev| before[prev].1.get_close());
+                        calc_true_range(*c, prev_close)
+                    })
+                    .sum::<f64>()
+                    / self.period as f64
+            }
+            SizeReference::AverageBody => {
+                reference_candles
+                    .iter()
+                    .map(|(_, c)| (c.get_close() - c.get_open()).abs())
+                    .sum::<f64>()
+                    / self.period as f64
+            }
+        };
+
+        if reference <= 0.0 {
+            return None;
+        }
+
+        let trend_to = before[before.len() - 1].1.get_close();
+        let trend_from = before[before.len() - 1 - self.trend_period].1.get_close();
+
+        let prior_trend = if trend_to > trend_from {
+            TrendDirection::Up
+        } else if trend_to < trend_from {
+            TrendDirection::Down
+        } else {
+            TrendDirection::Sideways
+        };
+
+        Some(CandlestickWindow {
+            time_keys: pattern.iter().map(|(k, _)| **k).collect(),
+            shapes: pattern
+                .iter()
+                .map(|(_, c)| CandleShape::from_candle(*c))
+                .collect(),
+            reference,
+            prior_trend,
+        })
+    }
+}
+
+impl Default for CandlestickConfig {
+    fn default() -> Self {
+        Self::atr(ATR_DEFAULT_PERIOD)
+    }
+}
+
+/// Pattern candles with the sizes they are compared against
+pub(crate) struct CandlestickWindow {
+    pub time_keys: Vec<u64>,
+    /// Pattern candles, oldest first
+    pub shapes: Vec<CandleShape>,
+    pub reference: f64,
+    /// Direction of closes over `trend_period` candles before the pattern
+    pub prior_trend: TrendDirection,
+}
+
+impl CandlestickWindow {
+    pub fn to_result(
+        &self,
+        pattern_type: PatternType,
+        direction: SignalDirection,
+        description: String,
+    ) -> PatternResult {
+        let evidence =
+            PatternEvidence::new(*self.time_keys.last().unwrap(), self.time_keys.clone())
+                .with_measurement("reference_size", self.reference);
+
+        PatternResult {
+            name: format!("{:?}", pattern_type),
+            direction,
+            description,
+            confidence: None,
+            pattern_type,
+            evidence,
+        }
+    }
+}
+
+/// Body and shadow sizes of a candle
+pub(crate) struct CandleShape {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl CandleShape {
+    pub fn from_candle(candle: &impl Candle) -> Self {
+        Self {
+            open: candle.get_open(),
+            high: candle.get_high(),
+            low: candle.get_low(),
+            close: candle.get_close(),
+        }
+    }
+
+    pub fn body(&self) -> f64 {
+        (self.close - self.open).abs()
+    }
+
+    pub fn range(&self) -> f64 {
+        self.high - self.low
+    }
+
+    pub fn body_top(&self) -> f64 {
+        self.open.max(self.close)
+    }
+
+    pub fn body_bottom(&self) -> f64 {
+        self.open.min(self.close)
+    }
+
+    pub fn body_middle(&self) -> f64 {
+        (self.open + self.close) / 2.0
+    }
+
+    pub fn upper_shadow(&self) -> f64 {
+        self.high - self.body_top()
+    }
+
+    pub fn lower_shadow(&self) -> f64 {
+        self.body_bottom() - self.low
+    }
+
+    pub fn is_bullish(&self) -> bool {
+        self.close > self.open
+    }
+
+    pub fn is_bearish(&self) -> bool {
+        self.close < self.open
+    }
+
+    pub fn get_direction(&self) -> SignalDirection {
+        if self.is_bullish() {
+            SignalDirection::Bullish
+        } else if self.is_bearish() {
+            SignalDirection::Bearish
+        } else {
+            SignalDirection::Neutral
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use super::{CandleShape, CandlestickConfig};
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Doji which is neither dragonfly, gravestone nor long-legged
+#[derive(Debug, Clone, Default)]
+pub struct Doji {
+    pub config: CandlestickConfig,
+}
+
+/// Doji with no upper shadow and a long lower shadow
+#[derive(Debug, Clone, Default)]
+pub struct DragonflyDoji {
+    pub config: CandlestickConfig,
+}
+
+/// Doji with no lower shadow and a long upper shadow
+#[derive(Debug, Clone, Default)]
+pub struct GravestoneDoji {
+    pub config: CandlestickConfig,
+}
+
+/// Doji with long shadows on both sides
+#[derive(Debug, Clone, Default)]
+pub struct LongLeggedDoji {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for Doji {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        match_doji(
+            &self.config,
+            candles,
+            PatternType::Doji,
+            SignalDirection::Neutral,
+        )
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for DragonflyDoji {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        match_doji(
+            &self.config,
+            candles,
+            PatternType::DragonflyDoji,
+            SignalDirection::Bullish,
+        )
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for GravestoneDoji {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        match_doji(
+            &self.config,
+            candles,
+            PatternType::GravestoneDoji,
+            SignalDirection::Bearish,
+        )
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for LongLeggedDoji {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        match_doji(
+            &self.config,
+            candles,
+            PatternType::LongLeggedDoji,
+            SignalDirection::Neutral,
+        )
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+fn match_doji<TCandle: Candle>(
+    config: &CandlestickConfig,
+    candles: &BTreeMap<u64, TCandle>,
+    pattern_type: PatternType,
+    direction: SignalDirection,
+) -> Option<PatternResult> {
+    let window = config.get_window(candles, 1)?;
+    let found = classify_doji(&window.shapes[0], config, window.reference)?;
+
+    if found != pattern_type {
+        return None;
+    }
+
+    let description = format!("{:?} with body {:.2}", found, window.shapes[0].body());
+    Some(window.to_result(pattern_type, direction, description))
+}
+
+fn classify_doji(
+    shape: &CandleShape,
+    config: &CandlestickConfig,
+    reference: f64,
+) -> Option<PatternType> {
+    if shape.range() <= 0.0 || shape.body() > config.doji_body * reference {
+        return None;
+    }
+
+    let upper_short = shape.upper_shadow() <= config.short_shadow * reference;
+    let lower_short = shape.lower_shadow() <= config.short_shadow * reference;
+    let upper_long = shape.upper_shadow() >= config.long_shadow * reference;
+    let lower_long = shape.lower_shadow() >= config.long_shadow * reference;
+
+    let result = if upper_short && lower_long {
+        PatternType::DragonflyDoji
+    } else if lower_short && upper_long {
+        PatternType::GravestoneDoji
+    } else if upper_long && lower_long {
+        PatternType::LongLeggedDoji
+    } else {
+        PatternType::Doji
+    };
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_plain_doji() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.3, -0.3, 0.02)]);
+
+        let result = Doji::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::Doji);
+        assert_eq!(result.evidence.time_keys, vec![15]);
+        assert!(DragonflyDoji::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_dragonfly_doji() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.02, -0.8, 0.0)]);
+
+        let result = DragonflyDoji::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert!(Doji::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_gravestone_doji() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.8, -0.02, 0.01)]);
+
+        let result = GravestoneDoji::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+    }
+
+    #[test]
+    fn detects_long_legged_doji() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.7, -0.7, -0.01)]);
+
+        let result = LongLeggedDoji::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::LongLeggedDoji);
+    }
+
+    #[test]
+    fn large_body_is_not_doji() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.5, -0.5, 0.2)]);
+
+        assert!(Doji::default().matches(&candles, 0.0).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use super::CandlestickConfig;
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Body of the last candle covers the opposite colored body of the previous one.
+/// Bullish when the last candle is bullish
+#[derive(Debug, Clone, Default)]
+pub struct Engulfing {
+    pub config: CandlestickConfig,
+}
+
+/// Small body inside the long body of the previous candle. Direction is opposite to the long candle
+#[derive(Debug, Clone, Default)]
+pub struct Harami {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for Engulfing {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+        let reference = window.reference;
+
+        let direction = last.get_direction();
+        if direction == SignalDirection::Neutral || prev.get_direction() != opposite(&direction) {
+            return None;
+        }
+
+        let engulfs = last.body_top() >= prev.body_top()
+            && last.body_bottom() <= prev.body_bottom()
+            && last.body() > prev.body();
+
+        if !engulfs
+            || prev.body() <= self.config.doji_body * reference
+            || last.body() < self.config.small_body * reference
+        {
+            return None;
+        }
+
+        let description = format!(
+            "{:?} engulfing, body {:.2} over {:.2}",
+            direction,
+            last.body(),
+            prev.body()
+        );
+        Some(window.to_result(PatternType::Engulfing, direction, description))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for Harami {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+        let reference = window.reference;
+
+        let inside = last.body_top() <= prev.body_top() && last.body_bottom() >= prev.body_bottom();
+
+        if !inside
+            || prev.body() < self.config.long_body * reference
+            || last.body() > self.config.small_body * reference
+        {
+            return None;
+        }
+
+        let direction = opposite(&prev.get_direction());
+        let description = format!(
+            "{:?} harami, body {:.2} inside {:.2}",
+            direction,
+            last.body(),
+            prev.body()
+        );
+        Some(window.to_result(PatternType::Harami, direction, description))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+fn opposite(direction: &SignalDirection) -> SignalDirection {
+    match direction {
+        SignalDirection::Bullish => SignalDirection::Bearish,
+        SignalDirection::Bearish => SignalDirection::Bullish,
+        SignalDirection::Neutral => SignalDirection::Neutral,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_bullish_engulfing() {
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.1, -0.4, -0.3), (-0.4, 0.3, -0.5, 0.2)]);
+
+        let result = Engulfing::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert_eq!(result.evidence.time_keys, vec![15, 16]);
+    }
+
+    #[test]
+    fn detects_bearish_engulfing() {
+        let candles = make_trend_candles(0.2, &[(0.0, 0.4, -0.1, 0.3), (0.4, 0.5, -0.3, -0.2)]);
+
+        let result = Engulfing::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+    }
+
+    #[test]
+    fn same_color_is_not_engulfing() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.4, -0.1, 0.3), (-0.1, 0.6, -0.2, 0.5)]);
+
+        assert!(Engulfing::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_harami() {
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.1, -0.9, -0.8), (-0.5, -0.3, -0.6, -0.4)]);
+
+        let result = Harami::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::Harami);
+        assert_eq!(result.direction, SignalDirection::Bullish);
+    }
+
+    #[test]
+    fn harami_needs_long_mother_candle() {
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.1, -0.4, -0.3), (-0.2, -0.1, -0.3, -0.15)]);
+
+        assert!(Harami::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn thresholds_follow_average_body() {
+        // Mother body 0.8 is long against ATR 1.0 and against average body 0.5 with the default ratio
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.1, -0.9, -0.8), (-0.6, -0.2, -0.7, -0.4)]);
+
+        let strict = Harami {
+            config: CandlestickConfig {
+                long_body: 2.0,
+                ..CandlestickConfig::average_body(14)
+            },
+        };
+
+        assert!(Harami::default().matches(&candles, 0.0).is_some());
+        assert!(
+            Harami {
+                config: CandlestickConfig::average_body(14)
+            }
+            .matches(&candles, 0.0)
+            .is_some()
+        );
+        assert!(strict.matches(&candles, 0.0).is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use super::CandlestickConfig;
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Range of the last candle is inside the range of the previous one.
+/// The previous range must be at least `long_body`
+#[derive(Debug, Clone, Default)]
+pub struct InsideBar {
+    pub config: CandlestickConfig,
+}
+
+/// Last candle makes both a higher high and a lower low than the previous one.
+/// Range of the last candle must be at least `long_body`, direction follows its color
+#[derive(Debug, Clone, Default)]
+pub struct OutsideBar {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for InsideBar {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+
+        let is_inside = last.high <= prev.high
+            && last.low >= prev.low
+            && last.range() < prev.range()
+            && prev.range() >= self.config.long_body * window.reference;
+
+        if !is_inside {
+            return None;
+        }
+
+        let description = format!("Inside bar within {:.2} - {:.2}", prev.low, prev.high);
+        Some(window.to_result(
+            PatternType::InsideBar,
+            SignalDirection::Neutral,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for OutsideBar {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+
+        let is_outside = last.high > prev.high
+            && last.low < prev.low
+            && last.range() >= self.config.long_body * window.reference;
+
+        if !is_outside {
+            return None;
+        }
+
+        let description = format!("Outside bar over {:.2} - {:.2}", prev.low, prev.high);
+        Some(window.to_result(PatternType::OutsideBar, last.get_direction(), description))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_inside_bar() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.8, -0.4, 0.5), (0.3, 0.6, -0.1, 0.2)]);
+
+        let result = InsideBar::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Neutral);
+        assert!(OutsideBar::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_outside_bar() {
+        let candles = make_trend_candles(0.0, &[(0.0, 0.4, -0.2, 0.3), (0.3, 0.6, -0.5, -0.4)]);
+
+        let result = OutsideBar::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+        assert!(InsideBar::default().matches(&candles, 0.0).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use super::CandlestickConfig;
+use crate::analyzer::{PatternResult, PatternType};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Long body with almost no shadows. Direction follows the candle color
+#[derive(Debug, Clone, Default)]
+pub struct Marubozu {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for Marubozu {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 1)?;
+        let shape = &window.shapes[0];
+        let reference = window.reference;
+
+        let is_marubozu = shape.body() >= self.config.long_body * reference
+            && shape.upper_shadow() <= self.config.short_shadow * reference
+            && shape.lower_shadow() <= self.config.short_shadow * reference;
+
+        if !is_marubozu {
+            return None;
+        }
+
+        let direction = shape.get_direction();
+        let description = format!("{:?} marubozu, body {:.2}", direction, shape.body());
+        Some(window.to_result(PatternType::Marubozu, direction, description))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::SignalDirection;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_bullish_and_bearish_marubozu() {
+        let bullish = make_trend_candles(0.0, &[(0.0, 0.82, -0.02, 0.8)]);
+        let bearish = make_trend_candles(0.0, &[(0.0, 0.01, -0.9, -0.9)]);
+
+        let pattern = Marubozu::default();
+
+        assert_eq!(
+            pattern.matches(&bullish, 0.0).unwrap().direction,
+            SignalDirection::Bullish
+        );
+        assert_eq!(
+            pattern.matches(&bearish, 0.0).unwrap().direction,
+            SignalDirection::Bearish
+        );
+    }
+
+    #[test]
+    fn marubozu_rejects_shadows() {
+        let candles = make_trend_candles(0.0, &[(0.0, 1.0, -0.02, 0.8)]);
+
+        assert!(Marubozu::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn not_enough_history() {
+        let mut candles = make_trend_candles(0.0, &[(0.0, 0.82, -0.02, 0.8)]);
+        candles.pop_first();
+        candles.pop_first();
+
+        let pattern = Marubozu::default();
+
+        assert_eq!(
+            <Marubozu as Pattern<crate::candle::CandleInstance>>::lookback(&pattern),
+            Some(15)
+        );
+        assert!(pattern.matches(&candles, 0.0).is_none());
+    }
+}
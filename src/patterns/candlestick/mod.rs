@@ -0,0 +1,21 @@
+mod candlestick_config;
+mod doji;
+mod engulfing;
+mod inside_outside_bar;
+mod marubozu;
+mod morning_star;
+mod piercing;
+mod pin_bars;
+mod three_soldiers;
+mod tweezers;
+
+pub use candlestick_config::*;
+pub use doji::*;
+pub use engulfing::*;
+pub use inside_outside_bar::*;
+pub use marubozu::*;
+pub use morning_star::*;
+pub use piercing::*;
+pub use pin_bars::*;
+pub use three_soldiers::*;
+pub use tweezers::*;
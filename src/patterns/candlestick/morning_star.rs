@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use super::CandlestickConfig;
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Long bearish candle, small body at or below its close, then a bullish candle closing above its body middle
+#[derive(Debug, Clone, Default)]
+pub struct MorningStar {
+    pub config: CandlestickConfig,
+}
+
+/// Long bullish candle, small body at or above its close, then a bearish candle closing below its body middle
+#[derive(Debug, Clone, Default)]
+pub struct EveningStar {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for MorningStar {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 3)?;
+        let (first, star, last) = (&window.shapes[0], &window.shapes[1], &window.shapes[2]);
+        let reference = window.reference;
+
+        let is_morning_star = first.is_bearish()
+            && first.body() >= self.config.long_body * reference
+            && star.body() <= self.config.small_body * reference
+            && star.body_top() <= first.close + self.config.tolerance * reference
+            && last.is_bullish()
+            && last.close > first.body_middle();
+
+        if !is_morning_star {
+            return None;
+        }
+
+        let description = format!("Morning star at {:.2}", star.low);
+        Some(window.to_result(
+            PatternType::MorningStar,
+            SignalDirection::Bullish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(3))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for EveningStar {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 3)?;
+        let (first, star, last) = (&window.shapes[0], &window.shapes[1], &window.shapes[2]);
+        let reference = window.reference;
+
+        let is_evening_star = first.is_bullish()
+            && first.body() >= self.config.long_body * reference
+            && star.body() <= self.config.small_body * reference
+            && star.body_bottom() >= first.close - self.config.tolerance * reference
+            && last.is_bearish()
+            && last.close < first.body_middle();
+
+        if !is_evening_star {
+            return None;
+        }
+
+        let description = format!("Evening star at {:.2}", star.high);
+        Some(window.to_result(
+            PatternType::EveningStar,
+            SignalDirection::Bearish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(3))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_morning_star() {
+        let candles = make_trend_candles(
+            -0.2,
+            &[
+                (0.0, 0.05, -0.85, -0.8),
+                (-0.9, -0.8, -1.1, -0.95),
+                (-0.9, -0.2, -0.95, -0.3),
+            ],
+        );
+
+        let result = MorningStar::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert_eq!(result.evidence.time_keys, vec![15, 16, 17]);
+    }
+
+    #[test]
+    fn morning_star_needs_strong_third_candle() {
+        let candles = make_trend_candles(
+            -0.2,
+            &[
+                (0.0, 0.05, -0.85, -0.8),
+                (-0.9, -0.8, -1.1, -0.95),
+                (-0.9, -0.5, -0.95, -0.6),
+            ],
+        );
+
+        assert!(MorningStar::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_evening_star() {
+        let candles = make_trend_candles(
+            0.2,
+            &[
+                (0.0, 0.85, -0.05, 0.8),
+                (0.9, 1.1, 0.8, 0.95),
+                (0.9, 0.95, 0.2, 0.3),
+            ],
+        );
+
+        let result = EveningStar::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+        assert!(MorningStar::default().matches(&candles, 0.0).is_none());
+    }
+}
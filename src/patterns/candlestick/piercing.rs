@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use super::CandlestickConfig;
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Long bearish candle, then a bullish one opening at or below its close and closing above its body middle
+#[derive(Debug, Clone, Default)]
+pub struct PiercingLine {
+    pub config: CandlestickConfig,
+}
+
+/// Long bullish candle, then a bearish one opening at or above its close and closing below its body middle
+#[derive(Debug, Clone, Default)]
+pub struct DarkCloudCover {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for PiercingLine {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+
+        let is_piercing = prev.is_bearish()
+            && prev.body() >= self.config.long_body * window.reference
+            && last.is_bullish()
+            && last.open <= prev.close
+            && last.close > prev.body_middle()
+            && last.close < prev.open;
+
+        if !is_piercing {
+            return None;
+        }
+
+        let description = format!(
+            "Piercing line closing at {:.2} above {:.2}",
+            last.close,
+            prev.body_middle()
+        );
+        Some(window.to_result(
+            PatternType::PiercingLine,
+            SignalDirection::Bullish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for DarkCloudCover {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+
+        let is_dark_cloud = prev.is_bullish()
+            && prev.body() >= self.config.long_body * window.reference
+            && last.is_bearish()
+            && last.open >= prev.close
+            && last.close < prev.body_middle()
+            && last.close > prev.open;
+
+        if !is_dark_cloud {
+            return None;
+        }
+
+        let description = format!(
+            "Dark cloud cover closing at {:.2} below {:.2}",
+            last.close,
+            prev.body_middle()
+        );
+        Some(window.to_result(
+            PatternType::DarkCloudCover,
+            SignalDirection::Bearish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_piercing_line() {
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.05, -0.85, -0.8), (-0.9, -0.2, -1.0, -0.25)]);
+
+        let result = PiercingLine::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+    }
+
+    #[test]
+    fn piercing_must_close_above_middle() {
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.05, -0.85, -0.8), (-0.9, -0.5, -1.0, -0.6)]);
+
+        assert!(PiercingLine::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_dark_cloud_cover() {
+        let candles = make_trend_candles(0.2, &[(0.0, 0.85, -0.05, 0.8), (0.9, 1.0, 0.2, 0.25)]);
+
+        let result = DarkCloudCover::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::DarkCloudCover);
+        assert!(PiercingLine::default().matches(&candles, 0.0).is_none());
+    }
+}
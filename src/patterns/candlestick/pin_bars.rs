@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use super::{CandleShape, CandlestickConfig};
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+use crate::patterns::hhll::TrendDirection;
+
+/// Long upper shadow and small body at the bottom of the range after an up move
+#[derive(Debug, Clone, Default)]
+pub struct ShootingStar {
+    pub config: CandlestickConfig,
+}
+
+/// Long upper shadow and small body at the bottom of the range after a down move
+#[derive(Debug, Clone, Default)]
+pub struct InvertedHammer {
+    pub config: CandlestickConfig,
+}
+
+/// Long lower shadow and small body at the top of the range after an up move
+#[derive(Debug, Clone, Default)]
+pub struct HangingMan {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for ShootingStar {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 1)?;
+        let shape = &window.shapes[0];
+
+        if window.prior_trend != TrendDirection::Up
+            || !is_upper_pin(shape, &self.config, window.reference)
+        {
+            return None;
+        }
+
+        let description = format!("Shooting star, upper shadow {:.2}", shape.upper_shadow());
+        Some(window.to_result(
+            PatternType::ShootingStar,
+            SignalDirection::Bearish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for InvertedHammer {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 1)?;
+        let shape = &window.shapes[0];
+
+        if window.prior_trend != TrendDirection::Down
+            || !is_upper_pin(shape, &self.config, window.reference)
+        {
+            return None;
+        }
+
+        let description = format!("Inverted hammer, upper shadow {:.2}", shape.upper_shadow());
+        Some(window.to_result(
+            PatternType::InvertedHammer,
+            SignalDirection::Bullish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for HangingMan {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 1)?;
+        let shape = &window.shapes[0];
+
+        if window.prior_trend != TrendDirection::Up
+            || !is_lower_pin(shape, &self.config, window.reference)
+        {
+            return None;
+        }
+
+        let description = format!("Hanging man, lower shadow {:.2}", shape.lower_shadow());
+        Some(window.to_result(
+            PatternType::HangingMan,
+            SignalDirection::Bearish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(1))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+/// Doji-sized bodies are left to the doji patterns
+fn is_upper_pin(shape: &CandleShape, config: &CandlestickConfig, reference: f64) -> bool {
+    shape.body() > config.doji_body * reference
+        && shape.upper_shadow() >= config.shadow_to_body * shape.body()
+        && shape.upper_shadow() >= config.long_shadow * reference
+        && shape.lower_shadow() <= config.short_shadow * reference
+}
+
+fn is_lower_pin(shape: &CandleShape, config: &CandlestickConfig, reference: f64) -> bool {
+    shape.body() > config.doji_body * reference
+        && shape.lower_shadow() >= config.shadow_to_body * shape.body()
+        && shape.lower_shadow() >= config.long_shadow * reference
+        && shape.upper_shadow() <= config.short_shadow * reference
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    const UPPER_PIN: (f64, f64, f64, f64) = (0.0, 1.0, -0.05, 0.2);
+    const LOWER_PIN: (f64, f64, f64, f64) = (0.0, 0.25, -0.8, 0.2);
+
+    #[test]
+    fn detects_shooting_star_after_up_move() {
+        let candles = make_trend_candles(0.2, &[UPPER_PIN]);
+
+        let result = ShootingStar::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+        assert!(InvertedHammer::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_inverted_hammer_after_down_move() {
+        let candles = make_trend_candles(-0.2, &[UPPER_PIN]);
+
+        let result = InvertedHammer::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert!(ShootingStar::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_hanging_man_after_up_move() {
+        let candles = make_trend_candles(0.2, &[LOWER_PIN]);
+
+        let result = HangingMan::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::HangingMan);
+        assert!(
+            HangingMan::default()
+                .matches(&make_trend_candles(-0.2, &[LOWER_PIN]), 0.0)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use super::{CandleShape, CandlestickConfig};
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+
+/// Three long bullish candles with short upper shadows, each opening within the previous body
+/// and closing higher
+#[derive(Debug, Clone, Default)]
+pub struct ThreeWhiteSoldiers {
+    pub config: CandlestickConfig,
+}
+
+/// Three long bearish candles with short lower shadows, each opening within the previous body
+/// and closing lower
+#[derive(Debug, Clone, Default)]
+pub struct ThreeBlackCrows {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for ThreeWhiteSoldiers {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 3)?;
+        let reference = window.reference;
+
+        let is_long = |shape: &CandleShape| {
+            shape.is_bullish()
+                && shape.body() >= self.config.long_body * reference
+                && shape.upper_shadow() <= self.config.short_shadow * reference
+        };
+
+        let is_soldiers = window.shapes.iter().all(is_long)
+            && window.shapes.windows(2).all(|pair| {
+                pair[1].close > pair[0].close
+                    && pair[1].open >= pair[0].open
+                    && pair[1].open <= pair[0].close
+            });
+
+        if !is_soldiers {
+            return None;
+        }
+
+        let description = format!("Three white soldiers up to {:.2}", window.shapes[2].close);
+        Some(window.to_result(
+            PatternType::ThreeWhiteSoldiers,
+            SignalDirection::Bullish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(3))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for ThreeBlackCrows {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 3)?;
+        let reference = window.reference;
+
+        let is_long = |shape: &CandleShape| {
+            shape.is_bearish()
+                && shape.body() >= self.config.long_body * reference
+                && shape.lower_shadow() <= self.config.short_shadow * reference
+        };
+
+        let is_crows = window.shapes.iter().all(is_long)
+            && window.shapes.windows(2).all(|pair| {
+                pair[1].close < pair[0].close
+                    && pair[1].open <= pair[0].open
+                    && pair[1].open >= pair[0].close
+            });
+
+        if !is_crows {
+            return None;
+        }
+
+        let description = format!("Three black crows down to {:.2}", window.shapes[2].close);
+        Some(window.to_result(
+            PatternType::ThreeBlackCrows,
+            SignalDirection::Bearish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(3))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_three_white_soldiers() {
+        let candles = make_trend_candles(
+            0.0,
+            &[
+                (0.0, 0.75, -0.05, 0.7),
+                (0.4, 1.45, 0.35, 1.4),
+                (1.1, 2.15, 1.05, 2.1),
+            ],
+        );
+
+        let result = ThreeWhiteSoldiers::default()
+            .matches(&candles, 0.0)
+            .unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+    }
+
+    #[test]
+    fn soldiers_reject_long_upper_shadow() {
+        let candles = make_trend_candles(
+            0.0,
+            &[
+                (0.0, 0.75, -0.05, 0.7),
+                (0.4, 1.9, 0.35, 1.4),
+                (1.1, 2.15, 1.05, 2.1),
+            ],
+        );
+
+        assert!(
+            ThreeWhiteSoldiers::default()
+                .matches(&candles, 0.0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn detects_three_black_crows() {
+        let candles = make_trend_candles(
+            0.0,
+            &[
+                (0.0, 0.05, -0.75, -0.7),
+                (-0.4, -0.35, -1.45, -1.4),
+                (-1.1, -1.05, -2.15, -2.1),
+            ],
+        );
+
+        let result = ThreeBlackCrows::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+        assert!(
+            ThreeWhiteSoldiers::default()
+                .matches(&candles, 0.0)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use super::CandlestickConfig;
+use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+use crate::patterns::hhll::TrendDirection;
+
+/// Bullish then bearish candle with equal highs after an up move
+#[derive(Debug, Clone, Default)]
+pub struct TweezerTop {
+    pub config: CandlestickConfig,
+}
+
+/// Bearish then bullish candle with equal lows after a down move
+#[derive(Debug, Clone, Default)]
+pub struct TweezerBottom {
+    pub config: CandlestickConfig,
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for TweezerTop {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+
+        let is_top = window.prior_trend == TrendDirection::Up
+            && prev.is_bullish()
+            && last.is_bearish()
+            && (prev.high - last.high).abs() <= self.config.tolerance * window.reference;
+
+        if !is_top {
+            return None;
+        }
+
+        let description = format!("Tweezer top at {:.2}", prev.high.max(last.high));
+        Some(window.to_result(
+            PatternType::TweezerTop,
+            SignalDirection::Bearish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for TweezerBottom {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, _level: f64) -> Option<PatternResult> {
+        let window = self.config.get_window(candles, 2)?;
+        let (prev, last) = (&window.shapes[0], &window.shapes[1]);
+
+        let is_bottom = window.prior_trend == TrendDirection::Down
+            && prev.is_bearish()
+            && last.is_bullish()
+            && (prev.low - last.low).abs() <= self.config.tolerance * window.reference;
+
+        if !is_bottom {
+            return None;
+        }
+
+        let description = format!("Tweezer bottom at {:.2}", prev.low.min(last.low));
+        Some(window.to_result(
+            PatternType::TweezerBottom,
+            SignalDirection::Bullish,
+            description,
+        ))
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.config.get_lookback(2))
+    }
+
+    fn is_level_based(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_candles::make_trend_candles;
+
+    #[test]
+    fn detects_tweezer_top() {
+        let candles = make_trend_candles(0.2, &[(0.0, 0.6, -0.1, 0.5), (0.5, 0.62, -0.2, -0.1)]);
+
+        let result = TweezerTop::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+    }
+
+    #[test]
+    fn tweezer_top_needs_equal_highs() {
+        let candles = make_trend_candles(0.2, &[(0.0, 0.6, -0.1, 0.5), (0.5, 0.8, -0.2, -0.1)]);
+
+        assert!(TweezerTop::default().matches(&candles, 0.0).is_none());
+    }
+
+    #[test]
+    fn detects_tweezer_bottom() {
+        let candles = make_trend_candles(-0.2, &[(0.0, 0.1, -0.6, -0.5), (-0.5, 0.2, -0.59, 0.1)]);
+
+        let result = TweezerBottom::default().matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert!(
+            TweezerBottom::default()
+                .matches(
+                    &make_trend_candles(0.2, &[(0.0, 0.1, -0.6, -0.5), (-0.5, 0.2, -0.59, 0.1)]),
+                    0.0
+                )
+                .is_none()
+        );
+    }
+}
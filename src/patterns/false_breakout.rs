@@ -0,0 +1,309 @@
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
+use crate::candle::Candle;
+use crate::patterns::Pattern;
+use crate::stop_loss::Luft;
+use crate::{ATR_DEFAULT_PERIOD, AtrCalculator, AtrSmoothing};
+use std::collections::BTreeMap;
+
+pub const FALSE_BREAKOUT_DEFAULT_MAX_DEPTH_ATR: f64 = 0.5;
+
+/// False breakout found by [`FalseBreakoutPattern::find_false_breakout`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FalseBreakoutMatch {
+    /// Direction of the trade: bearish after a failed break up, bullish after a failed break down
+    pub direction: SignalDirection,
+    /// 1 when the same candle broke the level and closed back, 2 when the next candle closed back
+    pub bars: usize,
+    /// Highest high (break up) or lowest low (break down) of the breakout candles
+    pub extreme: f64,
+    /// Distance from the level to the extreme
+    pub depth: f64,
+    pub atr: f64,
+    /// Extreme plus luft on the breakout side
+    pub stop_price: f64,
+    /// Time keys of the breakout candles, oldest first
+    pub time_keys: Vec<u64>,
+}
+
+/// Price goes beyond the level by more than the luft but less than `max_depth_atr` ATRs
+/// and closes back on the side it came from, either on the same candle or on the next one.
+#[derive(Debug, Clone)]
+pub struct FalseBreakoutPattern {
+    /// Breakout must go beyond the level by more than this. The stop is placed this far beyond the extreme
+    pub luft: Luft,
+    /// Breakout must stay within this many ATRs from the level
+    pub max_depth_atr: f64,
+    pub atr_period: usize,
+    /// Precomputed ATR value. `None` means it is calculated from `atr_period` candles before the breakout
+    pub atr: Option<f64>,
+}
+
+impl Default for FalseBreakoutPattern {
+    fn default() -> Self {
+        Self {
+            luft: Luft::new(0.0),
+            max_depth_atr: FALSE_BREAKOUT_DEFAULT_MAX_DEPTH_ATR,
+            atr_period: ATR_DEFAULT_PERIOD,
+            atr: None,
+        }
+    }
+}
+
+impl<TCandle: Candle> Pattern<TCandle> for FalseBreakoutPattern {
+    fn matches(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Option<PatternResult> {
+        let breakout = self.find_false_breakout(candles, level)?;
+        let pattern_type = PatternType::FalseBreakout;
+        let luft = self.luft.get_value();
+
+        let description = format!(
+            "{}-bar false breakout {} {:.2} by {:.2}, stop {:.2}",
+            breakout.bars,
+            match breakout.direction {
+                SignalDirection::Bearish => "above",
+                _ => "below",
+            },
+            level,
+            breakout.depth,
+            breakout.stop_price
+        );
+
+        let evidence = PatternEvidence::new(*breakout.time_keys.last()?, breakout.time_keys)
+            .with_level(level)
+            .with_tolerance_band(level - luft, level + luft)
+            .with_measurement("bars", breakout.bars as f64)
+            .with_measurement("extreme", breakout.extreme)
+            .with_measurement("depth", breakout.depth)
+            .with_measurement("depth_atr", breakout.depth / breakout.atr)
+            .with_measurement("atr", breakout.atr)
+            .with_measurement("stop_price", breakout.stop_price);
+
+        Some(PatternResult {
+            name: format!("{:?}", pattern_type),
+            direction: breakout.direction,
+            description,
+            confidence: None,
+            pattern_type,
+            evidence,
+        })
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        // One more candle gives the previous close of the first ATR candle
+        Some(match self.atr {
+            Some(_) => self.get_lookback(),
+            None => self.get_lookback() + 1,
+        })
+    }
+}
+
+impl FalseBreakoutPattern {
+    pub fn new(luft: Luft) -> Self {
+        Self {
+            luft,
+            ..Default::default()
+        }
+    }
+
+    /// Up to two breakout candles, the candle before them and the ATR candles
+    pub fn get_lookback(&self) -> usize {
+        match self.atr {
+            Some(_) => 3,
+            None => 2 + self.atr_period.max(1),
+        }
+    }
+
+    pub fn find_false_breakout(
+        &self,
+        candles: &BTreeMap<u64, impl Candle>,
+        level: f64,
+    ) -> Option<FalseBreakoutMatch> {
+        let lookback = self.get_lookback();
+        if candles.len() < lookback || (self.atr.is_none() && self.atr_period == 0) {
+            return None;
+        }
+
+        let recent: Vec<_> = candles.iter().rev().take(lookback + 1).collect();
+
+        // 1.0 checks a break up, -1.0 a break down
+        for side in [1.0, -1.0] {
+            // Positive when the price is beyond the level on the breakout side
+            let beyond = |price: f64| side * (price - level);
+
+            let (last, prev, before_prev) = (recent[0].1, recent[1].1, recent[2].1);
+
+            let bars = if beyond(last.get_close()) >= 0.0 {
+                continue;
+            } else if beyond(prev.get_close()) < 0.0 && beyond(last.get_open()) < 0.0 {
+                1
+            } else if beyond(prev.get_close()) > 0.0 && beyond(before_prev.get_close()) < 0.0 {
+                2
+            } else {
+                continue;
+            };
+
+            let extreme = recent[..bars]
+                .iter()
+                .map(|(_, c)| {
+                    if side > 0.0 {
+                        c.get_high()
+                    } else {
+                        c.get_low()
+                    }
+                })
+                .max_by(|a, b| beyond(*a).total_cmp(&beyond(*b)))?;
+            let depth = beyond(extreme);
+
+            let atr = match self.atr {
+                Some(val) => val,
+                None => {
+                    let mut atr_candles: Vec<_> = recent[bars..]
+                        .iter()
+                        .take(self.atr_period + 1)
+                        .map(|(_, c)| *c)
+                        .collect();
+                    atr_candles.reverse();
+
+                    AtrCalculator::new(self.atr_period, AtrSmoothing::Sma)
+                        .calc_recent_slice(&atr_candles)?
+                        .get_value()
+                }
+            };
+
+            if depth <= self.luft.get_value() || depth >= self.max_depth_atr * atr {
+                continue;
+            }
+
+            let mut time_keys: Vec<u64> = recent[..bars].iter().map(|(k, _)| **k).collect();
+            time_keys.reverse();
+
+            return Some(FalseBreakoutMatch {
+                direction: if side > 0.0 {
+                    SignalDirection::Bearish
+                } else {
+                    SignalDirection::Bullish
+                },
+                bars,
+                extreme,
+                depth,
+                atr,
+                stop_price: extreme + side * self.luft.get_value(),
+                time_keys,
+            });
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::test_candles;
+
+    const LEVEL: f64 = 100.0;
+
+    /// Three candles with range 2.0 below the level, then the given ones
+    fn make_candles(prices: &[(f64, f64, f64, f64)]) -> BTreeMap<u64, CandleInstance> {
+        let mut candles = vec![(97.0, 98.5, 96.5, 98.0); 3];
+        candles.extend_from_slice(prices);
+
+        test_candles::make_candles(&candles)
+    }
+
+    fn make_pattern() -> FalseBreakoutPattern {
+        FalseBreakoutPattern {
+            atr_period: 3,
+            ..FalseBreakoutPattern::new(Luft::new(0.1))
+        }
+    }
+
+    #[test]
+    fn one_bar_false_breakout_up() {
+        let candles = make_candles(&[(98.0, 99.8, 97.8, 99.5), (99.5, 100.6, 99.2, 99.6)]);
+
+        let result = make_pattern().matches(&candles, LEVEL).unwrap();
+
+        assert_eq!(result.pattern_type, PatternType::FalseBreakout);
+        assert_eq!(result.direction, SignalDirection::Bearish);
+        assert_eq!(result.evidence.time_keys, vec![4]);
+        assert_eq!(result.evidence.get_measurement("bars"), Some(1.0));
+        assert!((result.evidence.get_measurement("depth").unwrap() - 0.6).abs() < 1e-9);
+        assert!((result.evidence.get_measurement("stop_price").unwrap() - 100.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn two_bar_false_breakout_down() {
+        let candles = make_candles(&[
+            (98.0, 101.0, 97.8, 100.5),
+            (100.5, 100.8, 100.1, 100.4),
+            (100.4, 100.5, 99.5, 99.8),
+            (99.8, 100.4, 99.6, 100.3),
+        ]);
+
+        let breakout = make_pattern().find_false_breakout(&candles, LEVEL).unwrap();
+
+        assert_eq!(breakout.direction, SignalDirection::Bullish);
+        assert_eq!(breakout.bars, 2);
+        assert_eq!(breakout.time_keys, vec![5, 6]);
+        assert_eq!(breakout.extreme, 99.5);
+        assert!((breakout.stop_price - 99.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn breakout_within_luft_is_ignored() {
+        let candles = make_candles(&[(98.0, 99.8, 97.8, 99.5), (99.5, 100.05, 99.2, 99.6)]);
+
+        assert!(make_pattern().matches(&candles, LEVEL).is_none());
+    }
+
+    #[test]
+    fn deep_breakout_is_ignored() {
+        // ATR of the candles before the breakout is 2.0, so a 1.0 deep break is a real move
+        let candles = make_candles(&[(98.0, 99.8, 97.8, 99.5), (99.5, 101.0, 99.2, 99.6)]);
+
+        assert!(make_pattern().matches(&candles, LEVEL).is_none());
+
+        let wide = FalseBreakoutPattern {
+            max_depth_atr: 0.6,
+            ..make_pattern()
+        };
+        assert!(wide.matches(&candles, LEVEL).is_some());
+    }
+
+    #[test]
+    fn atr_includes_gaps() {
+        // The gap down from 98.0 makes the true range of the last candle before the breakout 3.5, not 1.0
+        let candles = make_candles(&[(95.0, 95.5, 94.5, 95.0), (99.0, 101.0, 94.8, 99.6)]);
+
+        let breakout = make_pattern().find_false_breakout(&candles, LEVEL).unwrap();
+
+        assert!((breakout.atr - 2.5).abs() < 1e-9);
+        assert!((breakout.depth - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn close_beyond_level_is_not_false() {
+        let candles = make_candles(&[(98.0, 99.8, 97.8, 99.5), (99.5, 100.6, 99.2, 100.4)]);
+
+        assert!(make_pattern().matches(&candles, LEVEL).is_none());
+    }
+
+    #[test]
+    fn scan_finds_one_and_two_bar_breakouts() {
+        let candles = make_candles(&[
+            (98.0, 99.8, 97.8, 99.5),
+            (99.5, 100.6, 99.2, 99.6),
+            (99.6, 99.9, 99.0, 99.3),
+            (99.3, 100.5, 99.2, 100.3),
+            (100.3, 100.4, 99.1, 99.4),
+        ]);
+
+        let found = make_pattern().scan(&candles, LEVEL);
+        let time_keys: Vec<u64> = found.iter().map(|m| m.time_key).collect();
+
+        assert_eq!(time_keys, vec![4, 7]);
+        assert_eq!(found[1].result.evidence.time_keys, vec![6, 7]);
+    }
+}
@@ -1,35 +1,334 @@
 use super::Pattern;
-use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
 use crate::candle::Candle;
+use crate::patterns::hhll::TrendDirection;
+use crate::stop_loss::Luft;
+use crate::{ATR_DEFAULT_PERIOD, AtrCalculator, AtrSmoothing};
 use std::collections::BTreeMap;
 
+pub const HAMMER_DEFAULT_TREND_PERIOD: usize = 5;
+pub const HAMMER_DEFAULT_MIN_WICK_ATR: f64 = 0.5;
+pub const HAMMER_DEFAULT_WICK_TO_BODY: f64 = 2.0;
+
+/// Share of the confidence given by the wick size (full at one ATR and more)
+const HAMMER_WICK_WEIGHT: f64 = 0.4;
+/// Share of the confidence given by a move against the pin before it
+const HAMMER_TREND_WEIGHT: f64 = 0.3;
+/// Share of the confidence given by a level inside the wick
+const HAMMER_LEVEL_WEIGHT: f64 = 0.3;
+
+/// Pin bar detector.
+///
+/// A long lower wick (hammer) is bullish, a long upper wick is a bearish [`PatternType::ShootingStar`].
+/// The wick is measured in ATR of the candles before the pin. Confidence grows with the wick size,
+/// a move in the opposite direction before the pin and a level (any of `levels` or the analyzed one)
+/// the wick reached within `luft`.
+#[derive(Debug, Clone)]
 pub struct Hammer {
     pub levels: Vec<f64>,
+    /// How far beyond the wick a level still counts as tested
+    pub luft: Luft,
+    pub atr_period: usize,
+    /// Precomputed ATR value. `None` means it is calculated from `atr_period` candles before the pin
+    pub atr: Option<f64>,
+    /// Number of candles before the pin the prior move is taken from
+    pub trend_period: usize,
+    /// Wick is at least this many ATRs
+    pub min_wick_atr: f64,
+    /// Wick is at least this many bodies
+    pub wick_to_body: f64,
+}
+
+impl Default for Hammer {
+    fn default() -> Self {
+        Self {
+            levels: vec![],
+            luft: Luft::new(0.0),
+            atr_period: ATR_DEFAULT_PERIOD,
+            atr: None,
+            trend_period: HAMMER_DEFAULT_TREND_PERIOD,
+            min_wick_atr: HAMMER_DEFAULT_MIN_WICK_ATR,
+            wick_to_body: HAMMER_DEFAULT_WICK_TO_BODY,
+        }
+    }
+}
+
+impl Hammer {
+    pub fn new(levels: Vec<f64>, luft: Luft) -> Self {
+        Self {
+            levels,
+            luft,
+            ..Default::default()
+        }
+    }
+
+    /// Candles needed before the pin for the ATR and the prior move.
+    /// One more candle is used for the previous close of the ATR when there is one
+    fn lookback_before_pin(&self) -> usize {
+        let atr_candles = if self.atr.is_some() {
+            0
+        } else {
+            self.atr_period
+        };
+        atr_candles.max(self.trend_period + 1)
+    }
+
+    /// Level closest to the wick extreme among those the wick reached
+    fn find_confluence(&self, level: f64, wick_from: f64, wick_to: f64) -> Option<f64> {
+        let luft = self.luft.get_value();
+        let (lower, upper) = if wick_to < wick_from {
+            (wick_to - luft, wick_from)
+        } else {
+            (wick_from, wick_to + luft)
+        };
+
+        self.levels
+            .iter()
+            .copied()
+            .chain(std::iter::once(level))
+            .filter(|level| (lower..=upper).contains(level))
+            .min_by(|a, b| (a - wick_to).abs().total_cmp(&(b - wick_to).abs()))
+    }
 }
 
 impl<TCandle: Candle> Pattern<TCandle> for Hammer {
     fn matches(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Option<PatternResult> {
-        let (_, last) = candles.iter().last()?;
+        let needed = self.lookback_before_pin();
+        if candles.len() < needed + 1 || (self.atr.is_none() && self.atr_period == 0) {
+            return None;
+        }
+
+        let mut iter = candles.iter().rev();
+        let (time_key, last) = iter.next()?;
+        let before: Vec<&TCandle> = iter.take(needed + 1).map(|(_, c)| c).collect();
+
         let body = (last.get_open() - last.get_close()).abs();
         let lower_wick = last.get_open().min(last.get_close()) - last.get_low();
         let upper_wick = last.get_high() - last.get_open().max(last.get_close());
-        let is_hammer = body < lower_wick && upper_wick < body;
 
-        if !is_hammer {
+        let (direction, wick, wick_from, wick_to) =
+            if lower_wick >= self.wick_to_body * body && upper_wick < body {
+                let body_bottom = last.get_open().min(last.get_close());
+                (
+                    SignalDirection::Bullish,
+                    lower_wick,
+                    body_bottom,
+                    last.get_low(),
+                )
+            } else if upper_wick >= self.wick_to_body * body && lower_wick < body {
+                let body_top = last.get_open().max(last.get_close());
+                (
+                    SignalDirection::Bearish,
+                    upper_wick,
+                    body_top,
+                    last.get_high(),
+                )
+            } else {
+                return None;
+            };
+
+        let atr = match self.atr {
+            Some(val) => val,
+            None => {
+                let mut atr_candles: Vec<&TCandle> =
+                    before.iter().take(self.atr_period + 1).copied().collect();
+                atr_candles.reverse();
+
+                AtrCalculator::new(self.atr_period, AtrSmoothing::Sma)
+                    .calc_recent_slice(&atr_candles)?
+                    .get_value()
+            }
+        };
+
+        if atr <= 0.0 || wick < self.min_wick_atr * atr {
             return None;
         }
 
-        let mut description = "Hammer detected".to_string();
-        if (last.get_low()..=last.get_high()).contains(&level) {
-            description = format!("Hammer near level {:.2}", level);
+        let trend_to = before[0].get_close();
+        let trend_from = before[self.trend_period].get_close();
+        let prior_trend = if trend_to > trend_from {
+            TrendDirection::Up
+        } else if trend_to < trend_from {
+            TrendDirection::Down
+        } else {
+            TrendDirection::Sideways
+        };
+
+        let with_trend = match direction {
+            SignalDirection::Bullish => prior_trend == TrendDirection::Down,
+            _ => prior_trend == TrendDirection::Up,
+        };
+
+        let confluence = self.find_confluence(level, wick_from, wick_to);
+
+        let wick_atr = wick / atr;
+        let mut confidence = HAMMER_WICK_WEIGHT * wick_atr.min(1.0);
+        if with_trend {
+            confidence += HAMMER_TREND_WEIGHT;
+        }
+        if confluence.is_some() {
+            confidence += HAMMER_LEVEL_WEIGHT;
         }
 
+        let mut description = match direction {
+            SignalDirection::Bullish => format!("Hammer, lower wick {:.2} ATR", wick_atr),
+            _ => format!("Shooting star, upper wick {:.2} ATR", wick_atr),
+        };
+        if with_trend {
+            description.push_str(&format!(" after {:?} move", prior_trend));
+        }
+
+        let mut evidence = PatternEvidence::new(*time_key, vec![*time_key])
+            .with_measurement("body", body)
+            .with_measurement("lower_wick", lower_wick)
+            .with_measurement("upper_wick", upper_wick)
+            .with_measurement("atr", atr)
+            .with_measurement("wick_atr", wick_atr);
+
+        if let Some(level) = confluence {
+            description.push_str(&format!(" at level {:.2}", level));
+            let luft = self.luft.get_value();
+            evidence = evidence
+                .with_level(level)
+                .with_tolerance_band(level - luft, level + luft);
+        }
+
+        let pattern_type = match direction {
+            SignalDirection::Bullish => PatternType::Hammer,
+            _ => PatternType::ShootingStar,
+        };
+
         Some(PatternResult {
-            name: "Hammer".to_string(),
-            direction: SignalDirection::Bullish,
+            name: format!("{:?}", pattern_type),
+            direction,
             description,
-            confidence: None,
-            pattern_type: PatternType::Hammer,
+            confidence: Some(confidence),
+            pattern_type,
+            evidence,
         })
     }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.lookback_before_pin() + 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::test_candles;
+
+    /// Six candles with true range 1.0 moving by `step`, then the pin
+    fn make_candles(step: f64, pin: (f64, f64, f64, f64)) -> BTreeMap<u64, CandleInstance> {
+        let mut prices: Vec<(f64, f64, f64, f64)> = (0..6)
+            .map(|i| {
+                let base = 100.0 + i as f64 * step;
+                (base + 0.1, base + 0.5, base - 0.5, base)
+            })
+            .collect();
+        prices.push(pin);
+
+        test_candles::make_candles(&prices)
+    }
+
+    fn make_hammer(levels: Vec<f64>) -> Hammer {
+        Hammer {
+            atr_period: 5,
+            ..Hammer::new(levels, Luft::new(0.2))
+        }
+    }
+
+    #[test]
+    fn hammer_after_down_move_at_level() {
+        let candles = make_candles(-0.5, (97.6, 97.65, 96.0, 97.5));
+
+        let result = make_hammer(vec![96.1, 90.0])
+            .matches(&candles, 0.0)
+            .unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bullish);
+        assert_eq!(result.evidence.level, Some(96.1));
+        assert!((result.evidence.get_measurement("wick_atr").unwrap() - 1.5).abs() < 1e-9);
+        assert!((result.confidence.unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn level_within_luft_below_the_wick() {
+        let candles = make_candles(-0.5, (97.6, 97.65, 96.0, 97.5));
+
+        let hammer = make_hammer(vec![95.85]);
+
+        assert_eq!(
+            hammer.matches(&candles, 0.0).unwrap().evidence.level,
+            Some(95.85)
+        );
+        assert!(
+            make_hammer(vec![95.5])
+                .matches(&candles, 0.0)
+                .unwrap()
+                .evidence
+                .level
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn analyzed_level_counts_as_confluence() {
+        let candles = make_candles(-0.5, (97.6, 97.65, 96.0, 97.5));
+
+        let result = make_hammer(vec![]).matches(&candles, 96.5).unwrap();
+
+        assert_eq!(result.evidence.level, Some(96.5));
+    }
+
+    #[test]
+    fn shooting_star_is_bearish() {
+        let candles = make_candles(0.5, (102.6, 104.2, 102.55, 102.7));
+
+        let result = make_hammer(vec![104.0]).matches(&candles, 0.0).unwrap();
+
+        assert_eq!(result.direction, SignalDirection::Bearish);
+        assert_eq!(result.pattern_type, PatternType::ShootingStar);
+        assert_eq!(result.evidence.level, Some(104.0));
+        assert!(result.description.starts_with("Shooting star"));
+    }
+
+    #[test]
+    fn trend_against_pin_lowers_confidence() {
+        let after_drop = make_candles(-0.5, (97.6, 97.65, 96.0, 97.5));
+        let after_rise = make_candles(0.5, (102.6, 102.65, 101.0, 102.5));
+
+        let hammer = make_hammer(vec![]);
+        let with_trend = hammer.matches(&after_drop, 0.0).unwrap();
+        let against_trend = hammer.matches(&after_rise, 0.0).unwrap();
+
+        assert_eq!(against_trend.direction, SignalDirection::Bullish);
+        assert!(against_trend.confidence < with_trend.confidence);
+        assert!((against_trend.confidence.unwrap() - HAMMER_WICK_WEIGHT).abs() < 1e-9);
+    }
+
+    #[test]
+    fn short_wick_in_atr_is_ignored() {
+        let candles = make_candles(-0.5, (97.6, 97.65, 97.25, 97.5));
+
+        assert!(make_hammer(vec![]).matches(&candles, 0.0).is_none());
+
+        let low_atr = Hammer {
+            atr: Some(0.2),
+            ..make_hammer(vec![])
+        };
+        assert!(low_atr.matches(&candles, 0.0).is_some());
+    }
+
+    #[test]
+    fn needs_history_for_atr_and_trend() {
+        let mut candles = make_candles(-0.5, (97.6, 97.65, 96.0, 97.5));
+        candles.pop_first();
+
+        let hammer = make_hammer(vec![]);
+
+        assert_eq!(Pattern::<CandleInstance>::lookback(&hammer), Some(8));
+        assert!(hammer.matches(&candles, 0.0).is_none());
+    }
 }
@@ -1,9 +1,13 @@
 use crate::{HowCandleCrossesLevel, candle::Candle, stop_loss::Luft};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BsuBpiIndex {
     pub bsu_index: usize,
     pub bpu_1_index: usize,
     pub bpu_2_index: usize,
+    /// How far BPU-2 stayed from the level. Never exceeds the luft
+    pub luft_distance: f64,
 }
 
 // Returns BSU index
@@ -30,11 +34,14 @@ pub fn find_bpu_bsu(candles: &[impl Candle], level: f64, luft: Luft) -> Option<B
         let bpu_1_to_check = candles.get(i).unwrap();
         let bpu_2_to_check = candles.get(i + 1).unwrap();
 
-        if are_candles_bpu1_and_bpu2(bpu_1_to_check, bpu_2_to_check, level, luft) {
+        if let Some(distance) = calc_bpu_2_distance(bpu_1_to_check, bpu_2_to_check, level)
+            && distance <= luft.get_value()
+        {
             return Some(BsuBpiIndex {
                 bsu_index,
                 bpu_1_index: i,
                 bpu_2_index: i + 1,
+                luft_distance: distance,
             });
         }
     }
@@ -42,15 +49,53 @@ pub fn find_bpu_bsu(candles: &[impl Candle], level: f64, luft: Luft) -> Option<B
     None
 }
 
+/// Every BSU/BPU-1/BPU-2 formation in the history, ordered by BPU-2.
+///
+/// A formation is reported once BPU-2 closes and uses the latest candle touching the level
+/// before BPU-1 as BSU, so no candle after BPU-2 is looked at.
+pub fn scan_bpu_bsu(candles: &[impl Candle], level: f64, luft: Luft) -> Vec<BsuBpiIndex> {
+    let mut result = Vec::new();
+    let mut bsu_index = None;
+
+    for i in 0..candles.len().saturating_sub(1) {
+        if let Some(bsu_index) = bsu_index
+            && let Some(distance) = calc_bpu_2_distance(&candles[i], &candles[i + 1], level)
+            && distance <= luft.get_value()
+        {
+            result.push(BsuBpiIndex {
+                bsu_index,
+                bpu_1_index: i,
+                bpu_2_index: i + 1,
+                luft_distance: distance,
+            });
+        }
+
+        let candle = &candles[i];
+        if candle.get_high() == level || candle.get_low() == level {
+            bsu_index = Some(i);
+        }
+    }
+
+    result
+}
+
 pub fn are_candles_bpu1_and_bpu2(
     bpu_1: &impl Candle,
     bpu_2: &impl Candle,
     level: f64,
     luft: Luft,
 ) -> bool {
+    match calc_bpu_2_distance(bpu_1, bpu_2, level) {
+        Some(distance) => distance <= luft.get_value(),
+        None => false,
+    }
+}
+
+/// Distance between BPU-2 and the level. `None` if the candles are not BPU-1 and BPU-2 from the same side
+pub fn calc_bpu_2_distance(bpu_1: &impl Candle, bpu_2: &impl Candle, level: f64) -> Option<f64> {
     let bpu_1_touch = HowCandleCrossesLevel::from_candle_and_level(bpu_1, level);
     if !bpu_1_touch.is_candle_touches_the_level() {
-        return false;
+        return None;
     }
 
     let bpu_2_touch = HowCandleCrossesLevel::from_candle_and_level(bpu_2, level);
@@ -58,32 +103,32 @@ pub fn are_candles_bpu1_and_bpu2(
     let distance = match bpu_2_touch {
         HowCandleCrossesLevel::CandleIsAbove { distance } => {
             if bpu_1_touch.is_below_or_touches_below() {
-                return false;
+                return None;
             }
             distance
         }
         HowCandleCrossesLevel::CandleIsBelow { distance } => {
             if bpu_1_touch.is_above_or_touches_above() {
-                return false;
+                return None;
             }
             distance
         }
         HowCandleCrossesLevel::CandleTouchesAbove => {
             if bpu_1_touch.is_below_or_touches_below() {
-                return false;
+                return None;
             }
             0.0
         }
         HowCandleCrossesLevel::CandleTouchesBelow => {
             if bpu_1_touch.is_above_or_touches_above() {
-                return false;
+                return None;
             }
             0.0
         }
-        _ => return false,
+        _ => return None,
     };
 
-    distance <= luft.get_value()
+    Some(distance)
 }
 
 //Checking after we detect BPU and BSU the price went our way
@@ -192,6 +237,7 @@ mod tests {
         assert_eq!(0, result.bsu_index);
         assert_eq!(2, result.bpu_1_index);
         assert_eq!(3, result.bpu_2_index);
+        assert_eq!(0.0, result.luft_distance);
     }
 
     #[test]
@@ -367,4 +413,38 @@ mod tests {
         let result = super::find_bpu_bsu(&candles, 7.0, 0.05.into());
         assert!(result.is_none());
     }
+
+    #[test]
+    fn scan_finds_every_formation() {
+        let make = |high: f64, low: f64| CandleInstance {
+            time_key: 0,
+            high,
+            open: low + 1.0,
+            close: high - 1.0,
+            low,
+            volume: 1.0,
+        };
+
+        let candles = vec![
+            make(7.0, 4.0),
+            make(6.0, 3.0),
+            make(7.0, 4.0),
+            make(7.0, 4.0),
+            make(6.0, 3.0),
+            make(5.0, 2.0),
+            make(7.0, 4.0),
+            make(7.0, 4.0),
+        ];
+
+        let found = super::scan_bpu_bsu(&candles, 7.0, 0.02.into());
+
+        let indexes: Vec<_> = found
+            .iter()
+            .map(|f| (f.bsu_index, f.bpu_1_index, f.bpu_2_index))
+            .collect();
+        assert_eq!(indexes, vec![(0, 2, 3), (3, 6, 7)]);
+
+        let first = super::find_bpu_bsu(&candles[..=3], 7.0, 0.02.into()).unwrap();
+        assert_eq!(first.bpu_2_index, found[0].bpu_2_index);
+    }
 }
@@ -1,18 +1,19 @@
 use std::collections::BTreeMap;
 
-use crate::{candle::Candle, round_to_precision};
+use crate::{InstrumentSpec, candle::Candle, round_to_precision};
 
 pub const LTD_DEFAULT_TOLERANCE: u32 = 2;
 pub const LTD_MIN_WINDOW_SIZE: usize = 3;
 
 #[derive(Debug, Clone)]
 pub struct LimitTraderDetectorPattern {
-    pub accuracy: u32,
+    pub spec: InstrumentSpec,
     pub points_tolerance: u32,
     pub window_size: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LimitTraderSignal {
     pub level: f64,  
     pub date_time_key: u64,             
@@ -20,6 +21,7 @@ pub struct LimitTraderSignal {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LimitTraderSide {
     Buyer,  // Limit Trader on lows
     Seller, // Limit Trader on highs
@@ -35,12 +37,12 @@ impl LimitTraderSide {
 }
 
 impl LimitTraderDetectorPattern {
-    pub fn new(accuracy: u32, tolerance: u32, window_size: usize) -> Self {
-        Self { accuracy, points_tolerance: tolerance, window_size }
+    pub fn new(spec: impl Into<InstrumentSpec>, tolerance: u32, window_size: usize) -> Self {
+        Self { spec: spec.into(), points_tolerance: tolerance, window_size }
     }
 
     pub fn calc_points_tolerance(&self) -> f64 {
-        crate::math::calc_points_tolerance(self.accuracy, self.points_tolerance)
+        crate::math::calc_points_tolerance(self.spec, self.points_tolerance)
     }
 
     pub fn detect<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<LimitTraderSignal> {
@@ -50,53 +52,74 @@ impl LimitTraderDetectorPattern {
             return None;
         }
 
-        for window in candle_vec.windows(self.window_size) {
-            
-            let avg_high = round_to_precision(
-                window.iter().map(|c| c.get_high()).sum::<f64>() / window.len() as f64,
-                self.accuracy,
-            );
-
-            let avg_low = round_to_precision(
-                window.iter().map(|c| c.get_low()).sum::<f64>() / window.len() as f64,
-                self.accuracy,
-            );
-
-            // let max_high = window.iter().map(|c| c.get_high()).fold(f64::MIN, f64::max);
-            // let min_high = window.iter().map(|c| c.get_high()).fold(f64::MAX, f64::min);
-            // let max_low = window.iter().map(|c| c.get_low()).fold(f64::MIN, f64::max);
-            // let min_low = window.iter().map(|c| c.get_low()).fold(f64::MAX, f64::min);
-
-            let date_time= window.first().unwrap().get_time_key();
-            // check highs near avg_high
-            let highs_near = window.iter().all(|c| {
-                let distance = (c.get_high() - avg_high).abs();
-                distance <= self.calc_points_tolerance()
-            });
+        candle_vec
+            .windows(self.window_size)
+            .find_map(|window| self.check_window(window))
+    }
 
-            // check lows near avg_low
-            let lows_near = window.iter().all(|c| {
-                let distance = (c.get_low() - avg_low).abs();
-                distance <= self.calc_points_tolerance()
-            });
+    /// Signals of every window in the history, oldest first.
+    ///
+    /// Each window ends at the signal's `date_time_key`, so later candles are never used.
+    pub fn scan<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<LimitTraderSignal> {
+        let candle_vec: Vec<&T> = candles.values().rev().collect();
+
+        if self.window_size == 0 || candle_vec.len() < self.window_size {
+            return Vec::new();
+        }
+
+        candle_vec
+            .windows(self.window_size)
+            .rev()
+            .filter_map(|window| self.check_window(window))
+            .collect()
+    }
+
+    /// `window` is ordered from the newest candle
+    fn check_window<T: Candle>(&self, window: &[&T]) -> Option<LimitTraderSignal> {
+        let avg_high = round_to_precision(
+            window.iter().map(|c| c.get_high()).sum::<f64>() / window.len() as f64,
+            self.spec,
+        );
+
+        let avg_low = round_to_precision(
+            window.iter().map(|c| c.get_low()).sum::<f64>() / window.len() as f64,
+            self.spec,
+        );
 
-            // check mixed candle directions
-            let up_count = window.iter().filter(|c| c.get_close() > c.get_open()).count();
-            let down_count = window.iter().filter(|c| c.get_close() < c.get_open()).count();
-
-            if highs_near && up_count > 0 && down_count > 0 {
-                return Some(LimitTraderSignal {
-                    level: avg_high,
-                    date_time_key: date_time,
-                    side: LimitTraderSide::Seller,
-                });
-            } else if lows_near && up_count > 0 && down_count > 0 {
-                return Some(LimitTraderSignal {
-                    level: avg_low,
-                    date_time_key: date_time,
-                    side: LimitTraderSide::Buyer,
-                });
-            }
+        // let max_high = window.iter().map(|c| c.get_high()).fold(f64::MIN, f64::max);
+        // let min_high = window.iter().map(|c| c.get_high()).fold(f64::MAX, f64::min);
+        // let max_low = window.iter().map(|c| c.get_low()).fold(f64::MIN, f64::max);
+        // let min_low = window.iter().map(|c| c.get_low()).fold(f64::MAX, f64::min);
+
+        let date_time= window.first().unwrap().get_time_key();
+        // check highs near avg_high
+        let highs_near = window.iter().all(|c| {
+            let distance = (c.get_high() - avg_high).abs();
+            distance <= self.calc_points_tolerance()
+        });
+
+        // check lows near avg_low
+        let lows_near = window.iter().all(|c| {
+            let distance = (c.get_low() - avg_low).abs();
+            distance <= self.calc_points_tolerance()
+        });
+
+        // check mixed candle directions
+        let up_count = window.iter().filter(|c| c.get_close() > c.get_open()).count();
+        let down_count = window.iter().filter(|c| c.get_close() < c.get_open()).count();
+
+        if highs_near && up_count > 0 && down_count > 0 {
+            return Some(LimitTraderSignal {
+                level: avg_high,
+                date_time_key: date_time,
+                side: LimitTraderSide::Seller,
+            });
+        } else if lows_near && up_count > 0 && down_count > 0 {
+            return Some(LimitTraderSignal {
+                level: avg_low,
+                date_time_key: date_time,
+                side: LimitTraderSide::Buyer,
+            });
         }
 
         None
@@ -142,6 +165,26 @@ mod tests {
         assert!((signal.level - signal_level).abs() <= f64::EPSILON);
     }
 
+    #[test]
+    fn scan_reports_window_end_without_look_ahead() {
+        let candles = vec![
+            CandleInstance { time_key: 1, open: 100.0, close: 99.0, high: 105.0, low: 98.0, volume: 1.0, },
+            CandleInstance { time_key: 2, open: 99.0, close: 100.5, high: 105.09, low: 97.5, volume: 1.0, },
+            CandleInstance { time_key: 3, open: 101.0, close: 100.0, high: 105.10, low: 99.0, volume: 1.0, },
+            CandleInstance { time_key: 4, open: 101.0, close: 100.0, high: 105.08, low: 99.0, volume: 1.0, },
+            CandleInstance { time_key: 5, open: 101.0, close: 100.0, high: 105.75, low: 99.0, volume: 1.0, },
+            CandleInstance { time_key: 6, open: 99.0, close: 99.5, high: 101.0, low: 99.01, volume: 1.0, },
+        ];
+
+        let map: BTreeMap<u64, CandleInstance> = candles.into_iter().map(|c| (c.time_key, c)).collect();
+
+        let detector = LimitTraderDetectorPattern::new(2, LTD_DEFAULT_TOLERANCE, LTD_MIN_WINDOW_SIZE);
+        let signals = detector.scan(&map);
+
+        let found: Vec<_> = signals.iter().map(|s| (s.date_time_key, s.side.clone())).collect();
+        assert_eq!(found, vec![(4, LimitTraderSide::Seller), (6, LimitTraderSide::Buyer)]);
+    }
+
     #[test]
     fn detects_limit_buyer() {
         let signal_level = 90.05;
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+
+use super::{LimitTraderDetectorPattern, LimitTraderSide, LimitTraderSignal};
+use crate::candle::Candle;
+
+pub const LTT_DEFAULT_ABANDON_AFTER: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LimitTraderOutcome {
+    /// Level was not broken nor left by the end of the history
+    Held,
+    /// Price closed beyond the level: the limit volume was taken out
+    Absorbed,
+    /// Price stayed away from the level for `abandon_after` candles
+    Abandoned,
+}
+
+impl LimitTraderOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitTraderOutcome::Held => "Held",
+            LimitTraderOutcome::Absorbed => "Absorbed",
+            LimitTraderOutcome::Abandoned => "Abandoned",
+        }
+    }
+}
+
+/// Limit trader level followed from its detection to the outcome
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimitTraderLevel {
+    pub signal: LimitTraderSignal,
+    pub outcome: LimitTraderOutcome,
+    /// Candles which reached the level, including the detection window
+    pub touches: usize,
+    /// Volume of the touching candles and of the candle which broke the level
+    pub absorbed_volume: f64,
+    pub last_touch_time_key: u64,
+    /// Candle which broke the level or after which it was abandoned. `None` while held
+    pub resolved_time_key: Option<u64>,
+}
+
+/// Lists every limit trader level of a history and tracks it until it is broken or abandoned.
+///
+/// A candle reaches a seller level when its high is within the detector tolerance below it or above it,
+/// and breaks it when it closes above the level by more than the tolerance. Buyer levels are mirrored.
+/// Signals repeating an unresolved level of the same side are skipped.
+#[derive(Debug, Clone)]
+pub struct LimitTraderTracker {
+    pub detector: LimitTraderDetectorPattern,
+    /// Level is abandoned after this many candles in a row without a touch
+    pub abandon_after: usize,
+}
+
+impl LimitTraderTracker {
+    pub fn new(detector: LimitTraderDetectorPattern, abandon_after: usize) -> Self {
+        Self {
+            detector,
+            abandon_after,
+        }
+    }
+
+    /// Tracked levels, oldest signal first
+    pub fn track<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<LimitTraderLevel> {
+        let tolerance = self.detector.calc_points_tolerance();
+        let mut result: Vec<LimitTraderLevel> = Vec::new();
+
+        for signal in self.detector.scan(candles) {
+            let is_repeated = result.iter().any(|tracked| {
+                tracked.signal.side == signal.side
+                    && (tracked.signal.level - signal.level).abs() <= tolerance
+                    && tracked
+                        .resolved_time_key
+                        .is_none_or(|resolved| resolved >= signal.date_time_key)
+            });
+
+            if !is_repeated {
+                result.push(self.track_level(candles, signal, tolerance));
+            }
+        }
+
+        result
+    }
+
+    fn track_level<T: Candle>(
+        &self,
+        candles: &BTreeMap<u64, T>,
+        signal: LimitTraderSignal,
+        tolerance: f64,
+    ) -> LimitTraderLevel {
+        // Positive when the price is beyond the level on the side the limit trader defends
+        let side = match signal.side {
+            LimitTraderSide::Seller => 1.0,
+            LimitTraderSide::Buyer => -1.0,
+        };
+        let level = signal.level;
+
+        let window: Vec<&T> = candles
+            .range(..=signal.date_time_key)
+            .rev()
+            .take(self.detector.window_size)
+            .map(|(_, c)| c)
+            .collect();
+
+        let mut tracked = LimitTraderLevel {
+            outcome: LimitTraderOutcome::Held,
+            touches: window.len(),
+            absorbed_volume: window.iter().map(|c| c.get_volume()).sum(),
+            last_touch_time_key: signal.date_time_key,
+            resolved_time_key: None,
+            signal,
+        };
+
+        let mut candles_without_touch = 0;
+
+        for (time_key, candle) in candles.range(tracked.signal.date_time_key + 1..) {
+            let extreme = match tracked.signal.side {
+                LimitTraderSide::Seller => candle.get_high(),
+                LimitTraderSide::Buyer => candle.get_low(),
+            };
+
+            if side * (candle.get_close() - level) > tolerance {
+                tracked.outcome = LimitTraderOutcome::Absorbed;
+                tracked.absorbed_volume += candle.get_volume();
+                tracked.resolved_time_key = Some(*time_key);
+                break;
+            }
+
+            if side * (extreme - level) >= -tolerance {
+                tracked.touches += 1;
+                tracked.absorbed_volume += candle.get_volume();
+                tracked.last_touch_time_key = *time_key;
+                candles_without_touch = 0;
+                continue;
+            }
+
+            candles_without_touch += 1;
+            if candles_without_touch >= self.abandon_after {
+                tracked.outcome = LimitTraderOutcome::Abandoned;
+                tracked.resolved_time_key = Some(*time_key);
+                break;
+            }
+        }
+
+        tracked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::patterns::{LTD_DEFAULT_TOLERANCE, LTD_MIN_WINDOW_SIZE};
+
+    /// Limit seller at 105.09 found on the window ending at 4
+    fn make_candles(after: &[(f64, f64, f64, f64, f64)]) -> BTreeMap<u64, CandleInstance> {
+        let seller = [
+            (100.0, 105.0, 98.0, 99.0, 1.0),
+            (99.0, 105.09, 97.5, 100.5, 1.0),
+            (101.0, 105.10, 99.0, 100.0, 1.0),
+            (101.0, 105.08, 99.0, 100.0, 1.0),
+        ];
+
+        seller
+            .iter()
+            .chain(after)
+            .enumerate()
+            .map(|(i, (open, high, low, close, volume))| {
+                let time_key = i as u64 + 1;
+                (
+                    time_key,
+                    CandleInstance {
+                        time_key,
+                        open: *open,
+                        high: *high,
+                        low: *low,
+                        close: *close,
+                        volume: *volume,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn make_tracker(abandon_after: usize) -> LimitTraderTracker {
+        LimitTraderTracker::new(
+            LimitTraderDetectorPattern::new(2, LTD_DEFAULT_TOLERANCE, LTD_MIN_WINDOW_SIZE),
+            abandon_after,
+        )
+    }
+
+    #[test]
+    fn held_level_is_reported_once() {
+        // Candle 5 touches the level and forms another seller window which repeats the tracked level
+        let candles = make_candles(&[
+            (99.5, 105.09, 99.0, 100.0, 2.0),
+            (100.0, 104.0, 99.5, 101.0, 1.0),
+        ]);
+
+        let levels = make_tracker(3).track(&candles);
+
+        assert_eq!(levels.len(), 1);
+        let level = &levels[0];
+        assert_eq!(level.signal.side, LimitTraderSide::Seller);
+        assert_eq!(level.outcome, LimitTraderOutcome::Held);
+        assert_eq!(level.touches, 4);
+        assert_eq!(level.absorbed_volume, 5.0);
+        assert_eq!(level.last_touch_time_key, 5);
+        assert_eq!(level.resolved_time_key, None);
+    }
+
+    #[test]
+    fn close_beyond_level_absorbs_it() {
+        let candles = make_candles(&[(100.0, 105.5, 99.8, 105.4, 5.0)]);
+
+        let levels = make_tracker(3).track(&candles);
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].outcome, LimitTraderOutcome::Absorbed);
+        assert_eq!(levels[0].resolved_time_key, Some(5));
+        assert_eq!(levels[0].absorbed_volume, 8.0);
+    }
+
+    #[test]
+    fn level_left_alone_is_abandoned() {
+        let candles = make_candles(&[
+            (100.0, 103.0, 99.5, 101.0, 1.0),
+            (101.0, 103.0, 99.6, 100.5, 1.0),
+        ]);
+
+        let levels = make_tracker(2).track(&candles);
+
+        assert_eq!(levels[0].outcome, LimitTraderOutcome::Abandoned);
+        assert_eq!(levels[0].resolved_time_key, Some(6));
+        assert_eq!(levels[0].touches, 3);
+    }
+
+    #[test]
+    fn buyer_level_broken_down() {
+        let candles: BTreeMap<u64, CandleInstance> = [
+            (95.0, 96.5, 90.0, 96.5),
+            (96.0, 94.8, 90.04, 95.0),
+            (94.5, 96.8, 90.06, 96.0),
+            (94.5, 99.8, 90.05, 96.0),
+            (94.5, 96.8, 90.75, 96.0),
+            (91.0, 91.2, 89.4, 89.5),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (open, high, low, close))| {
+            let time_key = i as u64 + 1;
+            let candle = CandleInstance {
+                time_key,
+                open: *open,
+                high: *high,
+                low: *low,
+                close: *close,
+                volume: 1.0,
+            };
+            (time_key, candle)
+        })
+        .collect();
+
+        let levels = make_tracker(LTT_DEFAULT_ABANDON_AFTER).track(&candles);
+
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].signal.side, LimitTraderSide::Buyer);
+        assert_eq!(levels[0].outcome, LimitTraderOutcome::Absorbed);
+        assert_eq!(levels[0].resolved_time_key, Some(6));
+        assert_eq!(levels[0].touches, 3);
+        assert_eq!(levels[0].absorbed_volume, 4.0);
+    }
+}
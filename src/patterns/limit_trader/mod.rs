@@ -1,2 +1,4 @@
 mod limit_trader_detector;
-pub use limit_trader_detector::*;
\ No newline at end of file
+pub use limit_trader_detector::*;
+mod limit_trader_tracker;
+pub use limit_trader_tracker::*;
@@ -5,6 +5,8 @@ mod small_bar_approach;
 mod trend;
 mod retest;
 mod pressure_buildup;
+mod false_breakout;
+mod breakout;
 
 use std::collections::BTreeMap;
 pub use atr_spike::AtrSpike;
@@ -13,13 +15,141 @@ pub use small_bar_approach::SmallBarApproach;
 pub use trend::*;
 pub use retest::*;
 pub use pressure_buildup::*;
+pub use false_breakout::*;
+pub use breakout::*;
 
-use crate::analyzer::PatternResult;
+use crate::analyzer::{PatternMatch, PatternResult};
 use crate::candle::*;
 
 mod limit_trader;
 pub use limit_trader::*;
 
-pub trait Pattern<TCandle: Candle> {
+mod candlestick;
+pub use candlestick::*;
+
+pub trait Pattern<TCandle: Candle>: Send + Sync {
     fn matches(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Option<PatternResult>;
+
+    /// Number of most recent candles `matches` looks at. `None` means the whole history is needed.
+    ///
+    /// Used by the streaming analyzer to decide how many candles to keep.
+    fn lookback(&self) -> Option<usize> {
+        None
+    }
+
+    /// `false` for patterns which ignore the `level` argument, so multi-level
+    /// analysis evaluates them only once.
+    fn is_level_based(&self) -> bool {
+        true
+    }
+
+    /// Evaluates the pattern at every candle of the history and returns all matches, oldest first.
+    ///
+    /// Each evaluation sees only the candles up to the evaluation point, the same as `matches` would
+    /// in live trading. Only the last [`Pattern::lookback`] candles are kept when the pattern has one.
+    fn scan(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Vec<PatternMatch>
+    where
+        TCandle: Clone,
+    {
+        let keep = self.lookback().map(|lookback| lookback.max(1));
+        let mut history = BTreeMap::new();
+        let mut result = Vec::new();
+
+        for (time_key, candle) in candles {
+            history.insert(*time_key, candle.clone());
+
+            if let Some(keep) = keep {
+                while history.len() > keep {
+                    history.pop_first();
+                }
+            }
+
+            if let Some(pattern_result) = self.matches(&history, level) {
+                result.push(PatternMatch {
+                    time_key: *time_key,
+                    result: pattern_result,
+                });
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{PatternEvidence, PatternType, SignalDirection};
+    use crate::test_candles::make_candles;
+
+    #[test]
+    fn scan_finds_every_occurrence_without_look_ahead() {
+        let candles = make_candles(&[
+            (5.0, 7.0, 4.0, 6.0),
+            (5.0, 7.0, 4.0, 6.0),
+            (5.0, 7.0, 4.0, 6.0),
+            (6.0, 6.05, 2.0, 5.9),
+            (5.9, 7.0, 5.5, 6.8),
+            (6.8, 6.85, 3.0, 6.7),
+            (6.7, 9.0, 6.5, 8.5),
+        ]);
+
+        let pattern = Hammer {
+            atr_period: 2,
+            trend_period: 1,
+            ..Hammer::default()
+        };
+        let found = pattern.scan(&candles, 6.0);
+
+        let time_keys: Vec<u64> = found.iter().map(|m| m.time_key).collect();
+        assert_eq!(time_keys, vec![3, 5]);
+        assert!(found.iter().all(|m| m.result.pattern_type == PatternType::Hammer));
+
+        // Same results as evaluating every prefix of the history
+        for found in found {
+            let prefix: BTreeMap<u64, CandleInstance> = candles
+                .range(..=found.time_key)
+                .map(|(k, c)| (*k, c.clone()))
+                .collect();
+
+            let expected = pattern.matches(&prefix, 6.0).unwrap();
+            assert_eq!(expected.evidence, found.result.evidence);
+        }
+    }
+
+    /// Pattern without a lookback which reports every candle it was given
+    struct WholePrefix;
+
+    impl Pattern<CandleInstance> for WholePrefix {
+        fn matches(
+            &self,
+            candles: &BTreeMap<u64, CandleInstance>,
+            _level: f64,
+        ) -> Option<PatternResult> {
+            let (time_key, _) = candles.last_key_value()?;
+            let pattern_type = PatternType::CloseRetest;
+
+            Some(PatternResult {
+                name: format!("{:?}", pattern_type),
+                direction: SignalDirection::Neutral,
+                description: String::new(),
+                confidence: None,
+                pattern_type,
+                evidence: PatternEvidence::new(*time_key, candles.keys().copied().collect()),
+            })
+        }
+    }
+
+    #[test]
+    fn scan_with_unbounded_lookback_sees_whole_prefix() {
+        let candles = make_candles(&[(5.0, 7.0, 4.0, 6.0); 5]);
+
+        let found = WholePrefix.scan(&candles, 7.0);
+
+        assert_eq!(found.len(), 5);
+        for found in found {
+            let expected: Vec<u64> = (0..=found.time_key).collect();
+            assert_eq!(found.result.evidence.time_keys, expected);
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
 use crate::candle::Candle;
 use crate::get_bounds;
 use crate::patterns::Pattern;
@@ -16,7 +16,7 @@ pub struct PressureBuildupPattern {
 impl<TCandle: Candle> Pattern<TCandle> for PressureBuildupPattern {
     fn matches(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Option<PatternResult> {
         let (lower_bound, upper_bound) = get_bounds(level, self.tolerance_percent);
-        let last_candles: Vec<_> = candles.values().rev().take(PERIOD).collect();
+        let last_candles: Vec<_> = candles.iter().rev().take(PERIOD).collect();
 
         if last_candles.len() < PERIOD {
             return None; // Not enough candles
@@ -24,7 +24,7 @@ impl<TCandle: Candle> Pattern<TCandle> for PressureBuildupPattern {
 
         let mut under_level = false;
 
-        for (i, candle) in last_candles.iter().enumerate() {
+        for (i, (_, candle)) in last_candles.iter().enumerate() {
             let prev_under_level = under_level;
             under_level = candle.get_high() <= upper_bound;
 
@@ -44,6 +44,10 @@ impl<TCandle: Candle> Pattern<TCandle> for PressureBuildupPattern {
         }
 
         let pattern_type = PatternType::PressureBuildup;
+        let time_keys: Vec<u64> = last_candles.iter().rev().map(|(key, _)| **key).collect();
+        let evidence = PatternEvidence::new(*last_candles[0].0, time_keys)
+            .with_level(level)
+            .with_tolerance_band(lower_bound, upper_bound);
 
         Some(PatternResult {
             name: format!("{:?}", pattern_type),
@@ -55,8 +59,13 @@ impl<TCandle: Candle> Pattern<TCandle> for PressureBuildupPattern {
             description: "".to_string(),
             confidence: None,
             pattern_type,
+            evidence,
         })
     }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(PERIOD)
+    }
 }
 
 impl Default for PressureBuildupPattern {
@@ -1,7 +1,7 @@
-use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
 use crate::candle::Candle;
-use crate::in_range;
 use crate::patterns::Pattern;
+use crate::{get_bounds, in_range};
 use std::collections::BTreeMap;
 
 const LEVEL_TOLERANCE_PERCENT: f64 = 2.0;
@@ -10,11 +10,24 @@ const LONG_PERIOD: usize = 30;
 const DEPTH_PERIOD: usize = 10;
 
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Ord, PartialOrd, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RetestPatternType {
     Close,
     Long,
 }
 
+/// Retest found by [`RetestPattern::find_retest`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetestMatch {
+    pub direction: BumpDirection,
+    pub retest_type: RetestPatternType,
+    /// Time keys of the candles which bumped into the level, oldest first
+    pub bump_time_keys: Vec<u64>,
+    /// Time keys from the first bump to the last candle, oldest first
+    pub time_keys: Vec<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RetestPattern {
     pub tolerance_percent: f64,
@@ -25,10 +38,17 @@ pub struct RetestPattern {
 
 impl<TCandle: Candle> Pattern<TCandle> for RetestPattern {
     fn matches(&self, candles: &BTreeMap<u64, TCandle>, level: f64) -> Option<PatternResult> {
-        let (direction, pattern_type) = self.get_type(candles, level)?;
+        let retest = self.find_retest(candles, level)?;
+        let pattern_type = retest.retest_type;
         let name = format!("{:?}", pattern_type);
 
-        let direction = match direction {
+        let (lower_bound, upper_bound) = get_bounds(level, LEVEL_TOLERANCE_PERCENT);
+        let evidence = PatternEvidence::new(*retest.time_keys.last()?, retest.time_keys)
+            .with_level(level)
+            .with_tolerance_band(lower_bound, upper_bound)
+            .with_measurement("bump_count", retest.bump_time_keys.len() as f64);
+
+        let direction = match retest.direction {
             BumpDirection::FromBelow => SignalDirection::Bullish,
             BumpDirection::FromAbove => SignalDirection::Bearish,
         };
@@ -40,6 +60,7 @@ impl<TCandle: Candle> Pattern<TCandle> for RetestPattern {
                 description: "".to_string(),
                 confidence: None,
                 pattern_type: PatternType::CloseRetest,
+                evidence,
             },
             RetestPatternType::Long => PatternResult {
                 name,
@@ -47,12 +68,21 @@ impl<TCandle: Candle> Pattern<TCandle> for RetestPattern {
                 description: "".to_string(),
                 confidence: None,
                 pattern_type: PatternType::LongRetest,
+                evidence,
             },
         };
 
         Some(result)
     }
+
+    /// Retests are searched within the last `LONG_PERIOD + 1` candles. Two more candles keep
+    /// the check of the second oldest candle out of that range, so a trimmed history gives
+    /// the same result as the whole one.
+    fn lookback(&self) -> Option<usize> {
+        Some(LONG_PERIOD + 3)
+    }
 }
+
 impl Default for RetestPattern {
     fn default() -> Self {
         Self {
@@ -70,6 +100,15 @@ impl RetestPattern {
         candles: &BTreeMap<u64, impl Candle>,
         level: f64,
     ) -> Option<(BumpDirection, RetestPatternType)> {
+        self.find_retest(candles, level)
+            .map(|retest| (retest.direction, retest.retest_type))
+    }
+
+    pub fn find_retest(
+        &self,
+        candles: &BTreeMap<u64, impl Candle>,
+        level: f64,
+    ) -> Option<RetestMatch> {
         if candles.len() < 3 {
             return None;
         }
@@ -77,13 +116,21 @@ impl RetestPattern {
         let mut bumps_count = 0;
         let mut result = None;
         let mut bump_dir = None;
+        let mut time_keys = Vec::new();
+        let mut bump_time_keys = Vec::new();
+
+        for (index, (key, candle)) in candles.iter().rev().enumerate() {
+            if index > LONG_PERIOD {
+                break;
+            }
 
-        for (index, (_key, candle)) in candles.iter().rev().enumerate() {
             let prev_bump_dir = bump_dir;
             bump_dir = bumped_into_level(candle, level, LEVEL_TOLERANCE_PERCENT);
+            time_keys.push(*key);
 
             if bump_dir.is_some() {
                 bumps_count += 1;
+                bump_time_keys.push(*key);
             }
 
             if index == 0 && bump_dir.is_none() {
@@ -110,7 +157,16 @@ impl RetestPattern {
             }
         }
 
-        result
+        let (direction, retest_type) = result?;
+        time_keys.reverse();
+        bump_time_keys.reverse();
+
+        Some(RetestMatch {
+            direction,
+            retest_type,
+            bump_time_keys,
+            time_keys,
+        })
     }
 }
 
@@ -135,6 +191,7 @@ fn bumped_into_level(
 }
 
 #[derive(Debug, Clone, PartialEq, Hash, Ord, PartialOrd, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BumpDirection {
     FromBelow,
     FromAbove,
@@ -143,7 +200,7 @@ pub enum BumpDirection {
 #[cfg(test)]
 mod tests {
     use crate::candle::CandleInstance;
-    use crate::patterns::{BumpDirection, RetestPattern, RetestPatternType};
+    use crate::patterns::{BumpDirection, Pattern, RetestPattern, RetestPatternType};
     use std::collections::BTreeMap;
 
     #[test]
@@ -193,5 +250,49 @@ mod tests {
             result,
             Some((BumpDirection::FromBelow, RetestPatternType::Close))
         );
+
+        let result = pattern.matches(&candles, 7.0).unwrap();
+
+        assert_eq!(result.evidence.trigger_time_key, 3);
+        assert_eq!(result.evidence.time_keys, vec![2, 3]);
+        assert_eq!(result.evidence.get_measurement("bump_count"), Some(2.0));
+    }
+
+    #[test]
+    fn trimmed_history_gives_same_results() {
+        // Candles touch the 7.0 level every seventh bar and on a short cluster, 80 bars in total
+        let candles: BTreeMap<u64, CandleInstance> = (0..80u64)
+            .map(|i| {
+                let high = if i % 7 == 0 || (40..45).contains(&i) { 7.0 } else { 6.0 };
+                let candle = CandleInstance {
+                    time_key: i,
+                    high,
+                    open: 5.0,
+                    close: 5.5,
+                    low: 4.0,
+                    volume: 1.0,
+                };
+                (i, candle)
+            })
+            .collect();
+
+        let pattern = RetestPattern::default();
+        let scanned: Vec<u64> = pattern
+            .scan(&candles, 7.0)
+            .iter()
+            .map(|m| m.time_key)
+            .collect();
+
+        let mut history = BTreeMap::new();
+        let mut expected = Vec::new();
+        for (time_key, candle) in candles.iter() {
+            history.insert(*time_key, candle.clone());
+            if pattern.matches(&history, 7.0).is_some() {
+                expected.push(*time_key);
+            }
+        }
+
+        assert!(!expected.is_empty());
+        assert_eq!(scanned, expected);
     }
 }
@@ -1,5 +1,5 @@
 use super::Pattern;
-use crate::analyzer::{PatternResult, PatternType, SignalDirection};
+use crate::analyzer::{PatternEvidence, PatternResult, PatternType, SignalDirection};
 use crate::candle::Candle;
 use std::collections::BTreeMap;
 
@@ -15,6 +15,7 @@ impl<TCandle: Candle> Pattern<TCandle> for SmallBarApproach {
             return None;
         }
 
+        let time_keys: Vec<u64> = candles.keys().skip(candles.len() - self.period).copied().collect();
         let candles: Vec<&TCandle> = candles.values().collect();
         let window = &candles[candles.len() - self.period..];
 
@@ -50,11 +51,19 @@ impl<TCandle: Candle> Pattern<TCandle> for SmallBarApproach {
                 //TODO: Calc automatically
                 confidence: Some(0.8),
                 pattern_type: PatternType::SmallBarApproach,
+                evidence: PatternEvidence::new(*time_keys.last()?, time_keys.clone())
+                    .with_level(level)
+                    .with_measurement("avg_body_ratio", avg_body_ratio)
+                    .with_measurement("body_ratio_threshold", threshold),
             });
         }
 
         None
     }
+
+    fn lookback(&self) -> Option<usize> {
+        Some(self.period)
+    }
 }
 
 fn is_small_bar(c: &impl Candle, threshold: f64) -> bool {
@@ -2,7 +2,8 @@ use std::collections::BTreeMap;
 
 use crate::candle::Candle;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrendDirection {
     Up,
     Down,
@@ -16,7 +17,34 @@ pub struct HHLLTrendDetector {
 impl HHLLTrendDetector {
     pub fn detect_trend<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<TrendDirection> {
         let candle_vec: Vec<&T> = candles.values().rev().collect();
+        self.detect_trend_newest_first(&candle_vec)
+    }
+
+    /// Trend of the `window_size` candles ending at every candle of the history, oldest first.
+    ///
+    /// The first `window_size - 1` candles have no full window and are skipped.
+    pub fn scan_trend<T: Candle>(
+        &self,
+        candles: &BTreeMap<u64, T>,
+        window_size: usize,
+    ) -> Vec<(u64, TrendDirection)> {
+        if window_size < 2 {
+            return Vec::new();
+        }
+
+        let candle_vec: Vec<&T> = candles.values().rev().collect();
+
+        candle_vec
+            .windows(window_size)
+            .rev()
+            .filter_map(|window| {
+                let trend = self.detect_trend_newest_first(window)?;
+                Some((window[0].get_time_key(), trend))
+            })
+            .collect()
+    }
 
+    fn detect_trend_newest_first<T: Candle>(&self, candle_vec: &[&T]) -> Option<TrendDirection> {
         if candle_vec.len() < 2 {
             return None;
         }
@@ -36,7 +64,7 @@ impl HHLLTrendDetector {
             }
         }
 
-        let total_checks = candles.len() - 1;
+        let total_checks = candle_vec.len() - 1;
         let required = (total_checks as f64 * self.min_confirmation_ratio).ceil() as usize;
 
         if higher_highs >= required {
@@ -52,6 +80,7 @@ impl HHLLTrendDetector {
 #[cfg(test)]
 mod tests {
     use crate::candle::CandleInstance;
+    use crate::test_candles::make_candles;
 
     use super::*;
 
@@ -307,4 +336,30 @@ mod tests {
 
         run_case(&candles[..8], 0.55, TrendDirection::Up);
     }
+
+    #[test]
+    fn scan_trend_reports_every_window() {
+        let highs = [1.0, 2.0, 3.0, 4.0, 3.0, 2.0];
+        let prices: Vec<(f64, f64, f64, f64)> = highs
+            .iter()
+            .map(|&high| (high - 0.5, high, high - 1.0, high - 0.5))
+            .collect();
+        let candles = make_candles(&prices);
+
+        let detector = HHLLTrendDetector {
+            min_confirmation_ratio: 1.0,
+        };
+
+        let trends = detector.scan_trend(&candles, 3);
+
+        assert_eq!(
+            trends,
+            vec![
+                (2, TrendDirection::Up),
+                (3, TrendDirection::Up),
+                (4, TrendDirection::Sideways),
+                (5, TrendDirection::Down),
+            ]
+        );
+    }
 }
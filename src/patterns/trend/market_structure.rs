@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::hhll::TrendDirection;
+use crate::candle::Candle;
+use crate::levels::{find_swing_highs, find_swing_lows};
+use crate::{ATR_DEFAULT_PERIOD, AtrCalculator, AtrSmoothing};
+
+pub const MS_DEFAULT_SWING_STRENGTH: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SwingKind {
+    High,
+    Low,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SwingLabel {
+    HigherHigh,
+    LowerHigh,
+    HigherLow,
+    LowerLow,
+}
+
+impl SwingLabel {
+    /// HH, LH, HL or LL
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwingLabel::HigherHigh => "HH",
+            SwingLabel::LowerHigh => "LH",
+            SwingLabel::HigherLow => "HL",
+            SwingLabel::LowerLow => "LL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwingPoint {
+    pub time_key: u64,
+    /// High of a swing high, low of a swing low
+    pub price: f64,
+    pub kind: SwingKind,
+    /// Compared with the previous swing of the same kind. `None` for the first one
+    pub label: Option<SwingLabel>,
+    /// Candle `swing_strength` candles later, when the swing became known
+    pub confirmed_time_key: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StructureEventKind {
+    /// Close beyond the last swing in the direction of the trend (or the first break)
+    BreakOfStructure,
+    /// Close beyond the last swing against the trend, which turns it
+    ChangeOfCharacter,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StructureEvent {
+    /// Candle which closed beyond the swing
+    pub time_key: u64,
+    pub kind: StructureEventKind,
+    pub direction: TrendDirection,
+    /// Swing which was broken
+    pub swing_time_key: u64,
+    pub swing_price: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarketStructure {
+    /// Swings alternating between highs and lows, oldest first
+    pub swings: Vec<SwingPoint>,
+    pub events: Vec<StructureEvent>,
+    /// Direction of the last structure break. `Sideways` before the first one
+    pub trend: TrendDirection,
+    /// Swing the current trend started from: the lowest swing low before an up trend,
+    /// the highest swing high before a down trend
+    pub trend_started_at: Option<u64>,
+}
+
+/// Swing based market structure.
+///
+/// Swings are taken from [`find_swing_highs`] / [`find_swing_lows`] and used only once confirmed,
+/// so the structure at a candle never depends on later candles. Two swings of the same kind in a row
+/// keep the more extreme one, and swings closer than `min_swing_atr` ATRs to the previous swing are skipped.
+#[derive(Debug, Clone)]
+pub struct MarketStructureDetector {
+    /// Number of candles on each side a swing has to dominate
+    pub swing_strength: usize,
+    /// Minimal distance from the previous swing in ATRs. `0.0` keeps all swings
+    pub min_swing_atr: f64,
+    pub atr_period: usize,
+}
+
+impl Default for MarketStructureDetector {
+    fn default() -> Self {
+        Self {
+            swing_strength: MS_DEFAULT_SWING_STRENGTH,
+            min_swing_atr: 0.0,
+            atr_period: ATR_DEFAULT_PERIOD,
+        }
+    }
+}
+
+impl MarketStructureDetector {
+    pub fn detect<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> MarketStructure {
+        let candle_vec: Vec<&T> = candles.values().collect();
+        let strength = self.swing_strength.max(1);
+        let swing_highs: BTreeSet<usize> = find_swing_highs(&candle_vec, strength)
+            .into_iter()
+            .collect();
+        let swing_lows: BTreeSet<usize> =
+            find_swing_lows(&candle_vec, strength).into_iter().collect();
+
+        let mut result = MarketStructure {
+            swings: Vec::new(),
+            events: Vec::new(),
+            trend: TrendDirection::Sideways,
+            trend_started_at: None,
+        };
+        // Time key the current trend was entered at, swings before it do not start the next trend
+        let mut trend_entered_at = 0;
+        let mut broken_swings: BTreeSet<u64> = BTreeSet::new();
+
+        for (index, candle) in candle_vec.iter().enumerate() {
+            if let Some(swing_index) = index.checked_sub(strength) {
+                let atr = self.calc_atr(&candle_vec[..=index]);
+
+                for (kind, swings) in [
+                    (SwingKind::High, &swing_highs),
+                    (SwingKind::Low, &swing_lows),
+                ] {
+                    if !swings.contains(&swing_index) {
+                        continue;
+                    }
+
+                    let swing_candle = candle_vec[swing_index];
+                    let swing = SwingPoint {
+                        time_key: swing_candle.get_time_key(),
+                        price: match kind {
+                            SwingKind::High => swing_candle.get_high(),
+                            SwingKind::Low => swing_candle.get_low(),
+                        },
+                        kind,
+                        label: None,
+                        confirmed_time_key: candle.get_time_key(),
+                    };
+                    self.add_swing(&mut result.swings, swing, atr);
+                }
+            }
+
+            let close = candle.get_close();
+
+            for kind in [SwingKind::High, SwingKind::Low] {
+                let Some(swing) = result.swings.iter().rev().find(|s| s.kind == kind) else {
+                    continue;
+                };
+
+                let direction = match kind {
+                    SwingKind::High if close > swing.price => TrendDirection::Up,
+                    SwingKind::Low if close < swing.price => TrendDirection::Down,
+                    _ => continue,
+                };
+
+                if !broken_swings.insert(swing.time_key) {
+                    continue;
+                }
+
+                let event_kind =
+                    if result.trend != TrendDirection::Sideways && result.trend != direction {
+                        StructureEventKind::ChangeOfCharacter
+                    } else {
+                        StructureEventKind::BreakOfStructure
+                    };
+
+                result.events.push(StructureEvent {
+                    time_key: candle.get_time_key(),
+                    kind: event_kind,
+                    direction,
+                    swing_time_key: swing.time_key,
+                    swing_price: swing.price,
+                });
+
+                if result.trend != direction {
+                    result.trend_started_at =
+                        find_trend_start(&result.swings, direction, trend_entered_at)
+                            .or(Some(candle.get_time_key()));
+                    result.trend = direction;
+                    trend_entered_at = candle.get_time_key();
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Keeps swings alternating and far enough from the previous one
+    fn add_swing(&self, swings: &mut Vec<SwingPoint>, mut swing: SwingPoint, atr: f64) {
+        if let Some(last) = swings.last() {
+            if last.kind == swing.kind {
+                let is_more_extreme = match swing.kind {
+                    SwingKind::High => swing.price > last.price,
+                    SwingKind::Low => swing.price < last.price,
+                };
+
+                if !is_more_extreme {
+                    return;
+                }
+
+                swings.pop();
+            } else if (swing.price - last.price).abs() < self.min_swing_atr * atr {
+                return;
+            }
+        }
+
+        swing.label = swings
+            .iter()
+            .rev()
+            .find(|s| s.kind == swing.kind)
+            .map(|prev| match (swing.kind, swing.price > prev.price) {
+                (SwingKind::High, true) => SwingLabel::HigherHigh,
+                (SwingKind::High, false) => SwingLabel::LowerHigh,
+                (SwingKind::Low, true) => SwingLabel::HigherLow,
+                (SwingKind::Low, false) => SwingLabel::LowerLow,
+            });
+
+        swings.push(swing);
+    }
+
+    /// Average true range of the last `atr_period` candles, fewer at the start of the history
+    fn calc_atr<T: Candle>(&self, candles: &[&T]) -> f64 {
+        let period = self.atr_period.max(1).min(candles.len());
+
+        AtrCalculator::new(period, AtrSmoothing::Sma)
+            .calc_recent_slice(candles)
+            .map_or(0.0, |atr| atr.get_value())
+    }
+}
+
+/// Most extreme opposite swing since the previous trend was entered
+fn find_trend_start(swings: &[SwingPoint], direction: TrendDirection, since: u64) -> Option<u64> {
+    let candidates = swings.iter().filter(|s| s.time_key >= since);
+
+    let swing = match direction {
+        TrendDirection::Up => candidates
+            .filter(|s| s.kind == SwingKind::Low)
+            .min_by(|a, b| a.price.total_cmp(&b.price)),
+        TrendDirection::Down => candidates
+            .filter(|s| s.kind == SwingKind::High)
+            .max_by(|a, b| a.price.total_cmp(&b.price)),
+        TrendDirection::Sideways => None,
+    };
+
+    swing.map(|s| s.time_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::test_candles;
+
+    /// Candles with range 1.0 closing at the given prices
+    fn make_candles(closes: &[f64]) -> BTreeMap<u64, CandleInstance> {
+        let prices: Vec<(f64, f64, f64, f64)> = closes
+            .iter()
+            .map(|close| (*close, close + 0.5, close - 0.5, *close))
+            .collect();
+
+        test_candles::make_candles(&prices)
+    }
+
+    fn make_detector() -> MarketStructureDetector {
+        MarketStructureDetector {
+            swing_strength: 1,
+            ..Default::default()
+        }
+    }
+
+    fn to_labels(structure: &MarketStructure) -> Vec<Option<&'static str>> {
+        structure
+            .swings
+            .iter()
+            .map(|s| s.label.map(|l| l.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn labels_up_trend() {
+        let candles = make_candles(&[10.0, 12.0, 11.0, 13.0, 12.0, 14.0, 13.0, 15.0]);
+
+        let structure = make_detector().detect(&candles);
+
+        assert_eq!(
+            to_labels(&structure),
+            vec![None, None, Some("HH"), Some("HL"), Some("HH"), Some("HL")]
+        );
+        assert_eq!(structure.swings[2].confirmed_time_key, 4);
+
+        let events: Vec<_> = structure
+            .events
+            .iter()
+            .map(|e| (e.time_key, e.kind))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (3, StructureEventKind::BreakOfStructure),
+                (5, StructureEventKind::BreakOfStructure),
+                (7, StructureEventKind::BreakOfStructure),
+            ]
+        );
+        assert_eq!(structure.trend, TrendDirection::Up);
+        assert_eq!(structure.trend_started_at, Some(2));
+    }
+
+    #[test]
+    fn change_of_character_turns_trend() {
+        let candles = make_candles(&[
+            10.0, 12.0, 11.0, 13.0, 12.0, 14.0, 12.5, 13.0, 11.0, 12.0, 10.0,
+        ]);
+
+        let structure = make_detector().detect(&candles);
+
+        let events: Vec<_> = structure
+            .events
+            .iter()
+            .map(|e| (e.time_key, e.kind, e.direction))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (3, StructureEventKind::BreakOfStructure, TrendDirection::Up),
+                (5, StructureEventKind::BreakOfStructure, TrendDirection::Up),
+                (
+                    8,
+                    StructureEventKind::ChangeOfCharacter,
+                    TrendDirection::Down
+                ),
+                (
+                    10,
+                    StructureEventKind::BreakOfStructure,
+                    TrendDirection::Down
+                ),
+            ]
+        );
+        assert_eq!(structure.events[2].swing_time_key, 6);
+        assert_eq!(structure.trend, TrendDirection::Down);
+        assert_eq!(structure.trend_started_at, Some(5));
+        assert_eq!(
+            to_labels(&structure)[6..],
+            [Some("LH"), Some("LL"), Some("LH")]
+        );
+    }
+
+    #[test]
+    fn atr_filter_skips_shallow_pullbacks() {
+        let candles = make_candles(&[10.0, 12.0, 11.8, 14.0, 13.0, 16.0]);
+
+        let all = make_detector().detect(&candles);
+        // True ranges include the gaps between the candles, so the ATR is about 1.8 here
+        let filtered = MarketStructureDetector {
+            min_swing_atr: 0.8,
+            ..make_detector()
+        }
+        .detect(&candles);
+
+        let to_keys = |s: &MarketStructure| s.swings.iter().map(|s| s.time_key).collect::<Vec<_>>();
+        assert_eq!(to_keys(&all), vec![1, 2, 3, 4]);
+        assert_eq!(to_keys(&filtered), vec![3, 4]);
+        assert_eq!(to_labels(&filtered), vec![None, None]);
+    }
+
+    #[test]
+    fn no_swings_no_trend() {
+        let candles = make_candles(&[10.0, 11.0, 12.0, 13.0]);
+
+        let structure = make_detector().detect(&candles);
+
+        assert!(structure.swings.is_empty());
+        assert!(structure.events.is_empty());
+        assert_eq!(structure.trend, TrendDirection::Sideways);
+        assert_eq!(structure.trend_started_at, None);
+    }
+}
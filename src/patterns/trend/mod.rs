@@ -1 +1,2 @@
 pub mod hhll;
+pub mod market_structure;
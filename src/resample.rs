@@ -0,0 +1,477 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono_tz::Tz;
+use rust_extensions::chrono::{DateTime, Duration, NaiveDateTime, NaiveTime, TimeZone};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use crate::candle::{Candle, CandleInstance};
+use crate::sessions::Exchange;
+use crate::{TimeKeyFormat, Timeframe, UsMarketMoment};
+
+/// US regular session open (local New York time)
+pub const US_SESSION_OPEN_HOUR: u32 = 9;
+pub const US_SESSION_OPEN_MINUTE: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BucketAnchor {
+    /// Buckets start at local midnight (e.g. 1h bars at :00, daily bars at 00:00)
+    Aligned,
+    /// Buckets start at the US session open 09:30 local time (use with `America/New_York`)
+    SessionOpen,
+}
+
+/// Aggregates candles into a higher timeframe.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    /// Timeframe of the candles being aggregated. Used to tell if the last bucket is complete
+    pub source: Timeframe,
+    pub target: Timeframe,
+    pub anchor: BucketAnchor,
+    /// Time zone buckets are aligned in
+    pub time_zone: Tz,
+    /// Format of both source and resulting time keys
+    pub key_format: TimeKeyFormat,
+    /// Use only candles of the US regular session (see [`UsMarketMoment`]).
+    /// A bucket is then complete once the session closes, e.g. a daily bar at 16:00 New York time
+    pub regular_hours_only: bool,
+    /// Keep the last bucket if it is not complete yet
+    pub include_partial: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResampleError {
+    /// Timeframe with a zero duration, e.g. `Timeframe::Minutes(0)`
+    ZeroTimeframe(Timeframe),
+    /// Target timeframe is not longer than the source one
+    NotIncreasing {
+        source: Timeframe,
+        target: Timeframe,
+    },
+    /// Resampler keys differ from the candles it is used with, see [`crate::MultiTimeframeCandles::add_resampled`]
+    KeyFormatMismatch {
+        expected: TimeKeyFormat,
+        actual: TimeKeyFormat,
+    },
+}
+
+impl fmt::Display for ResampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResampleError::ZeroTimeframe(timeframe) => {
+                write!(f, "timeframe {:?} has zero duration", timeframe)
+            }
+            ResampleError::NotIncreasing { source, target } => {
+                write!(
+                    f,
+                    "target {:?} is not longer than source {:?}",
+                    target, source
+                )
+            }
+            ResampleError::KeyFormatMismatch { expected, actual } => {
+                write!(f, "key format {:?} does not match {:?}", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResampleError {}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResampleResult {
+    /// Aggregated candles keyed by bucket start
+    pub candles: BTreeMap<u64, CandleInstance>,
+    /// `true` if the last candle is an incomplete bucket
+    pub last_is_partial: bool,
+}
+
+impl Resampler {
+    /// Fails if a timeframe has zero duration or `target` is not longer than `source`
+    pub fn new(
+        source: Timeframe,
+        target: Timeframe,
+        key_format: TimeKeyFormat,
+    ) -> Result<Self, ResampleError> {
+        let result = Self {
+            source,
+            target,
+            anchor: BucketAnchor::Aligned,
+            time_zone: Tz::UTC,
+            key_format,
+            regular_hours_only: false,
+            include_partial: true,
+        };
+
+        result.validate()?;
+        Ok(result)
+    }
+
+    /// Checks the timeframes again, as they can be changed after [`Resampler::new`]
+    pub fn validate(&self) -> Result<(), ResampleError> {
+        for timeframe in [self.source, self.target] {
+            if timeframe.get_duration_seconds() <= 0 {
+                return Err(ResampleError::ZeroTimeframe(timeframe));
+            }
+        }
+
+        if self.target.get_duration_seconds() <= self.source.get_duration_seconds() {
+            return Err(ResampleError::NotIncreasing {
+                source: self.source,
+                target: self.target,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn resample<T: Candle>(
+        &self,
+        candles: &BTreeMap<u64, T>,
+    ) -> Result<ResampleResult, ResampleError> {
+        self.validate()?;
+
+        let mut result: BTreeMap<u64, CandleInstance> = BTreeMap::new();
+        // Key and end (unix microseconds) of the last bucket
+        let mut last_bucket: Option<(u64, i64)> = None;
+        let mut last_candle_end = None;
+
+        for candle in candles.values() {
+            let Some(dt) = self.key_format.to_date_time(candle.get_time_key()) else {
+                continue;
+            };
+
+            if self.regular_hours_only && !UsMarketMoment::from(dt).is_working() {
+                continue;
+            }
+
+            let (bucket_start, bucket_end) = self.get_bucket(dt)?;
+            let bucket_key = self
+                .key_format
+                .from_date_time(DateTimeAsMicroseconds::new(bucket_start.timestamp_micros()));
+
+            match result.get_mut(&bucket_key) {
+                Some(bar) => {
+                    bar.high = bar.high.max(candle.get_high());
+                    bar.low = bar.low.min(candle.get_low());
+                    bar.close = candle.get_close();
+                    bar.volume += candle.get_volume();
+                }
+                None => {
+                    result.insert(
+                        bucket_key,
+                        CandleInstance {
+                            time_key: bucket_key,
+                            open: candle.get_open(),
+                            high: candle.get_high(),
+                            low: candle.get_low(),
+                            close: candle.get_close(),
+                            volume: candle.get_volume(),
+                        },
+                    );
+                }
+            }
+
+            let mut bucket_end = bucket_end.timestamp_micros();
+            if self.regular_hours_only
+                && let Some(session_close) = Exchange::Nyse.next_close(dt)
+            {
+                bucket_end = bucket_end.min(session_close.unix_microseconds);
+            }
+
+            last_bucket = Some((bucket_key, bucket_end));
+            last_candle_end = Some(dt.unix_microseconds + self.source.get_duration_microseconds());
+        }
+
+        let mut last_is_partial = match (last_bucket, last_candle_end) {
+            (Some((_, bucket_end)), Some(candle_end)) => candle_end < bucket_end,
+            _ => false,
+        };
+
+        if last_is_partial && !self.include_partial {
+            if let Some((bucket_key, _)) = last_bucket {
+                result.remove(&bucket_key);
+            }
+            last_is_partial = false;
+        }
+
+        Ok(ResampleResult {
+            candles: result,
+            last_is_partial,
+        })
+    }
+
+    /// Start and end of the bucket the moment belongs to
+    pub fn get_bucket(
+        &self,
+        dt: DateTimeAsMicroseconds,
+    ) -> Result<(DateTime<Tz>, DateTime<Tz>), ResampleError> {
+        if self.target.get_duration_seconds() <= 0 {
+            return Err(ResampleError::ZeroTimeframe(self.target));
+        }
+
+        let local = dt
+            .to_chrono_utc()
+            .with_timezone(&self.time_zone)
+            .naive_local();
+
+        let anchor_time = match self.anchor {
+            BucketAnchor::Aligned => NaiveTime::MIN,
+            BucketAnchor::SessionOpen => {
+                NaiveTime::from_hms_opt(US_SESSION_OPEN_HOUR, US_SESSION_OPEN_MINUTE, 0).unwrap()
+            }
+        };
+
+        let anchor = local.date().and_time(anchor_time);
+        let duration = Duration::seconds(self.target.get_duration_seconds());
+
+        let start = match self.target {
+            Timeframe::Day => {
+                if local >= anchor {
+                    anchor
+                } else {
+                    anchor - Duration::days(1)
+                }
+            }
+            _ => {
+                let elapsed = (local - anchor).num_seconds();
+                let buckets = elapsed.div_euclid(self.target.get_duration_seconds());
+                anchor + Duration::seconds(buckets * self.target.get_duration_seconds())
+            }
+        };
+
+        Ok((self.to_zoned(start), self.to_zoned(start + duration)))
+    }
+
+    fn to_zoned(&self, local: NaiveDateTime) -> DateTime<Tz> {
+        self.time_zone
+            .from_local_datetime(&local)
+            .earliest()
+            .unwrap_or_else(|| self.time_zone.from_utc_datetime(&local))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2025-04-07 (Monday) 09:30 New York
+    const MONDAY_OPEN: u64 = 1744032600;
+    const MINUTE: u64 = 60;
+
+    fn make_minutes(start: u64, count: u64) -> BTreeMap<u64, CandleInstance> {
+        (0..count)
+            .map(|i| {
+                let time_key = start + i * MINUTE;
+                let price = 100.0 + i as f64;
+                (
+                    time_key,
+                    CandleInstance {
+                        time_key,
+                        open: price,
+                        high: price + 0.5,
+                        low: price - 0.5,
+                        close: price + 0.25,
+                        volume: 1.0,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aggregates_ohlcv_into_aligned_buckets() {
+        let candles = make_minutes(MONDAY_OPEN, 10);
+        let resampler = Resampler::new(
+            Timeframe::Minutes(1),
+            Timeframe::Minutes(5),
+            TimeKeyFormat::UnixSeconds,
+        )
+        .unwrap();
+
+        let result = resampler.resample(&candles).unwrap();
+        assert_eq!(result.candles.len(), 2);
+        assert!(!result.last_is_partial);
+
+        let first = &result.candles[&MONDAY_OPEN];
+        assert_eq!(first.open, 100.0);
+        assert_eq!(first.high, 104.5);
+        assert_eq!(first.low, 99.5);
+        assert_eq!(first.close, 104.25);
+        assert_eq!(first.volume, 5.0);
+    }
+
+    #[test]
+    fn hourly_buckets_anchored_to_session_open() {
+        let candles = make_minutes(MONDAY_OPEN, 90);
+
+        let aligned = Resampler {
+            time_zone: chrono_tz::America::New_York,
+            ..Resampler::new(
+                Timeframe::Minutes(1),
+                Timeframe::Hours(1),
+                TimeKeyFormat::UnixSeconds,
+            )
+            .unwrap()
+        };
+        let keys: Vec<u64> = aligned
+            .resample(&candles)
+            .unwrap()
+            .candles
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(
+            keys,
+            vec![MONDAY_OPEN - 30 * MINUTE, MONDAY_OPEN + 30 * MINUTE]
+        );
+
+        let anchored = Resampler {
+            anchor: BucketAnchor::SessionOpen,
+            ..aligned
+        };
+        let result = anchored.resample(&candles).unwrap();
+        let keys: Vec<u64> = result.candles.keys().copied().collect();
+        assert_eq!(keys, vec![MONDAY_OPEN, MONDAY_OPEN + 60 * MINUTE]);
+        assert!(result.last_is_partial);
+    }
+
+    #[test]
+    fn daily_buckets_use_time_zone() {
+        // 23:00 and 23:01 New York on Monday are already Tuesday in UTC
+        let candles: BTreeMap<u64, CandleInstance> = [202504080300, 202504080301]
+            .into_iter()
+            .map(|time_key| {
+                (
+                    time_key,
+                    CandleInstance {
+                        time_key,
+                        open: 100.0,
+                        high: 101.0,
+                        low: 99.0,
+                        close: 100.0,
+                        volume: 1.0,
+                    },
+                )
+            })
+            .collect();
+
+        let utc = Resampler::new(
+            Timeframe::Minutes(1),
+            Timeframe::Day,
+            TimeKeyFormat::YearMonthDayHourMinute,
+        )
+        .unwrap();
+        let keys: Vec<u64> = utc
+            .resample(&candles)
+            .unwrap()
+            .candles
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(keys, vec![202504080000]);
+
+        let new_york = Resampler {
+            time_zone: chrono_tz::America::New_York,
+            ..utc
+        };
+        let keys: Vec<u64> = new_york
+            .resample(&candles)
+            .unwrap()
+            .candles
+            .keys()
+            .copied()
+            .collect();
+        assert_eq!(keys, vec![202504070400]);
+    }
+
+    #[test]
+    fn partial_last_bar_can_be_dropped() {
+        let candles = make_minutes(MONDAY_OPEN, 7);
+        let resampler = Resampler {
+            include_partial: false,
+            ..Resampler::new(
+                Timeframe::Minutes(1),
+                Timeframe::Minutes(5),
+                TimeKeyFormat::UnixSeconds,
+            )
+            .unwrap()
+        };
+
+        let result = resampler.resample(&candles).unwrap();
+        assert_eq!(result.candles.len(), 1);
+        assert!(!result.last_is_partial);
+    }
+
+    #[test]
+    fn regular_hours_only_skips_pre_market() {
+        let candles = make_minutes(MONDAY_OPEN - 5 * MINUTE, 10);
+        let resampler = Resampler {
+            regular_hours_only: true,
+            ..Resampler::new(
+                Timeframe::Minutes(1),
+                Timeframe::Minutes(5),
+                TimeKeyFormat::UnixSeconds,
+            )
+            .unwrap()
+        };
+
+        let result = resampler.resample(&candles).unwrap();
+        assert_eq!(result.candles.len(), 1);
+        assert_eq!(result.candles[&MONDAY_OPEN].open, 105.0);
+    }
+
+    #[test]
+    fn full_regular_session_is_complete_daily_bar() {
+        let resampler = Resampler {
+            time_zone: chrono_tz::America::New_York,
+            regular_hours_only: true,
+            include_partial: false,
+            ..Resampler::new(
+                Timeframe::Minutes(1),
+                Timeframe::Day,
+                TimeKeyFormat::UnixSeconds,
+            )
+            .unwrap()
+        };
+
+        // 09:30 - 15:59
+        let session = make_minutes(MONDAY_OPEN, 390);
+        let result = resampler.resample(&session).unwrap();
+        assert!(!result.last_is_partial);
+        assert_eq!(result.candles.len(), 1);
+        assert_eq!(result.candles[&(MONDAY_OPEN - 570 * MINUTE)].close, 489.25);
+
+        let unfinished = make_minutes(MONDAY_OPEN, 389);
+        assert!(resampler.resample(&unfinished).unwrap().candles.is_empty());
+    }
+
+    #[test]
+    fn zero_and_non_increasing_timeframes_are_rejected() {
+        let new = |source, target| Resampler::new(source, target, TimeKeyFormat::UnixSeconds);
+
+        assert_eq!(
+            new(Timeframe::Minutes(1), Timeframe::Minutes(0)).unwrap_err(),
+            ResampleError::ZeroTimeframe(Timeframe::Minutes(0))
+        );
+        assert_eq!(
+            new(Timeframe::Hours(0), Timeframe::Day).unwrap_err(),
+            ResampleError::ZeroTimeframe(Timeframe::Hours(0))
+        );
+        assert_eq!(
+            new(Timeframe::Hours(1), Timeframe::Minutes(60)).unwrap_err(),
+            ResampleError::NotIncreasing {
+                source: Timeframe::Hours(1),
+                target: Timeframe::Minutes(60),
+            }
+        );
+
+        let changed = Resampler {
+            target: Timeframe::Hours(0),
+            ..new(Timeframe::Minutes(1), Timeframe::Hours(1)).unwrap()
+        };
+        let candles = make_minutes(MONDAY_OPEN, 3);
+        assert!(changed.resample(&candles).is_err());
+    }
+}
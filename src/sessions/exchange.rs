@@ -0,0 +1,534 @@
+use chrono_tz::Tz;
+use rust_extensions::chrono::{
+    Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday,
+};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+use super::*;
+
+/// Number of days `next_open`/`next_close` look ahead
+const MAX_SEARCH_DAYS: i64 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Exchange {
+    Nyse,
+    Nasdaq,
+    /// CME Globex equity index futures: Sunday to Friday 17:00 - 16:00 Chicago time with a daily maintenance break
+    CmeGlobex,
+    /// London Stock Exchange
+    Lse,
+    /// Deutsche Börse XETRA
+    Xetra,
+    /// Tokyo Stock Exchange
+    Tse,
+    /// 24/5 from Sunday 17:00 to Friday 17:00 New York time
+    Forex,
+    /// 24/7
+    Crypto,
+}
+
+/// Trading hours of a single exchange day, local time
+struct DaySchedule {
+    pre_open: Option<NaiveTime>,
+    open: NaiveTime,
+    close: NaiveTime,
+    post_close: Option<NaiveTime>,
+    lunch_break: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl Exchange {
+    pub fn get_time_zone(&self) -> Tz {
+        match self {
+            Exchange::Nyse | Exchange::Nasdaq | Exchange::Forex => chrono_tz::America::New_York,
+            Exchange::CmeGlobex => chrono_tz::America::Chicago,
+            Exchange::Lse => chrono_tz::Europe::London,
+            Exchange::Xetra => chrono_tz::Europe::Berlin,
+            Exchange::Tse => chrono_tz::Asia::Tokyo,
+            Exchange::Crypto => Tz::UTC,
+        }
+    }
+
+    /// Full day holiday of the exchange (local date)
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        match self {
+            Exchange::Nyse | Exchange::Nasdaq => is_us_market_holiday(date),
+            Exchange::CmeGlobex => is_cme_full_holiday(date),
+            Exchange::Lse => is_uk_market_holiday(date),
+            Exchange::Xetra => is_german_market_holiday(date),
+            Exchange::Tse => is_japan_market_holiday(date),
+            Exchange::Forex | Exchange::Crypto => false,
+        }
+    }
+
+    /// Close time if the exchange closes early on the date (local time)
+    pub fn get_early_close(&self, date: NaiveDate) -> Option<NaiveTime> {
+        match self {
+            Exchange::Nyse | Exchange::Nasdaq if is_us_market_early_close(date) => Some(hm(13, 0)),
+            Exchange::Lse if is_uk_market_early_close(date) => Some(hm(12, 30)),
+            Exchange::CmeGlobex
+                if !is_weekend(date)
+                    && !is_cme_full_holiday(date)
+                    && is_us_market_holiday(date) =>
+            {
+                Some(hm(12, 0))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get_phase(&self, dt: DateTimeAsMicroseconds) -> SessionPhase {
+        let local = dt
+            .to_chrono_utc()
+            .with_timezone(&self.get_time_zone())
+            .naive_local();
+
+        match self {
+            Exchange::Crypto => SessionPhase::Regular,
+            Exchange::Forex => get_forex_phase(local),
+            Exchange::CmeGlobex => get_cme_globex_phase(local),
+            _ => self.get_day_phase(local),
+        }
+    }
+
+    pub fn is_open(&self, dt: DateTimeAsMicroseconds) -> bool {
+        self.get_phase(dt).is_regular()
+    }
+
+    /// Next moment after `dt` the regular session starts. `None` for exchanges which never close
+    pub fn next_open(&self, dt: DateTimeAsMicroseconds) -> Option<DateTimeAsMicroseconds> {
+        self.find_next_transition(dt, true)
+    }
+
+    /// Next moment after `dt` the regular session ends. `None` for exchanges which never close
+    pub fn next_close(&self, dt: DateTimeAsMicroseconds) -> Option<DateTimeAsMicroseconds> {
+        self.find_next_transition(dt, false)
+    }
+
+    fn get_schedule(&self, date: NaiveDate) -> Option<DaySchedule> {
+        let mut result = match self {
+            Exchange::Nyse | Exchange::Nasdaq => DaySchedule {
+                pre_open: Some(hm(4, 0)),
+                open: hm(9, 30),
+                close: hm(16, 0),
+                post_close: Some(hm(20, 0)),
+                lunch_break: None,
+            },
+            Exchange::Lse => DaySchedule {
+                pre_open: None,
+                open: hm(8, 0),
+                close: hm(16, 30),
+                post_close: None,
+                lunch_break: None,
+            },
+            Exchange::Xetra => DaySchedule {
+                pre_open: None,
+                open: hm(9, 0),
+                close: hm(17, 30),
+                post_close: None,
+                lunch_break: None,
+            },
+            Exchange::Tse => DaySchedule {
+                pre_open: None,
+                open: hm(9, 0),
+                close: hm(15, 30),
+                post_close: None,
+                lunch_break: Some((hm(11, 30), hm(12, 30))),
+            },
+            _ => return None,
+        };
+
+        if let Some(early_close) = self.get_early_close(date) {
+            result.close = early_close;
+            // Extended hours end at 17:00 on US early close days
+            result.post_close = result.post_close.map(|_| hm(17, 0));
+        }
+
+        Some(result)
+    }
+
+    fn get_day_phase(&self, local: NaiveDateTime) -> SessionPhase {
+        let date = local.date();
+
+        if is_weekend(date) {
+            return SessionPhase::Closed;
+        }
+
+        if self.is_holiday(date) {
+            return SessionPhase::Holiday;
+        }
+
+        let Some(schedule) = self.get_schedule(date) else {
+            return SessionPhase::Closed;
+        };
+
+        let time = local.time();
+
+        if time < schedule.open {
+            return match schedule.pre_open {
+                Some(pre_open) if time >= pre_open => SessionPhase::PreMarket,
+                _ => SessionPhase::Closed,
+            };
+        }
+
+        if time >= schedule.close {
+            return match schedule.post_close {
+                Some(post_close) if time < post_close => SessionPhase::PostMarket,
+                _ => SessionPhase::Closed,
+            };
+        }
+
+        if let Some((break_start, break_end)) = schedule.lunch_break
+            && break_start <= time
+            && time < break_end
+        {
+            return SessionPhase::Break;
+        }
+
+        SessionPhase::Regular
+    }
+
+    /// Local times at which the phase of the exchange can change
+    fn get_boundary_times(&self) -> Vec<NaiveTime> {
+        match self {
+            Exchange::Nyse | Exchange::Nasdaq => vec![
+                hm(4, 0),
+                hm(9, 30),
+                hm(13, 0),
+                hm(16, 0),
+                hm(17, 0),
+                hm(20, 0),
+            ],
+            Exchange::Lse => vec![hm(8, 0), hm(12, 30), hm(16, 30)],
+            Exchange::Xetra => vec![hm(9, 0), hm(17, 30)],
+            Exchange::Tse => vec![hm(9, 0), hm(11, 30), hm(12, 30), hm(15, 30)],
+            Exchange::CmeGlobex => vec![hm(12, 0), hm(16, 0), hm(17, 0)],
+            Exchange::Forex => vec![hm(17, 0)],
+            Exchange::Crypto => vec![],
+        }
+    }
+
+    fn find_next_transition(
+        &self,
+        dt: DateTimeAsMicroseconds,
+        to_regular: bool,
+    ) -> Option<DateTimeAsMicroseconds> {
+        let time_zone = self.get_time_zone();
+        let start_date = dt.to_chrono_utc().with_timezone(&time_zone).date_naive();
+        let boundary_times = self.get_boundary_times();
+
+        for day in 0..=MAX_SEARCH_DAYS {
+            let date = start_date + Duration::days(day);
+
+            for time in boundary_times.iter() {
+                let Some(candidate) = time_zone
+                    .from_local_datetime(&date.and_time(*time))
+                    .earliest()
+                else {
+                    continue;
+                };
+
+                let candidate = DateTimeAsMicroseconds::new(candidate.timestamp_micros());
+                if candidate.unix_microseconds <= dt.unix_microseconds {
+                    continue;
+                }
+
+                let before = DateTimeAsMicroseconds::new(candidate.unix_microseconds - 1);
+                let is_open = self.is_open(candidate);
+                let was_open = self.is_open(before);
+
+                if is_open == to_regular && was_open != to_regular {
+                    return Some(candidate);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Globex equity index futures are closed on Good Friday, Christmas and New Year's Day.
+/// Other US holidays have an early halt at 12:00.
+fn is_cme_full_holiday(date: NaiveDate) -> bool {
+    if !is_us_market_holiday(date) {
+        return false;
+    }
+
+    let easter = calc_easter_sunday(date.year());
+    let is_good_friday = date == easter - Duration::days(2);
+    let is_christmas = date.month() == 12 && (24..=26).contains(&date.day());
+    let is_new_year = date.month() == 1 && date.day() <= 2;
+
+    is_good_friday || is_christmas || is_new_year
+}
+
+/// Globex sessions start at 17:00 on the previous day, so the trading date after 17:00 is the next day
+fn get_cme_globex_phase(local: NaiveDateTime) -> SessionPhase {
+    let time = local.time();
+    let trading_date = get_trading_date(local, hm(17, 0));
+
+    if is_weekend(trading_date) {
+        return SessionPhase::Closed;
+    }
+
+    if is_cme_full_holiday(trading_date) {
+        return SessionPhase::Holiday;
+    }
+
+    if local.date() == trading_date && time >= hm(16, 0) {
+        if trading_date.weekday() == Weekday::Fri {
+            return SessionPhase::Closed;
+        }
+        return SessionPhase::Break;
+    }
+
+    if local.date() == trading_date
+        && let Some(early_close) = Exchange::CmeGlobex.get_early_close(trading_date)
+        && time >= early_close
+    {
+        return SessionPhase::Closed;
+    }
+
+    SessionPhase::Regular
+}
+
+fn get_forex_phase(local: NaiveDateTime) -> SessionPhase {
+    let trading_date = get_trading_date(local, hm(17, 0));
+
+    if is_weekend(trading_date) {
+        return SessionPhase::Closed;
+    }
+
+    SessionPhase::Regular
+}
+
+fn get_trading_date(local: NaiveDateTime, session_start: NaiveTime) -> NaiveDate {
+    if local.time() >= session_start {
+        local.date() + Duration::days(1)
+    } else {
+        local.date()
+    }
+}
+
+fn hm(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_extensions::chrono::Utc;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTimeAsMicroseconds {
+        let dt = Utc
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap();
+        DateTimeAsMicroseconds::new(dt.timestamp_micros())
+    }
+
+    fn local(
+        exchange: Exchange,
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+    ) -> DateTimeAsMicroseconds {
+        let dt = exchange
+            .get_time_zone()
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap();
+        DateTimeAsMicroseconds::new(dt.timestamp_micros())
+    }
+
+    #[test]
+    fn nyse_phases() {
+        let nyse = Exchange::Nyse;
+
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 26, 8, 0)),
+            SessionPhase::PreMarket
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 26, 10, 0)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 26, 16, 30)),
+            SessionPhase::PostMarket
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 26, 21, 0)),
+            SessionPhase::Closed
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 27, 11, 0)),
+            SessionPhase::Holiday
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 29, 11, 0)),
+            SessionPhase::Closed
+        );
+    }
+
+    #[test]
+    fn nyse_black_friday_closes_early() {
+        let nyse = Exchange::Nyse;
+
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 28, 12, 59)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 28, 13, 30)),
+            SessionPhase::PostMarket
+        );
+        assert_eq!(
+            nyse.get_phase(local(nyse, 2025, 11, 28, 17, 30)),
+            SessionPhase::Closed
+        );
+
+        let close = nyse.next_close(local(nyse, 2025, 11, 28, 10, 0)).unwrap();
+        assert_eq!(close, local(nyse, 2025, 11, 28, 13, 0));
+    }
+
+    #[test]
+    fn nyse_next_open_skips_weekend() {
+        let nyse = Exchange::Nyse;
+
+        let open = nyse.next_open(local(nyse, 2025, 11, 28, 14, 0)).unwrap();
+        assert_eq!(open, local(nyse, 2025, 12, 1, 9, 30));
+
+        let open = nyse.next_open(local(nyse, 2025, 11, 26, 17, 0)).unwrap();
+        assert_eq!(open, local(nyse, 2025, 11, 28, 9, 30));
+
+        let close = nyse.next_close(local(nyse, 2025, 11, 26, 10, 0)).unwrap();
+        assert_eq!(close, utc(2025, 11, 26, 21, 0));
+    }
+
+    #[test]
+    fn cme_globex_daily_break_and_weekend() {
+        let cme = Exchange::CmeGlobex;
+
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 7, 10, 0)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 7, 16, 30)),
+            SessionPhase::Break
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 7, 17, 30)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 11, 16, 30)),
+            SessionPhase::Closed
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 12, 12, 0)),
+            SessionPhase::Closed
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 13, 18, 0)),
+            SessionPhase::Regular
+        );
+
+        let open = cme.next_open(local(cme, 2025, 4, 12, 12, 0)).unwrap();
+        assert_eq!(open, local(cme, 2025, 4, 13, 17, 0));
+
+        let close = cme.next_close(local(cme, 2025, 4, 7, 10, 0)).unwrap();
+        assert_eq!(close, local(cme, 2025, 4, 7, 16, 0));
+    }
+
+    #[test]
+    fn cme_globex_holidays() {
+        let cme = Exchange::CmeGlobex;
+
+        // Good Friday
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 4, 18, 10, 0)),
+            SessionPhase::Holiday
+        );
+        // Thanksgiving: early halt at 12:00
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 11, 27, 11, 0)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 11, 27, 13, 0)),
+            SessionPhase::Closed
+        );
+        assert_eq!(
+            cme.get_phase(local(cme, 2025, 11, 27, 17, 30)),
+            SessionPhase::Regular
+        );
+    }
+
+    #[test]
+    fn european_and_asian_exchanges() {
+        let lse = Exchange::Lse;
+        assert_eq!(
+            lse.get_phase(local(lse, 2025, 12, 24, 12, 0)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            lse.get_phase(local(lse, 2025, 12, 24, 13, 0)),
+            SessionPhase::Closed
+        );
+        assert_eq!(
+            lse.get_phase(local(lse, 2025, 12, 26, 10, 0)),
+            SessionPhase::Holiday
+        );
+
+        let xetra = Exchange::Xetra;
+        assert_eq!(
+            xetra.get_phase(local(xetra, 2025, 12, 23, 17, 0)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            xetra.get_phase(local(xetra, 2025, 12, 24, 10, 0)),
+            SessionPhase::Holiday
+        );
+
+        let tse = Exchange::Tse;
+        assert_eq!(
+            tse.get_phase(local(tse, 2025, 4, 7, 10, 0)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            tse.get_phase(local(tse, 2025, 4, 7, 12, 0)),
+            SessionPhase::Break
+        );
+        assert_eq!(
+            tse.get_phase(local(tse, 2025, 1, 2, 10, 0)),
+            SessionPhase::Holiday
+        );
+
+        let open = tse.next_open(local(tse, 2025, 4, 7, 11, 45)).unwrap();
+        assert_eq!(open, local(tse, 2025, 4, 7, 12, 30));
+    }
+
+    #[test]
+    fn forex_and_crypto() {
+        let forex = Exchange::Forex;
+        assert_eq!(
+            forex.get_phase(local(forex, 2025, 4, 11, 16, 59)),
+            SessionPhase::Regular
+        );
+        assert_eq!(
+            forex.get_phase(local(forex, 2025, 4, 11, 17, 0)),
+            SessionPhase::Closed
+        );
+        assert_eq!(
+            forex.get_phase(local(forex, 2025, 4, 13, 17, 0)),
+            SessionPhase::Regular
+        );
+
+        let crypto = Exchange::Crypto;
+        assert!(crypto.is_open(utc(2025, 4, 12, 3, 0)));
+        assert!(crypto.next_open(utc(2025, 4, 12, 3, 0)).is_none());
+        assert!(crypto.next_close(utc(2025, 4, 12, 3, 0)).is_none());
+    }
+}
@@ -0,0 +1,278 @@
+use rust_extensions::chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+pub fn calc_easter_sunday(year: i32) -> NaiveDate {
+    // Anonymous Gregorian algorithm
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+/// `n`-th (starting from 1) weekday of the month
+pub fn get_nth_weekday(year: i32, month: u32, weekday: Weekday, n: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8).unwrap()
+}
+
+pub fn get_last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let mut date = NaiveDate::from_ymd_opt(next_year, next_month, 1).unwrap() - Duration::days(1);
+
+    while date.weekday() != weekday {
+        date -= Duration::days(1);
+    }
+
+    date
+}
+
+pub fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// NYSE/Nasdaq full day holidays
+pub fn is_us_market_holiday(date: NaiveDate) -> bool {
+    let year = date.year();
+
+    // New Year's Day falling on Saturday is not moved to Friday
+    let new_year = ymd(year, 1, 1);
+    let new_year_observed = match new_year.weekday() {
+        Weekday::Sun => Some(ymd(year, 1, 2)),
+        Weekday::Sat => None,
+        _ => Some(new_year),
+    };
+
+    if new_year_observed == Some(date) {
+        return true;
+    }
+
+    let mut holidays = vec![
+        get_nth_weekday(year, 1, Weekday::Mon, 3),
+        get_nth_weekday(year, 2, Weekday::Mon, 3),
+        calc_easter_sunday(year) - Duration::days(2),
+        get_last_weekday(year, 5, Weekday::Mon),
+        observe_us(ymd(year, 7, 4)),
+        get_nth_weekday(year, 9, Weekday::Mon, 1),
+        get_nth_weekday(year, 11, Weekday::Thu, 4),
+        observe_us(ymd(year, 12, 25)),
+    ];
+
+    if year >= 2022 {
+        holidays.push(observe_us(ymd(year, 6, 19)));
+    }
+
+    holidays.contains(&date)
+}
+
+/// NYSE/Nasdaq 13:00 closes: July 3rd, the day after Thanksgiving and Christmas Eve
+pub fn is_us_market_early_close(date: NaiveDate) -> bool {
+    if is_weekend(date) || is_us_market_holiday(date) {
+        return false;
+    }
+
+    let year = date.year();
+    let day_after_thanksgiving = get_nth_weekday(year, 11, Weekday::Thu, 4) + Duration::days(1);
+
+    date == ymd(year, 7, 3) || date == day_after_thanksgiving || date == ymd(year, 12, 24)
+}
+
+/// London Stock Exchange holidays (regular bank holidays)
+pub fn is_uk_market_holiday(date: NaiveDate) -> bool {
+    let year = date.year();
+    let easter = calc_easter_sunday(year);
+
+    let new_year = match ymd(year, 1, 1).weekday() {
+        Weekday::Sat => ymd(year, 1, 3),
+        Weekday::Sun => ymd(year, 1, 2),
+        _ => ymd(year, 1, 1),
+    };
+
+    let christmas = match ymd(year, 12, 25).weekday() {
+        Weekday::Fri => [ymd(year, 12, 25), ymd(year, 12, 28)],
+        Weekday::Sat => [ymd(year, 12, 27), ymd(year, 12, 28)],
+        Weekday::Sun => [ymd(year, 12, 26), ymd(year, 12, 27)],
+        _ => [ymd(year, 12, 25), ymd(year, 12, 26)],
+    };
+
+    let holidays = [
+        new_year,
+        easter - Duration::days(2),
+        easter + Duration::days(1),
+        get_nth_weekday(year, 5, Weekday::Mon, 1),
+        get_last_weekday(year, 5, Weekday::Mon),
+        get_last_weekday(year, 8, Weekday::Mon),
+        christmas[0],
+        christmas[1],
+    ];
+
+    holidays.contains(&date)
+}
+
+/// London Stock Exchange 12:30 closes on Christmas Eve and New Year's Eve
+pub fn is_uk_market_early_close(date: NaiveDate) -> bool {
+    if is_weekend(date) || is_uk_market_holiday(date) {
+        return false;
+    }
+
+    (date.month() == 12 && date.day() == 24) || (date.month() == 12 && date.day() == 31)
+}
+
+/// XETRA (Frankfurt) holidays
+pub fn is_german_market_holiday(date: NaiveDate) -> bool {
+    let year = date.year();
+    let easter = calc_easter_sunday(year);
+
+    let holidays = [
+        ymd(year, 1, 1),
+        easter - Duration::days(2),
+        easter + Duration::days(1),
+        ymd(year, 5, 1),
+        ymd(year, 12, 24),
+        ymd(year, 12, 25),
+        ymd(year, 12, 26),
+        ymd(year, 12, 31),
+    ];
+
+    holidays.contains(&date)
+}
+
+/// Tokyo Stock Exchange holidays: year end holidays (Dec 31 - Jan 3) and Japanese
+/// national holidays by the current rules, including substitute holidays.
+pub fn is_japan_market_holiday(date: NaiveDate) -> bool {
+    if (date.month() == 1 && date.day() <= 3) || (date.month() == 12 && date.day() == 31) {
+        return true;
+    }
+
+    if is_japan_national_holiday(date) {
+        return true;
+    }
+
+    // Substitute holiday: the first non holiday after a holiday falling on Sunday
+    let mut prev = date - Duration::days(1);
+    while is_japan_national_holiday(prev) {
+        if prev.weekday() == Weekday::Sun {
+            return true;
+        }
+        prev -= Duration::days(1);
+    }
+
+    false
+}
+
+fn is_japan_national_holiday(date: NaiveDate) -> bool {
+    const FIXED: [(u32, u32); 10] = [
+        (1, 1),
+        (2, 11),
+        (2, 23),
+        (4, 29),
+        (5, 3),
+        (5, 4),
+        (5, 5),
+        (8, 11),
+        (11, 3),
+        (11, 23),
+    ];
+
+    if FIXED.contains(&(date.month(), date.day())) {
+        return true;
+    }
+
+    let year = date.year();
+
+    // Equinox approximation valid for 1980 - 2099
+    let shift = 0.242194 * (year - 1980) as f64 - ((year - 1980) / 4) as f64;
+    let vernal_equinox = ymd(year, 3, (20.8431 + shift).floor() as u32);
+    let autumnal_equinox = ymd(year, 9, (23.2488 + shift).floor() as u32);
+
+    let holidays = [
+        get_nth_weekday(year, 1, Weekday::Mon, 2),
+        get_nth_weekday(year, 7, Weekday::Mon, 3),
+        get_nth_weekday(year, 9, Weekday::Mon, 3),
+        get_nth_weekday(year, 10, Weekday::Mon, 2),
+        vernal_equinox,
+        autumnal_equinox,
+    ];
+
+    holidays.contains(&date)
+}
+
+/// US rule: holidays on Saturday are observed on Friday, on Sunday - on Monday
+fn observe_us(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easter() {
+        assert_eq!(calc_easter_sunday(2024), ymd(2024, 3, 31));
+        assert_eq!(calc_easter_sunday(2025), ymd(2025, 4, 20));
+        assert_eq!(calc_easter_sunday(2026), ymd(2026, 4, 5));
+    }
+
+    #[test]
+    fn us_holidays() {
+        assert!(is_us_market_holiday(ymd(2025, 1, 20))); // MLK day
+        assert!(is_us_market_holiday(ymd(2025, 4, 18))); // Good Friday
+        assert!(is_us_market_holiday(ymd(2025, 6, 19))); // Juneteenth
+        assert!(is_us_market_holiday(ymd(2025, 11, 27))); // Thanksgiving
+        assert!(is_us_market_holiday(ymd(2026, 7, 3))); // July 4th on Saturday
+        assert!(is_us_market_holiday(ymd(2023, 1, 2))); // New Year on Sunday
+        assert!(!is_us_market_holiday(ymd(2021, 12, 31))); // New Year on Saturday is not moved
+        assert!(!is_us_market_holiday(ymd(2025, 11, 28)));
+    }
+
+    #[test]
+    fn us_early_closes() {
+        assert!(is_us_market_early_close(ymd(2025, 11, 28)));
+        assert!(is_us_market_early_close(ymd(2025, 12, 24)));
+        assert!(is_us_market_early_close(ymd(2025, 7, 3)));
+        assert!(!is_us_market_early_close(ymd(2026, 7, 3)));
+        assert!(!is_us_market_early_close(ymd(2025, 12, 23)));
+    }
+
+    #[test]
+    fn uk_holidays() {
+        assert!(is_uk_market_holiday(ymd(2025, 4, 21))); // Easter Monday
+        assert!(is_uk_market_holiday(ymd(2025, 8, 25)));
+        assert!(is_uk_market_holiday(ymd(2027, 12, 27))); // Christmas on Saturday
+        assert!(is_uk_market_holiday(ymd(2027, 12, 28)));
+        assert!(!is_uk_market_holiday(ymd(2025, 12, 24)));
+        assert!(is_uk_market_early_close(ymd(2025, 12, 24)));
+    }
+
+    #[test]
+    fn japan_holidays() {
+        assert!(is_japan_market_holiday(ymd(2025, 1, 2)));
+        assert!(is_japan_market_holiday(ymd(2025, 1, 13))); // Coming of Age Day
+        assert!(is_japan_market_holiday(ymd(2025, 3, 20))); // Vernal equinox
+        assert!(is_japan_market_holiday(ymd(2025, 9, 23))); // Autumnal equinox
+        assert!(is_japan_market_holiday(ymd(2025, 11, 24))); // Substitute for Sunday Nov 23
+        assert!(is_japan_market_holiday(ymd(2025, 5, 6))); // Substitute for Sunday May 4
+        assert!(!is_japan_market_holiday(ymd(2025, 5, 7)));
+    }
+}
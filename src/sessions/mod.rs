@@ -0,0 +1,6 @@
+mod session_phase;
+pub use session_phase::*;
+mod holidays;
+pub use holidays::*;
+mod exchange;
+pub use exchange::*;
@@ -0,0 +1,19 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionPhase {
+    /// Outside of any session (nights, weekends)
+    Closed,
+    /// Trading day is an exchange holiday
+    Holiday,
+    PreMarket,
+    Regular,
+    /// Break inside a trading day (lunch break, daily maintenance)
+    Break,
+    PostMarket,
+}
+
+impl SessionPhase {
+    pub fn is_regular(&self) -> bool {
+        matches!(self, Self::Regular)
+    }
+}
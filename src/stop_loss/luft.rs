@@ -1,6 +1,7 @@
 use super::TechStopLoss;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Luft(f64);
 
 impl Luft {
@@ -1,6 +1,7 @@
 use crate::Atr;
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TechStopLoss(f64);
 
 impl TechStopLoss {
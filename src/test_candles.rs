@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use crate::candle::CandleInstance;
+
+pub const HISTORY_LEN: usize = 15;
+
+/// Candles keyed by their index with volume 1.0
+pub fn make_candles(prices: &[(f64, f64, f64, f64)]) -> BTreeMap<u64, CandleInstance> {
+    let prices: Vec<(f64, f64, f64, f64, f64)> = prices
+        .iter()
+        .map(|&(open, high, low, close)| (open, high, low, close, 1.0))
+        .collect();
+
+    make_candles_with_volume(&prices)
+}
+
+/// Candles keyed by their index from `(open, high, low, close, volume)`
+pub fn make_candles_with_volume(
+    prices: &[(f64, f64, f64, f64, f64)],
+) -> BTreeMap<u64, CandleInstance> {
+    prices
+        .iter()
+        .enumerate()
+        .map(|(i, &(open, high, low, close, volume))| {
+            let candle = CandleInstance {
+                time_key: i as u64,
+                open,
+                high,
+                low,
+                close,
+                volume,
+            };
+            (i as u64, candle)
+        })
+        .collect()
+}
+
+/// History of candles with range 1.0 and body 0.5 (ATR 1.0, average body 0.5) moving by `trend_step`
+/// per candle, followed by pattern candles. Pattern prices are offsets from the last history close
+pub fn make_trend_candles(
+    trend_step: f64,
+    pattern: &[(f64, f64, f64, f64)],
+) -> BTreeMap<u64, CandleInstance> {
+    let mut result = BTreeMap::new();
+
+    for i in 0..HISTORY_LEN {
+        let base = 100.0 + i as f64 * trend_step;
+        result.insert(
+            i as u64,
+            CandleInstance {
+                time_key: i as u64,
+                open: base - 0.25,
+                high: base + 0.5,
+                low: base - 0.5,
+                close: base + 0.25,
+                volume: 1.0,
+            },
+        );
+    }
+
+    let last_close = 100.0 + (HISTORY_LEN - 1) as f64 * trend_step + 0.25;
+
+    for (i, (open, high, low, close)) in pattern.iter().enumerate() {
+        let time_key = (HISTORY_LEN + i) as u64;
+        result.insert(
+            time_key,
+            CandleInstance {
+                time_key,
+                open: last_close + open,
+                high: last_close + high,
+                low: last_close + low,
+                close: last_close + close,
+                volume: 1.0,
+            },
+        );
+    }
+
+    result
+}
@@ -0,0 +1,108 @@
+use rust_extensions::chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc};
+use rust_extensions::date_time::DateTimeAsMicroseconds;
+
+/// How candle time keys are encoded. Formatted keys are in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeKeyFormat {
+    UnixSeconds,
+    UnixMilliseconds,
+    UnixMicroseconds,
+    /// yyyyMMddHHmm, e.g. `202504101930`
+    YearMonthDayHourMinute,
+    /// yyyyMMddHH, e.g. `2025041019`
+    YearMonthDayHour,
+    /// yyyyMMdd, e.g. `20250410`
+    YearMonthDay,
+}
+
+impl TimeKeyFormat {
+    /// `None` if the time key is not valid for the format
+    pub fn to_date_time(&self, time_key: u64) -> Option<DateTimeAsMicroseconds> {
+        let unix_microseconds = match self {
+            TimeKeyFormat::UnixSeconds => (time_key as i64).checked_mul(1_000_000)?,
+            TimeKeyFormat::UnixMilliseconds => (time_key as i64).checked_mul(1_000)?,
+            TimeKeyFormat::UnixMicroseconds => time_key as i64,
+            TimeKeyFormat::YearMonthDayHourMinute => {
+                parse_formatted(time_key / 10000, (time_key / 100) % 100, time_key % 100)?
+            }
+            TimeKeyFormat::YearMonthDayHour => parse_formatted(time_key / 100, time_key % 100, 0)?,
+            TimeKeyFormat::YearMonthDay => parse_formatted(time_key, 0, 0)?,
+        };
+
+        Some(DateTimeAsMicroseconds::new(unix_microseconds))
+    }
+
+    pub fn from_date_time(&self, dt: DateTimeAsMicroseconds) -> u64 {
+        match self {
+            TimeKeyFormat::UnixSeconds => (dt.unix_microseconds / 1_000_000) as u64,
+            TimeKeyFormat::UnixMilliseconds => (dt.unix_microseconds / 1_000) as u64,
+            TimeKeyFormat::UnixMicroseconds => dt.unix_microseconds as u64,
+            TimeKeyFormat::YearMonthDayHourMinute => {
+                let utc = dt.to_chrono_utc();
+                to_day_key(&utc) * 10000 + utc.hour() as u64 * 100 + utc.minute() as u64
+            }
+            TimeKeyFormat::YearMonthDayHour => {
+                let utc = dt.to_chrono_utc();
+                to_day_key(&utc) * 100 + utc.hour() as u64
+            }
+            TimeKeyFormat::YearMonthDay => to_day_key(&dt.to_chrono_utc()),
+        }
+    }
+}
+
+/// yyyyMMdd key of a date
+pub fn to_day_key(date: &impl Datelike) -> u64 {
+    date.year() as u64 * 10000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+fn parse_formatted(day_key: u64, hour: u64, minute: u64) -> Option<i64> {
+    let date = NaiveDate::from_ymd_opt(
+        (day_key / 10000) as i32,
+        ((day_key / 100) % 100) as u32,
+        (day_key % 100) as u32,
+    )?;
+    let date_time = date.and_hms_opt(hour as u32, minute as u32, 0)?;
+    let utc: DateTime<Utc> = Utc.from_utc_datetime(&date_time);
+
+    Some(utc.timestamp_micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatted_keys_round_trip() {
+        for (format, key) in [
+            (TimeKeyFormat::YearMonthDayHourMinute, 202504101930),
+            (TimeKeyFormat::YearMonthDayHour, 2025041019),
+            (TimeKeyFormat::YearMonthDay, 20250410),
+            (TimeKeyFormat::UnixSeconds, 1744313400),
+            (TimeKeyFormat::UnixMilliseconds, 1744313400000),
+        ] {
+            let dt = format.to_date_time(key).unwrap();
+            assert_eq!(format.from_date_time(dt), key);
+        }
+    }
+
+    #[test]
+    fn formatted_and_unix_keys_agree() {
+        let formatted = TimeKeyFormat::YearMonthDayHourMinute
+            .to_date_time(202504101930)
+            .unwrap();
+        let unix = TimeKeyFormat::UnixSeconds.to_date_time(1744313400).unwrap();
+
+        assert_eq!(formatted.unix_microseconds, unix.unix_microseconds);
+    }
+
+    #[test]
+    fn invalid_formatted_key() {
+        assert!(TimeKeyFormat::YearMonthDay.to_date_time(20251345).is_none());
+        assert!(
+            TimeKeyFormat::YearMonthDayHour
+                .to_date_time(2025041025)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,21 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Timeframe {
+    Minutes(u32),
+    Hours(u32),
+    Day,
+}
+
+impl Timeframe {
+    pub fn get_duration_seconds(&self) -> i64 {
+        match self {
+            Timeframe::Minutes(minutes) => *minutes as i64 * 60,
+            Timeframe::Hours(hours) => *hours as i64 * 60 * 60,
+            Timeframe::Day => 24 * 60 * 60,
+        }
+    }
+
+    pub fn get_duration_microseconds(&self) -> i64 {
+        self.get_duration_seconds() * 1_000_000
+    }
+}
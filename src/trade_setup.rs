@@ -0,0 +1,238 @@
+use crate::analyzer::SignalDirection;
+use crate::candle::Candle;
+use crate::patterns::level_bounce::BsuBpiIndex;
+use crate::stop_loss::{Luft, TechStopLoss};
+use crate::{Atr, HowCandleCrossesLevel, InstrumentSpec};
+
+pub const TS_DEFAULT_TAKE_PROFIT_R: [f64; 3] = [1.0, 2.0, 3.0];
+
+#[derive(Debug, Clone)]
+pub struct TradeSetupParams {
+    /// Take profit targets in risks (R)
+    pub take_profit_r: Vec<f64>,
+    /// Money risked per trade
+    pub account_risk: f64,
+    /// Prices are rounded to its tick size, position size to its lot step
+    pub spec: InstrumentSpec,
+}
+
+impl TradeSetupParams {
+    pub fn new(account_risk: f64, spec: impl Into<InstrumentSpec>) -> Self {
+        Self {
+            take_profit_r: TS_DEFAULT_TAKE_PROFIT_R.to_vec(),
+            account_risk,
+            spec: spec.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradeSetup {
+    pub direction: SignalDirection,
+    pub level: f64,
+    /// Limit order price: level + luft for longs, level - luft for shorts
+    pub entry_price: f64,
+    pub stop_price: f64,
+    pub take_profit_prices: Vec<f64>,
+    pub tech_stop_loss: TechStopLoss,
+    pub luft: Luft,
+    pub position_size: f64,
+    pub bsu_time_key: u64,
+    pub bpu_1_time_key: u64,
+    pub bpu_2_time_key: u64,
+}
+
+impl TradeSetup {
+    /// Builds a limit order setup from a BSU/BPU match found by [`crate::patterns::level_bounce::find_bpu_bsu`].
+    ///
+    /// Returns `None` if the direction does not fit the side BPU1 touched the level from
+    /// (a long needs a touch from above, a short from below), the direction is neutral
+    /// or the position size is zero.
+    pub fn from_bsu_bpu(
+        candles: &[impl Candle],
+        bsu_bpu: &BsuBpiIndex,
+        level: f64,
+        direction: SignalDirection,
+        day_atr: Atr,
+        params: &TradeSetupParams,
+    ) -> Option<Self> {
+        let bsu = candles.get(bsu_bpu.bsu_index)?;
+        let bpu_1 = candles.get(bsu_bpu.bpu_1_index)?;
+        let bpu_2 = candles.get(bsu_bpu.bpu_2_index)?;
+
+        let bpu_1_touch = HowCandleCrossesLevel::from_candle_and_level(bpu_1, level);
+
+        let sign = match direction {
+            SignalDirection::Bullish if bpu_1_touch.is_above_or_touches_above() => 1.0,
+            SignalDirection::Bearish if bpu_1_touch.is_below_or_touches_below() => -1.0,
+            _ => return None,
+        };
+
+        let tech_stop_loss = TechStopLoss::from_crypto_and_us_stock_day_atr(day_atr);
+        let luft = Luft::from(tech_stop_loss);
+
+        let spec = &params.spec;
+        let entry_price = spec.round_price(level + sign * luft.get_value());
+        let stop_price = spec.round_price(entry_price - sign * tech_stop_loss.get_value());
+        let risk = (entry_price - stop_price).abs();
+
+        let take_profit_prices = params
+            .take_profit_r
+            .iter()
+            .map(|r| spec.round_price(entry_price + sign * risk * r))
+            .collect();
+
+        let position_size = calc_position_size(params.account_risk, risk, spec);
+        if position_size <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            direction,
+            level,
+            entry_price,
+            stop_price,
+            take_profit_prices,
+            tech_stop_loss,
+            luft,
+            position_size,
+            bsu_time_key: bsu.get_time_key(),
+            bpu_1_time_key: bpu_1.get_time_key(),
+            bpu_2_time_key: bpu_2.get_time_key(),
+        })
+    }
+
+    pub fn get_risk_per_unit(&self) -> f64 {
+        (self.entry_price - self.stop_price).abs()
+    }
+}
+
+/// Position size risking `account_risk` with a stop `risk_per_unit` away, rounded down to the lot step
+pub fn calc_position_size(account_risk: f64, risk_per_unit: f64, spec: &InstrumentSpec) -> f64 {
+    let risk_per_lot = risk_per_unit * spec.contract_multiplier;
+    if risk_per_lot <= 0.0 {
+        return 0.0;
+    }
+
+    spec.round_volume(account_risk / risk_per_lot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::patterns::level_bounce::find_bpu_bsu;
+
+    fn make_candle(time_key: u64, high: f64, low: f64) -> CandleInstance {
+        CandleInstance {
+            time_key,
+            open: low,
+            high,
+            low,
+            close: high,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn short_setup_below_resistance() {
+        let candles = vec![
+            make_candle(1, 100.0, 98.0),
+            make_candle(2, 99.0, 97.0),
+            make_candle(3, 100.0, 98.5),
+            make_candle(4, 99.9, 98.0),
+        ];
+
+        let bsu_bpu = find_bpu_bsu(&candles, 100.0, 0.2.into()).unwrap();
+        let params = TradeSetupParams::new(100.0, 2);
+
+        let setup = TradeSetup::from_bsu_bpu(
+            &candles,
+            &bsu_bpu,
+            100.0,
+            SignalDirection::Bearish,
+            Atr::new(10.0),
+            &params,
+        )
+        .unwrap();
+
+        assert_eq!(setup.entry_price, 99.7);
+        assert_eq!(setup.stop_price, 101.2);
+        assert_eq!(setup.take_profit_prices, vec![98.2, 96.7, 95.2]);
+        assert_eq!(setup.position_size, 66.0);
+        assert_eq!(setup.bsu_time_key, 1);
+        assert_eq!(setup.bpu_1_time_key, 3);
+        assert_eq!(setup.bpu_2_time_key, 4);
+    }
+
+    #[test]
+    fn direction_must_match_bpu_side() {
+        let candles = vec![
+            make_candle(1, 100.0, 98.0),
+            make_candle(2, 99.0, 97.0),
+            make_candle(3, 100.0, 98.5),
+            make_candle(4, 99.9, 98.0),
+        ];
+
+        let bsu_bpu = find_bpu_bsu(&candles, 100.0, 0.2.into()).unwrap();
+        let params = TradeSetupParams::new(100.0, 2);
+
+        let setup = TradeSetup::from_bsu_bpu(
+            &candles,
+            &bsu_bpu,
+            100.0,
+            SignalDirection::Bullish,
+            Atr::new(10.0),
+            &params,
+        );
+
+        assert!(setup.is_none());
+    }
+
+    #[test]
+    fn long_setup_above_support() {
+        let candles = vec![
+            make_candle(1, 52.0, 50.0),
+            make_candle(2, 53.0, 51.0),
+            make_candle(3, 51.5, 50.0),
+            make_candle(4, 52.0, 50.05),
+        ];
+
+        let bsu_bpu = find_bpu_bsu(&candles, 50.0, 0.1.into()).unwrap();
+        let params = TradeSetupParams {
+            take_profit_r: vec![3.0],
+            ..TradeSetupParams::new(10.0, InstrumentSpec::crypto(2, 0.5))
+        };
+
+        let setup = TradeSetup::from_bsu_bpu(
+            &candles,
+            &bsu_bpu,
+            50.0,
+            SignalDirection::Bullish,
+            Atr::new(2.0),
+            &params,
+        )
+        .unwrap();
+
+        assert_eq!(setup.entry_price, 50.06);
+        assert_eq!(setup.stop_price, 49.76);
+        assert_eq!(setup.take_profit_prices, vec![50.96]);
+        assert_eq!(setup.position_size, 33.0);
+    }
+
+    #[test]
+    fn position_size_is_rounded_down_to_lot_step() {
+        let stock = InstrumentSpec::us_stock(2);
+        assert_eq!(calc_position_size(100.0, 3.0, &stock), 33.0);
+        assert_eq!(calc_position_size(1.0, 3.0, &stock), 0.0);
+        assert_eq!(calc_position_size(100.0, 0.0, &stock), 0.0);
+
+        let crypto = InstrumentSpec::crypto(2, 0.01);
+        assert_eq!(calc_position_size(100.0, 0.4, &crypto), 250.0);
+        assert_eq!(calc_position_size(100.0, 3.0, &crypto), 33.33);
+
+        let es = InstrumentSpec::future(2, 0.25, 50.0);
+        assert_eq!(calc_position_size(1000.0, 4.0, &es), 5.0);
+    }
+}
@@ -0,0 +1,6 @@
+mod relative_volume;
+pub use relative_volume::*;
+mod vwap;
+pub use vwap::*;
+mod volume_flags;
+pub use volume_flags::*;
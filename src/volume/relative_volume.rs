@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono_tz::Tz;
+use rust_extensions::chrono::NaiveTime;
+
+use crate::TimeKeyFormat;
+use crate::candle::Candle;
+
+pub const RELATIVE_VOLUME_DEFAULT_SESSIONS: usize = 10;
+
+/// Volume of a candle relative to the average volume at the same time of day over previous sessions.
+///
+/// Every session contributes the candle starting at that local time, so missing candles
+/// (holidays, early closes) simply do not count.
+#[derive(Debug, Clone)]
+pub struct RelativeVolume {
+    /// Number of previous sessions averaged
+    pub sessions: usize,
+    pub key_format: TimeKeyFormat,
+    /// Time zone the time of day is taken in
+    pub time_zone: Tz,
+}
+
+impl RelativeVolume {
+    pub fn new(key_format: TimeKeyFormat, time_zone: Tz) -> Self {
+        Self {
+            sessions: RELATIVE_VOLUME_DEFAULT_SESSIONS,
+            key_format,
+            time_zone,
+        }
+    }
+
+    /// Relative volume of every candle with `sessions` previous volumes at its time of day.
+    ///
+    /// Candles with time keys not valid for `key_format` or a zero average are skipped.
+    pub fn calculate<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> BTreeMap<u64, f64> {
+        let mut result = BTreeMap::new();
+        let mut history: BTreeMap<NaiveTime, VecDeque<f64>> = BTreeMap::new();
+
+        if self.sessions == 0 {
+            return result;
+        }
+
+        for (time_key, candle) in candles {
+            let Some(dt) = self.key_format.to_date_time(*time_key) else {
+                continue;
+            };
+
+            let time_of_day = dt.to_chrono_utc().with_timezone(&self.time_zone).time();
+            let volumes = history.entry(time_of_day).or_default();
+
+            if volumes.len() == self.sessions {
+                let average = volumes.iter().sum::<f64>() / self.sessions as f64;
+                if average > 0.0 {
+                    result.insert(*time_key, candle.get_volume() / average);
+                }
+                volumes.pop_front();
+            }
+
+            volumes.push_back(candle.get_volume());
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use chrono_tz::America::New_York;
+
+    /// 2024-01-02 09:30 New York
+    const SESSION_OPEN: u64 = 1704205800;
+    const DAY: u64 = 86400;
+
+    #[test]
+    fn compares_same_time_of_day() {
+        let volumes = [
+            (SESSION_OPEN, 100.0),
+            (SESSION_OPEN + 60, 1000.0),
+            (SESSION_OPEN + DAY, 200.0),
+            (SESSION_OPEN + DAY + 60, 1000.0),
+            (SESSION_OPEN + 2 * DAY, 300.0),
+            (SESSION_OPEN + 2 * DAY + 60, 500.0),
+        ];
+
+        let candles: BTreeMap<u64, CandleInstance> = volumes
+            .iter()
+            .map(|(time_key, volume)| {
+                let candle = CandleInstance {
+                    time_key: *time_key,
+                    open: 10.0,
+                    high: 11.0,
+                    low: 9.0,
+                    close: 10.5,
+                    volume: *volume,
+                };
+                (*time_key, candle)
+            })
+            .collect();
+
+        let relative_volume = RelativeVolume {
+            sessions: 2,
+            ..RelativeVolume::new(TimeKeyFormat::UnixSeconds, New_York)
+        };
+        let result = relative_volume.calculate(&candles);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[&(SESSION_OPEN + 2 * DAY)], 2.0);
+        assert_eq!(result[&(SESSION_OPEN + 2 * DAY + 60)], 0.5);
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use crate::candle::Candle;
+
+pub const VOLUME_DEFAULT_PERIOD: usize = 20;
+pub const VOLUME_DEFAULT_CLIMAX_RATIO: f64 = 2.5;
+pub const VOLUME_DEFAULT_DRY_UP_RATIO: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VolumeFlag {
+    /// Volume is at least `climax_ratio` times the average
+    Climax,
+    /// Volume is at most `dry_up_ratio` times the average
+    DryUp,
+}
+
+/// Flags unusual volume of the last candle against the average of `period` candles before it
+#[derive(Debug, Clone)]
+pub struct VolumeFlagDetector {
+    pub period: usize,
+    pub climax_ratio: f64,
+    pub dry_up_ratio: f64,
+}
+
+impl Default for VolumeFlagDetector {
+    fn default() -> Self {
+        Self {
+            period: VOLUME_DEFAULT_PERIOD,
+            climax_ratio: VOLUME_DEFAULT_CLIMAX_RATIO,
+            dry_up_ratio: VOLUME_DEFAULT_DRY_UP_RATIO,
+        }
+    }
+}
+
+impl VolumeFlagDetector {
+    /// Last volume to the average volume before it. `None` without enough candles or volume
+    pub fn get_volume_ratio<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<f64> {
+        let candle_vec: Vec<&T> = candles.values().rev().take(self.period + 1).collect();
+        self.calc_ratio(&candle_vec)
+    }
+
+    pub fn get_flag<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Option<VolumeFlag> {
+        self.to_flag(self.get_volume_ratio(candles)?)
+    }
+
+    pub fn is_climax<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> bool {
+        self.get_flag(candles) == Some(VolumeFlag::Climax)
+    }
+
+    pub fn is_dry_up<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> bool {
+        self.get_flag(candles) == Some(VolumeFlag::DryUp)
+    }
+
+    /// Flags of every candle in the history, oldest first. Only earlier candles are used for each one
+    pub fn scan<T: Candle>(&self, candles: &BTreeMap<u64, T>) -> Vec<(u64, VolumeFlag)> {
+        let candle_vec: Vec<&T> = candles.values().rev().collect();
+
+        candle_vec
+            .windows(self.period + 1)
+            .rev()
+            .filter_map(|window| {
+                let flag = self.to_flag(self.calc_ratio(window)?)?;
+                Some((window[0].get_time_key(), flag))
+            })
+            .collect()
+    }
+
+    /// `candles` are ordered from the newest one
+    fn calc_ratio<T: Candle>(&self, candles: &[&T]) -> Option<f64> {
+        if self.period == 0 || candles.len() < self.period + 1 {
+            return None;
+        }
+
+        let average = candles[1..].iter().map(|c| c.get_volume()).sum::<f64>() / self.period as f64;
+
+        if average <= 0.0 {
+            return None;
+        }
+
+        Some(candles[0].get_volume() / average)
+    }
+
+    fn to_flag(&self, ratio: f64) -> Option<VolumeFlag> {
+        if ratio >= self.climax_ratio {
+            Some(VolumeFlag::Climax)
+        } else if ratio <= self.dry_up_ratio {
+            Some(VolumeFlag::DryUp)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+    use crate::test_candles::make_candles_with_volume;
+
+    fn make_candles(volumes: &[f64]) -> BTreeMap<u64, CandleInstance> {
+        let prices: Vec<(f64, f64, f64, f64, f64)> = volumes
+            .iter()
+            .map(|volume| (10.0, 11.0, 9.0, 10.5, *volume))
+            .collect();
+
+        make_candles_with_volume(&prices)
+    }
+
+    fn make_detector() -> VolumeFlagDetector {
+        VolumeFlagDetector {
+            period: 4,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flags_climax_and_dry_up() {
+        let detector = make_detector();
+
+        assert!(detector.is_climax(&make_candles(&[10.0, 10.0, 10.0, 10.0, 30.0])));
+        assert!(detector.is_dry_up(&make_candles(&[10.0, 10.0, 10.0, 10.0, 4.0])));
+        assert_eq!(
+            detector.get_flag(&make_candles(&[10.0, 10.0, 10.0, 10.0, 12.0])),
+            None
+        );
+        assert_eq!(
+            detector.get_volume_ratio(&make_candles(&[10.0, 10.0, 10.0, 30.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn scan_flags_every_candle() {
+        let candles = make_candles(&[10.0, 10.0, 10.0, 10.0, 30.0, 2.0, 12.0]);
+
+        let flags = make_detector().scan(&candles);
+
+        assert_eq!(flags, vec![(4, VolumeFlag::Climax), (5, VolumeFlag::DryUp)]);
+    }
+}
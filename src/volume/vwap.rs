@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use chrono_tz::America::New_York;
+
+use crate::candle::Candle;
+use crate::{TimeKeyFormat, UsMarketMoment, to_day_key};
+
+/// Volume weighted average of the typical price `(high + low + close) / 3`
+/// with its volume weighted standard deviation
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VwapPoint {
+    pub vwap: f64,
+    pub std_dev: f64,
+}
+
+impl VwapPoint {
+    pub fn get_upper_band(&self, multiplier: f64) -> f64 {
+        self.vwap + self.std_dev * multiplier
+    }
+
+    pub fn get_lower_band(&self, multiplier: f64) -> f64 {
+        self.vwap - self.std_dev * multiplier
+    }
+}
+
+#[derive(Default)]
+struct VwapAccumulator {
+    volume: f64,
+    price_volume: f64,
+    squared_price_volume: f64,
+}
+
+impl VwapAccumulator {
+    fn add(&mut self, candle: &impl Candle) {
+        let price = (candle.get_high() + candle.get_low() + candle.get_close()) / 3.0;
+        let volume = candle.get_volume();
+
+        self.volume += volume;
+        self.price_volume += price * volume;
+        self.squared_price_volume += price * price * volume;
+    }
+
+    /// `None` until some volume is accumulated
+    fn get_point(&self) -> Option<VwapPoint> {
+        if self.volume <= 0.0 {
+            return None;
+        }
+
+        let vwap = self.price_volume / self.volume;
+        let variance = self.squared_price_volume / self.volume - vwap * vwap;
+
+        Some(VwapPoint {
+            vwap,
+            std_dev: variance.max(0.0).sqrt(),
+        })
+    }
+}
+
+/// VWAP of the US regular session (see [`UsMarketMoment`]) after every regular hours candle.
+///
+/// Resets on the first candle of each New York trading day. Candles outside regular hours,
+/// with time keys not valid for `key_format` or before any volume is traded get no value.
+pub fn calc_session_vwap<T: Candle>(
+    candles: &BTreeMap<u64, T>,
+    key_format: TimeKeyFormat,
+) -> BTreeMap<u64, VwapPoint> {
+    let mut result = BTreeMap::new();
+    let mut accumulator = VwapAccumulator::default();
+    let mut session_day = None;
+
+    for (time_key, candle) in candles {
+        let Some(dt) = key_format.to_date_time(*time_key) else {
+            continue;
+        };
+
+        if !UsMarketMoment::from(dt).is_working() {
+            continue;
+        }
+
+        let day_key = to_day_key(&dt.to_chrono_utc().with_timezone(&New_York));
+        if session_day != Some(day_key) {
+            session_day = Some(day_key);
+            accumulator = VwapAccumulator::default();
+        }
+
+        accumulator.add(candle);
+
+        if let Some(point) = accumulator.get_point() {
+            result.insert(*time_key, point);
+        }
+    }
+
+    result
+}
+
+/// VWAP accumulated from the `anchor_time_key` candle (e.g. a BSU) without session resets
+pub fn calc_anchored_vwap<T: Candle>(
+    candles: &BTreeMap<u64, T>,
+    anchor_time_key: u64,
+) -> BTreeMap<u64, VwapPoint> {
+    let mut result = BTreeMap::new();
+    let mut accumulator = VwapAccumulator::default();
+
+    for (time_key, candle) in candles.range(anchor_time_key..) {
+        accumulator.add(candle);
+
+        if let Some(point) = accumulator.get_point() {
+            result.insert(*time_key, point);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candle::CandleInstance;
+
+    /// 2024-01-02 09:30 New York
+    const SESSION_OPEN: u64 = 1704205800;
+    const DAY: u64 = 86400;
+
+    fn make_candle(
+        time_key: u64,
+        high: f64,
+        low: f64,
+        close: f64,
+        volume: f64,
+    ) -> (u64, CandleInstance) {
+        (
+            time_key,
+            CandleInstance {
+                time_key,
+                open: close,
+                high,
+                low,
+                close,
+                volume,
+            },
+        )
+    }
+
+    #[test]
+    fn session_vwap_resets_every_day() {
+        let candles: BTreeMap<u64, CandleInstance> = [
+            // Pre-market is skipped
+            make_candle(SESSION_OPEN - 5400, 50.0, 40.0, 45.0, 1000.0),
+            make_candle(SESSION_OPEN, 11.0, 9.0, 10.0, 100.0),
+            make_candle(SESSION_OPEN + 60, 14.0, 12.0, 13.0, 300.0),
+            make_candle(SESSION_OPEN + DAY, 21.0, 19.0, 20.0, 50.0),
+        ]
+        .into_iter()
+        .collect();
+
+        let vwap = calc_session_vwap(&candles, TimeKeyFormat::UnixSeconds);
+
+        assert_eq!(vwap.len(), 3);
+        assert_eq!(vwap[&SESSION_OPEN].vwap, 10.0);
+
+        let point = vwap[&(SESSION_OPEN + 60)];
+        assert!((point.vwap - 12.25).abs() < 1e-9);
+        assert!((point.std_dev - 1.6875f64.sqrt()).abs() < 1e-9);
+        assert!((point.get_upper_band(2.0) - (12.25 + 2.0 * 1.6875f64.sqrt())).abs() < 1e-9);
+
+        assert_eq!(
+            vwap[&(SESSION_OPEN + DAY)],
+            VwapPoint {
+                vwap: 20.0,
+                std_dev: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn anchored_vwap_starts_at_anchor() {
+        let candles: BTreeMap<u64, CandleInstance> = [
+            make_candle(1, 31.0, 29.0, 30.0, 100.0),
+            make_candle(2, 11.0, 9.0, 10.0, 100.0),
+            make_candle(3, 14.0, 12.0, 13.0, 300.0),
+        ]
+        .into_iter()
+        .collect();
+
+        let vwap = calc_anchored_vwap(&candles, 2);
+
+        assert_eq!(vwap.keys().copied().collect::<Vec<_>>(), vec![2, 3]);
+        assert!((vwap[&3].vwap - 12.25).abs() < 1e-9);
+        assert!((vwap[&3].get_lower_band(1.0) - (12.25 - 1.6875f64.sqrt())).abs() < 1e-9);
+    }
+}